@@ -0,0 +1,258 @@
+//! IP allowlist for ingestion endpoints.
+//!
+//! Restricts which networks may submit logs, based on CIDR ranges from
+//! `INGEST_IP_ALLOWLIST`.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// A network in CIDR notation, e.g. `10.0.0.0/8` or `fd00::/8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// Parse `addr/prefix`; a bare address matches only itself
+    pub fn parse(s: &str) -> Result<Self, AllowlistError> {
+        let invalid = || AllowlistError::InvalidNetwork(s.to_string());
+
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.trim().parse().map_err(|_| invalid())?,
+            None => max_prefix,
+        };
+
+        if prefix > max_prefix {
+            return Err(invalid());
+        }
+
+        Ok(Self { addr, prefix })
+    }
+
+    /// Whether `ip` falls inside this network
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(u32::from(net).into(), u32::from(ip).into(), 32, self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(net), u128::from(ip), 128, self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compare the top `prefix` bits of two addresses `width` bits wide
+fn prefix_matches(net: u128, ip: u128, width: u8, prefix: u8) -> bool {
+    if prefix == 0 {
+        return true;
+    }
+    let shift = width - prefix;
+    net >> shift == ip >> shift
+}
+
+/// Networks allowed to reach ingestion routes
+#[derive(Debug, Clone, Default)]
+pub struct IpAllowlist {
+    networks: Vec<IpNetwork>,
+    /// Take the client address from `X-Forwarded-For` (set by our proxy)
+    trust_forwarded_for: bool,
+}
+
+impl IpAllowlist {
+    /// Parse a list of CIDR ranges; an empty list allows everyone
+    pub fn parse(ranges: &[String], trust_forwarded_for: bool) -> Result<Self, AllowlistError> {
+        let networks = ranges
+            .iter()
+            .map(|range| IpNetwork::parse(range))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            networks,
+            trust_forwarded_for,
+        })
+    }
+
+    /// Whether any restriction is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.networks.is_empty()
+    }
+
+    /// Whether `ip` may submit logs
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        !self.is_enabled() || self.networks.iter().any(|net| net.contains(ip))
+    }
+
+    /// Address the request originated from
+    ///
+    /// With a trusted proxy, this is the last `X-Forwarded-For` hop (the one the
+    /// proxy appended); earlier hops are client-controlled and ignored.
+    pub fn client_ip(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let forwarded = headers
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .last()
+                .and_then(|hop| hop.trim().parse().ok());
+
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+
+        peer
+    }
+}
+
+/// Client address resolved by the ingest guard, available as a request extension
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+/// Middleware rejecting ingestion requests from outside the allowlist
+///
+/// Also records the resolved [`ClientIp`] for the handler.
+pub async fn enforce_ingest_allowlist(
+    State(allowlist): State<Arc<IpAllowlist>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let client = allowlist.client_ip(peer, request.headers());
+    request.extensions_mut().insert(ClientIp(client));
+
+    if !allowlist.is_enabled() {
+        return next.run(request).await;
+    }
+
+    match client {
+        Some(ip) if allowlist.allows(ip) => next.run(request).await,
+        client => {
+            tracing::warn!(client = ?client, "Rejected ingestion from address outside allowlist");
+            (
+                StatusCode::FORBIDDEN,
+                "Source address is not allowed to submit logs",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Allowlist errors
+#[derive(Debug, thiserror::Error)]
+pub enum AllowlistError {
+    #[error("Invalid network '{0}' (expected CIDR like 10.0.0.0/8)")]
+    InvalidNetwork(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::post, Router};
+    use tower::ServiceExt;
+
+    fn allowlist(ranges: &[&str], trust_forwarded_for: bool) -> IpAllowlist {
+        let ranges: Vec<String> = ranges.iter().map(|r| r.to_string()).collect();
+        IpAllowlist::parse(&ranges, trust_forwarded_for).unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_matching() {
+        let net = IpNetwork::parse("10.1.0.0/16").unwrap();
+        assert!(net.contains(ip("10.1.200.3")));
+        assert!(!net.contains(ip("10.2.0.1")));
+        assert!(net.contains(ip("::ffff:10.1.0.9")));
+
+        let net = IpNetwork::parse("fd00::/8").unwrap();
+        assert!(net.contains(ip("fd12:3456::1")));
+        assert!(!net.contains(ip("fe80::1")));
+        assert!(!net.contains(ip("10.1.0.1")));
+
+        assert!(IpNetwork::parse("0.0.0.0/0")
+            .unwrap()
+            .contains(ip("8.8.8.8")));
+        assert!(IpNetwork::parse("192.168.1.7")
+            .unwrap()
+            .contains(ip("192.168.1.7")));
+    }
+
+    #[test]
+    fn test_invalid_networks_rejected() {
+        for bad in ["10.0.0.0/33", "fd00::/129", "not-an-ip/8", "10.0.0.0/x"] {
+            assert!(IpNetwork::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_allowed_and_blocked_sources() {
+        let list = allowlist(&["192.168.0.0/24", "10.0.0.0/8"], false);
+        assert!(list.allows(ip("192.168.0.42")));
+        assert!(list.allows(ip("10.20.30.40")));
+        assert!(!list.allows(ip("192.168.1.1")));
+        assert!(!list.allows(ip("203.0.113.5")));
+
+        assert!(IpAllowlist::default().allows(ip("203.0.113.5")));
+    }
+
+    #[test]
+    fn test_forwarded_for_only_when_trusted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.2.3.4, 10.0.0.5".parse().unwrap());
+        let peer = Some(ip("127.0.0.1"));
+
+        let untrusted = allowlist(&["10.0.0.0/8"], false);
+        assert_eq!(untrusted.client_ip(peer, &headers), peer);
+
+        let trusted = allowlist(&["10.0.0.0/8"], true);
+        assert_eq!(trusted.client_ip(peer, &headers), Some(ip("10.0.0.5")));
+        assert_eq!(trusted.client_ip(peer, &HeaderMap::new()), peer);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_returns_forbidden() {
+        let list = Arc::new(allowlist(&["10.0.0.0/8"], false));
+        let app = Router::new().route(
+            "/logs",
+            post(|| async { StatusCode::CREATED }).route_layer(middleware::from_fn_with_state(
+                list,
+                enforce_ingest_allowlist,
+            )),
+        );
+
+        let request = |peer: &str| {
+            let mut request = Request::post("/logs").body(Body::empty()).unwrap();
+            let addr: SocketAddr = peer.parse().unwrap();
+            request.extensions_mut().insert(ConnectInfo(addr));
+            request
+        };
+
+        let response = app.clone().oneshot(request("10.1.2.3:5000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app.oneshot(request("203.0.113.5:5000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,361 @@
+//! Append-only archive of received log entries.
+//!
+//! Writes every ingested entry to daily JSONL files so logs survive restarts,
+//! optionally partitioned into one directory per source. Finished days can be
+//! gzipped on a blocking thread when the archive rotates to a new day.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::{write::GzEncoder, Compression};
+use parking_lot::Mutex;
+
+use crate::models::{LogEntry, LogLevel};
+use crate::storage::sanitize_filename;
+
+/// Directory name used when an entry's source sanitizes to nothing
+const UNKNOWN_SOURCE: &str = "unknown";
+
+/// Date format of archive file names
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+/// Appends entries to `{dir}/{date}.jsonl` or `{dir}/{source}/{date}.jsonl`
+pub struct ArchiveSink {
+    dir: PathBuf,
+    by_source: bool,
+    min_level: LogLevel,
+    compress: bool,
+    /// Day of the last write; the lock also serializes writes so concurrent
+    /// appends don't interleave lines
+    last_day: Mutex<Option<NaiveDate>>,
+    /// Held while compressing so overlapping rotations don't gzip a file twice
+    compressing: Arc<Mutex<()>>,
+}
+
+impl ArchiveSink {
+    /// Create a sink writing into `dir`
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            by_source: false,
+            min_level: LogLevel::Trace,
+            compress: false,
+            last_day: Mutex::new(None),
+            compressing: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Gzip earlier days' files when the archive rotates to a new day
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Partition archive files by the entry's source
+    pub fn with_source_partitioning(mut self, by_source: bool) -> Self {
+        self.by_source = by_source;
+        self
+    }
+
+    /// Only archive entries at or above `min_level`
+    pub fn with_min_level(mut self, min_level: LogLevel) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Append an entry to today's archive file
+    ///
+    /// Entries below the minimum level are skipped. The first write of a new
+    /// day starts compressing earlier days in the background; must be called
+    /// from within a Tokio runtime when compression is on.
+    pub fn append(&self, entry: &LogEntry) -> Result<(), ArchiveError> {
+        if let Some(today) = self.append_at(entry, Utc::now())? {
+            self.compress_in_background(today);
+        }
+        Ok(())
+    }
+
+    /// Write `entry` to its archive file, returning the new day if this write
+    /// rotated the archive and earlier days should be compressed
+    fn append_at(
+        &self,
+        entry: &LogEntry,
+        now: DateTime<Utc>,
+    ) -> Result<Option<NaiveDate>, ArchiveError> {
+        if LogLevel::from_str(&entry.level) < self.min_level {
+            return Ok(None);
+        }
+
+        let mut line =
+            serde_json::to_string(entry).map_err(|e| ArchiveError::Serialization(e.to_string()))?;
+        line.push('\n');
+
+        let mut last_day = self.last_day.lock();
+
+        // An entry stamped just before midnight that lost the race for the
+        // lock goes in the new day's file, not one that may be mid-compression
+        let today = last_day.map_or(now.date_naive(), |day| day.max(now.date_naive()));
+
+        // The first write after startup counts as a rotation too, which picks
+        // up files left plaintext by a restart
+        let rotated = (self.compress && *last_day != Some(today)).then_some(today);
+        *last_day = Some(today);
+
+        let path = self.path_for(entry, today);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| io_error(&path, e))?;
+
+        Ok(rotated)
+    }
+
+    /// Gzip the files dated before `today` on a blocking thread, so a day
+    /// rollover doesn't hold up the ingest request that triggered it
+    fn compress_in_background(&self, today: NaiveDate) {
+        let dir = self.dir.clone();
+        let by_source = self.by_source;
+        let compressing = self.compressing.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let _compressing = compressing.lock();
+            if let Err(e) = compress_before(&dir, by_source, today) {
+                tracing::warn!(error = %e, "Failed to compress archive files");
+            }
+        });
+    }
+
+    /// Archive file for an entry written on `day`
+    fn path_for(&self, entry: &LogEntry, day: NaiveDate) -> PathBuf {
+        let file_name = format!("{}.jsonl", day.format(DAY_FORMAT));
+
+        if !self.by_source {
+            return self.dir.join(file_name);
+        }
+
+        let source = sanitize_filename(&entry.source);
+        let source = if source.is_empty() {
+            UNKNOWN_SOURCE
+        } else {
+            &source
+        };
+        self.dir.join(source).join(file_name)
+    }
+}
+
+/// Gzip every plaintext archive file in `dir` dated before `today`
+fn compress_before(dir: &Path, by_source: bool, today: NaiveDate) -> Result<usize, ArchiveError> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let mut dirs = vec![dir.to_path_buf()];
+    if by_source {
+        let sources = fs::read_dir(dir).map_err(|e| io_error(dir, e))?;
+        dirs.extend(
+            sources
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir()),
+        );
+    }
+
+    let mut compressed = 0;
+    for dir in dirs {
+        let files = fs::read_dir(&dir).map_err(|e| io_error(&dir, e))?;
+        for path in files.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            let day = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".jsonl"))
+                .and_then(|stem| NaiveDate::parse_from_str(stem, DAY_FORMAT).ok());
+
+            if day.is_some_and(|day| day < today) {
+                gzip_file(&path)?;
+                compressed += 1;
+            }
+        }
+    }
+
+    if compressed > 0 {
+        tracing::info!(files = compressed, "Compressed archive files");
+    }
+    Ok(compressed)
+}
+
+/// Compress `{day}.jsonl` into `{day}.jsonl.gz` and remove the plaintext
+///
+/// An existing `.gz` is appended to as another gzip member, which readers
+/// decompress as one stream.
+fn gzip_file(path: &Path) -> Result<(), ArchiveError> {
+    let gz_path = path.with_extension("jsonl.gz");
+
+    let mut input = File::open(path).map_err(|e| io_error(path, e))?;
+    let output = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&gz_path)
+        .map_err(|e| io_error(&gz_path, e))?;
+
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| io_error(&gz_path, e))?;
+
+    fs::remove_file(path).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> ArchiveError {
+    ArchiveError::Io(format!("{}: {}", path.display(), e))
+}
+
+/// Archive errors
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("IO error: {0}")]
+    Io(String),
+
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+    use std::io::Read;
+
+    fn create_entry(id: &str, source: &str) -> LogEntry {
+        LogEntry {
+            id: id.to_string(),
+            timestamp: Utc::now(),
+            level: "info".to_string(),
+            message: format!("Message {}", id),
+            user_id: None,
+            device_id: "test-device".to_string(),
+            source: source.to_string(),
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            file: String::new(),
+            function: String::new(),
+            line: 0,
+        }
+    }
+
+    fn read_ids(path: &Path) -> Vec<String> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<LogEntry>(line).unwrap().id)
+            .collect()
+    }
+
+    #[test]
+    fn test_daily_archive_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ArchiveSink::new(dir.path().to_path_buf());
+        let now = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+
+        sink.append_at(&create_entry("1", "ios-device"), now)
+            .unwrap();
+        sink.append_at(&create_entry("2", "ios-simulator"), now)
+            .unwrap();
+
+        assert_eq!(read_ids(&dir.path().join("2024-01-15.jsonl")), ["1", "2"]);
+    }
+
+    #[test]
+    fn test_source_partitioned_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ArchiveSink::new(dir.path().to_path_buf()).with_source_partitioning(true);
+        let now = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+
+        sink.append_at(&create_entry("1", "ios-device"), now)
+            .unwrap();
+        sink.append_at(&create_entry("2", "ios-simulator"), now)
+            .unwrap();
+        sink.append_at(&create_entry("3", "../etc"), now).unwrap();
+
+        let day = "2024-01-15.jsonl";
+        assert_eq!(read_ids(&dir.path().join("ios-device").join(day)), ["1"]);
+        assert_eq!(read_ids(&dir.path().join("ios-simulator").join(day)), ["2"]);
+        assert_eq!(read_ids(&dir.path().join("etc").join(day)), ["3"]);
+    }
+
+    #[test]
+    fn test_previous_day_compressed_on_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ArchiveSink::new(dir.path().to_path_buf())
+            .with_source_partitioning(true)
+            .with_compression(true);
+        let day1 = Utc.with_ymd_and_hms(2024, 1, 15, 23, 59, 0).unwrap();
+        let day2 = Utc.with_ymd_and_hms(2024, 1, 16, 0, 1, 0).unwrap();
+
+        let first = sink.append_at(&create_entry("1", "ios"), day1).unwrap();
+        assert_eq!(first, Some(day1.date_naive()));
+        let same_day = sink.append_at(&create_entry("2", "ios"), day1).unwrap();
+        assert_eq!(same_day, None);
+        let rotated = sink.append_at(&create_entry("3", "ios"), day2).unwrap();
+        assert_eq!(rotated, Some(day2.date_naive()));
+
+        // Writing never compresses; that's left to the background job
+        let source_dir = dir.path().join("ios");
+        assert!(source_dir.join("2024-01-15.jsonl").exists());
+        let compressed = compress_before(dir.path(), true, day2.date_naive()).unwrap();
+        assert_eq!(compressed, 1);
+
+        assert!(!source_dir.join("2024-01-15.jsonl").exists());
+        assert_eq!(read_ids(&source_dir.join("2024-01-16.jsonl")), ["3"]);
+
+        let mut archived = String::new();
+        let gz = File::open(source_dir.join("2024-01-15.jsonl.gz")).unwrap();
+        flate2::read::MultiGzDecoder::new(gz)
+            .read_to_string(&mut archived)
+            .unwrap();
+        let ids: Vec<String> = archived
+            .lines()
+            .map(|line| serde_json::from_str::<LogEntry>(line).unwrap().id)
+            .collect();
+        assert_eq!(ids, ["1", "2"]);
+    }
+
+    #[test]
+    fn test_late_entry_goes_to_current_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ArchiveSink::new(dir.path().to_path_buf()).with_compression(true);
+        let day1 = Utc.with_ymd_and_hms(2024, 1, 15, 23, 59, 59).unwrap();
+        let day2 = Utc.with_ymd_and_hms(2024, 1, 16, 0, 0, 1).unwrap();
+
+        sink.append_at(&create_entry("1", "ios"), day2).unwrap();
+        let late = sink.append_at(&create_entry("2", "ios"), day1).unwrap();
+        assert_eq!(late, None);
+
+        assert_eq!(read_ids(&dir.path().join("2024-01-16.jsonl")), ["1", "2"]);
+        assert!(!dir.path().join("2024-01-15.jsonl").exists());
+    }
+
+    #[test]
+    fn test_no_compression_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = ArchiveSink::new(dir.path().to_path_buf());
+
+        let day1 = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        sink.append_at(&create_entry("1", "ios"), day1).unwrap();
+        let rotated = sink
+            .append_at(&create_entry("2", "ios"), day1 + chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(rotated, None);
+
+        assert_eq!(read_ids(&dir.path().join("2024-01-15.jsonl")), ["1"]);
+        assert!(!dir.path().join("2024-01-15.jsonl.gz").exists());
+    }
+}
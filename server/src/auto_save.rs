@@ -0,0 +1,161 @@
+//! Saves the buffer to timestamped files as it nears capacity, so entries are
+//! kept on disk before they would be evicted.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::buffer::{self, LogBuffer};
+use crate::models::LogEntry;
+use crate::AppState;
+
+/// Directory inside the upload directory that receives auto-saves
+pub const AUTO_SAVE_DIR: &str = "auto-saves";
+
+/// How often the monitor checks the buffer
+const CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// Tracks what has already been saved so each file holds only new entries
+pub struct AutoSaver {
+    dir: PathBuf,
+    /// Unsaved entries needed to trigger a save
+    threshold: usize,
+    /// Id of the newest entry already saved
+    last_saved_id: Option<String>,
+}
+
+impl AutoSaver {
+    /// Save once unsaved entries reach `fraction` of `capacity`
+    pub fn new(dir: PathBuf, capacity: usize, fraction: f64) -> Self {
+        let threshold = (capacity as f64 * fraction).ceil().max(1.0) as usize;
+        Self {
+            dir,
+            threshold,
+            last_saved_id: None,
+        }
+    }
+
+    /// Save the unsaved entries if there are enough of them, returning the file written
+    pub fn check(&mut self, buffer: &LogBuffer, now: DateTime<Utc>) -> io::Result<Option<PathBuf>> {
+        let entries = buffer.get_all();
+        let unsaved = unsaved_entries(&entries, self.last_saved_id.as_deref());
+        let Some(newest) = unsaved.last() else {
+            return Ok(None);
+        };
+        if unsaved.len() < self.threshold {
+            return Ok(None);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self
+            .dir
+            .join(format!("buffer-{}.jsonl", now.format("%Y%m%dT%H%M%S%.3fZ")));
+        buffer::write_jsonl(&path, unsaved)?;
+
+        self.last_saved_id = Some(newest.id.clone());
+        Ok(Some(path))
+    }
+}
+
+/// Entries after the last saved one (all of them if it has left the buffer)
+fn unsaved_entries<'a>(entries: &'a [LogEntry], last_saved_id: Option<&str>) -> &'a [LogEntry] {
+    let saved = last_saved_id
+        .and_then(|id| entries.iter().rposition(|entry| entry.id == id))
+        .map_or(0, |index| index + 1);
+    &entries[saved..]
+}
+
+/// Check the buffer every second until the process exits
+pub async fn run(state: Arc<AppState>, mut saver: AutoSaver) {
+    let mut ticker = tokio::time::interval(CHECK_PERIOD);
+    loop {
+        ticker.tick().await;
+
+        match saver.check(&state.buffer, Utc::now()) {
+            Ok(Some(path)) => tracing::info!(path = %path.display(), "Auto-saved buffer"),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "Failed to auto-save buffer"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+
+    fn create_entry(id: &str) -> LogEntry {
+        LogEntry {
+            id: id.to_string(),
+            timestamp: Utc::now(),
+            level: "info".to_string(),
+            message: "hello".to_string(),
+            user_id: None,
+            device_id: "device-1".to_string(),
+            source: "test".to_string(),
+            metadata: HashMap::new(),
+            tags: vec![],
+            file: String::new(),
+            function: String::new(),
+            line: 0,
+        }
+    }
+
+    fn saved_ids(path: &std::path::Path) -> Vec<String> {
+        let restored = LogBuffer::new(100);
+        restored.load_from_file(path).unwrap();
+        restored.get_all().into_iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn test_saves_when_threshold_reached() {
+        let dir = tempfile::tempdir().unwrap();
+        let buffer = LogBuffer::new(10);
+        let mut saver = AutoSaver::new(dir.path().join(AUTO_SAVE_DIR), 10, 0.8);
+        let now = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+
+        for i in 0..7 {
+            buffer.append(create_entry(&i.to_string()));
+        }
+        assert_eq!(saver.check(&buffer, now).unwrap(), None);
+
+        buffer.append(create_entry("7"));
+        let path = saver.check(&buffer, now).unwrap().unwrap();
+        assert_eq!(
+            path.file_name().unwrap(),
+            "buffer-20240115T093000.000Z.jsonl"
+        );
+        assert_eq!(saved_ids(&path).len(), 8);
+    }
+
+    #[test]
+    fn test_next_save_holds_only_new_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let buffer = LogBuffer::new(4);
+        let mut saver = AutoSaver::new(dir.path().to_path_buf(), 4, 0.5);
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+
+        for id in ["a", "b"] {
+            buffer.append(create_entry(id));
+        }
+        let first = saver.check(&buffer, start).unwrap().unwrap();
+        assert_eq!(saved_ids(&first), ["a", "b"]);
+
+        // Already saved entries don't trigger again
+        buffer.append(create_entry("c"));
+        assert_eq!(saver.check(&buffer, start).unwrap(), None);
+
+        buffer.append(create_entry("d"));
+        buffer.append(create_entry("e"));
+        let second = saver
+            .check(&buffer, start + chrono::Duration::seconds(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved_ids(&second), ["c", "d", "e"]);
+    }
+}
@@ -2,24 +2,117 @@
 //!
 //! Uses parking_lot::RwLock for better performance than std::sync::RwLock.
 
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
 use parking_lot::RwLock;
+use serde::Serialize;
 use tokio::sync::broadcast;
+use uuid::Uuid;
 
 use crate::models::{LogEntry, LogLevel};
 
+/// Metadata key giving an entry's time-to-live in seconds
+pub const TTL_METADATA_KEY: &str = "ttlSeconds";
+
+/// Metadata key counting the repeats collapsed into an entry in dedup mode
+pub const DEDUP_COUNT_METADATA_KEY: &str = "count";
+
 /// Thread-safe circular buffer for log entries
 pub struct LogBuffer {
     inner: RwLock<BufferInner>,
-    broadcast_tx: broadcast::Sender<LogEntry>,
+    broadcast_tx: broadcast::Sender<BufferEvent>,
+}
+
+/// Notification sent to live stream subscribers
+#[derive(Debug, Clone)]
+pub enum BufferEvent {
+    /// A new entry was appended
+    Log(Box<LogEntry>),
+    /// All entries were removed
+    Clear,
+    /// A device uploaded logs for a request
+    Upload(UploadNotice),
+}
+
+/// Sent to live stream subscribers when a device's upload is stored
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UploadNotice {
+    #[serde(rename = "requestId")]
+    pub request_id: Uuid,
+
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+
+    /// Number of entries uploaded
+    pub count: usize,
+}
+
+/// A buffered entry with its optional expiry
+struct BufferedEntry {
+    entry: LogEntry,
+    expires_at: Option<Instant>,
+    /// Position in arrival order; never reused, even after eviction or clear
+    seq: u64,
 }
 
 struct BufferInner {
-    entries: Vec<LogEntry>,
+    entries: VecDeque<BufferedEntry>,
     capacity: usize,
-    start_index: usize,
-    count: usize,
+    /// Byte budget; when set, replaces the entry-count cap
+    max_bytes: Option<usize>,
+    /// Approximate serialized size of buffered entries
+    total_bytes: usize,
     min_level: LogLevel,
     source_filter: Option<Vec<String>>,
+    high_water_mark: usize,
+    evictions: u64,
+    /// Number of buffered entries carrying a TTL
+    ttl_entries: usize,
+    /// Sequence number for the next appended entry
+    next_seq: u64,
+    /// Collapse consecutive repeats into the last entry
+    dedup: bool,
+}
+
+/// A page of entries read with [`LogBuffer::chunk_from`]
+#[derive(Debug)]
+pub struct BufferChunk {
+    pub entries: Vec<LogEntry>,
+
+    /// Sequence number to pass as `from` for the following page
+    pub next_seq: u64,
+
+    /// Whether more entries were already buffered past this page
+    pub has_more: bool,
+}
+
+/// Occupancy counters for the buffer
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferStats {
+    /// Current number of entries
+    pub count: usize,
+
+    /// Maximum number of entries
+    pub capacity: usize,
+
+    /// Highest entry count observed since startup
+    #[serde(rename = "highWaterMark")]
+    pub high_water_mark: usize,
+
+    /// Entries dropped because the buffer was full
+    pub evictions: u64,
+
+    /// Approximate serialized size of buffered entries
+    #[serde(rename = "approxBytes")]
+    pub approx_bytes: usize,
+
+    /// Byte budget, if the buffer is memory-bounded
+    #[serde(rename = "maxBytes", skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
 }
 
 impl LogBuffer {
@@ -30,66 +123,160 @@ impl LogBuffer {
 
         Self {
             inner: RwLock::new(BufferInner {
-                entries: Vec::with_capacity(capacity),
+                entries: VecDeque::with_capacity(capacity),
                 capacity,
-                start_index: 0,
-                count: 0,
+                max_bytes: None,
+                total_bytes: 0,
                 min_level: LogLevel::Trace,
                 source_filter: None,
+                high_water_mark: 0,
+                evictions: 0,
+                ttl_entries: 0,
+                next_seq: 0,
+                dedup: false,
             }),
             broadcast_tx,
         }
     }
 
-    /// Subscribe to new log entry notifications
-    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+    /// Bound the buffer by approximate memory use instead of entry count
+    ///
+    /// Oldest entries are evicted until the total falls under `max_bytes`.
+    /// The newest entry is always kept, even if it alone exceeds the budget.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.inner.get_mut().max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Collapse an entry repeating the last one (same level, source and
+    /// message) into it, counting repeats in its `count` metadata
+    ///
+    /// The collapsed entry is broadcast again with the new count, under its
+    /// original id.
+    pub fn with_dedup(mut self) -> Self {
+        self.inner.get_mut().dedup = true;
+        self
+    }
+
+    /// Subscribe to new log entry and clear notifications
+    pub fn subscribe(&self) -> broadcast::Receiver<BufferEvent> {
         self.broadcast_tx.subscribe()
     }
 
     /// Append a log entry to the buffer
-    pub fn append(&self, entry: LogEntry) {
+    ///
+    /// Entries with a `ttlSeconds` metadata value expire that long after
+    /// being appended, regardless of capacity. Returns whether the buffer was
+    /// full and older entries were evicted to make room.
+    pub fn append(&self, entry: LogEntry) -> bool {
+        self.append_at(entry, Instant::now())
+    }
+
+    fn append_at(&self, entry: LogEntry, now: Instant) -> bool {
+        let expires_at = entry_ttl(&entry).map(|ttl| now + ttl);
+
         let mut inner = self.inner.write();
+        inner.remove_expired(now);
+
+        if inner.dedup {
+            if let Some(collapsed) = inner.collapse_repeat(&entry) {
+                drop(inner);
+                let _ = self
+                    .broadcast_tx
+                    .send(BufferEvent::Log(Box::new(collapsed)));
+                return false;
+            }
+        }
 
-        if inner.count < inner.capacity {
-            // Buffer not full yet
-            inner.entries.push(entry.clone());
-            inner.count += 1;
-        } else {
-            // Overwrite oldest entry
-            let idx = inner.start_index;
-            let cap = inner.capacity;
-            inner.entries[idx] = entry.clone();
-            inner.start_index = (idx + 1) % cap;
+        inner.total_bytes += approx_entry_size(&entry);
+        if expires_at.is_some() {
+            inner.ttl_entries += 1;
         }
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner.entries.push_back(BufferedEntry {
+            entry: entry.clone(),
+            expires_at,
+            seq,
+        });
+
+        // Evict oldest entries while over the byte budget or entry capacity
+        let evictions_before = inner.evictions;
+        while inner.entries.len() > 1 && inner.is_over_limit() {
+            if let Some(evicted) = inner.entries.pop_front() {
+                inner.total_bytes -= approx_entry_size(&evicted.entry);
+                if evicted.expires_at.is_some() {
+                    inner.ttl_entries -= 1;
+                }
+                inner.evictions += 1;
+            }
+        }
+
+        inner.high_water_mark = inner.high_water_mark.max(inner.entries.len());
+        let evicted = inner.evictions > evictions_before;
 
         // Release lock before broadcasting to prevent deadlock
         drop(inner);
 
         // Broadcast to SSE subscribers (ignore errors if no listeners)
-        let _ = self.broadcast_tx.send(entry);
+        let _ = self.broadcast_tx.send(BufferEvent::Log(Box::new(entry)));
+
+        evicted
+    }
+
+    /// Tell live stream subscribers that fresh uploaded logs are available
+    pub fn notify_upload(&self, notice: UploadNotice) {
+        let _ = self.broadcast_tx.send(BufferEvent::Upload(notice));
+    }
+
+    /// Remove entries whose TTL has elapsed, returning how many were removed
+    pub fn purge_expired(&self) -> usize {
+        self.purge_expired_at(Instant::now())
+    }
+
+    fn purge_expired_at(&self, now: Instant) -> usize {
+        // Most buffers hold no TTL entries; avoid the write lock for them
+        if self.inner.read().ttl_entries == 0 {
+            return 0;
+        }
+        self.inner.write().remove_expired(now)
     }
 
     /// Get all entries in chronological order
     pub fn get_all(&self) -> Vec<LogEntry> {
+        self.purge_expired();
+        let inner = self.inner.read();
+        get_all_from_inner(&inner)
+    }
+
+    /// Up to `size` entries with sequence numbers from `from` on, oldest first
+    ///
+    /// Sequence numbers only grow, so a page boundary stays put while new
+    /// entries arrive. If entries at `from` were evicted, the page starts at
+    /// the oldest one still buffered.
+    pub fn chunk_from(&self, from: u64, size: usize) -> BufferChunk {
+        self.purge_expired();
         let inner = self.inner.read();
 
-        if inner.count < inner.capacity {
-            // Return entries as-is
-            inner.entries.clone()
-        } else {
-            // Reconstruct chronological order
-            let mut result = Vec::with_capacity(inner.capacity);
-            let tail = &inner.entries[inner.start_index..];
-            let head = &inner.entries[..inner.start_index];
-            result.extend(tail.iter().cloned());
-            result.extend(head.iter().cloned());
-            result
+        let start = inner
+            .entries
+            .partition_point(|buffered| buffered.seq < from);
+        let page: Vec<&BufferedEntry> = inner.entries.range(start..).take(size).collect();
+
+        BufferChunk {
+            next_seq: page.last().map_or(from, |buffered| buffered.seq + 1),
+            has_more: start + page.len() < inner.entries.len(),
+            entries: page
+                .into_iter()
+                .map(|buffered| buffered.entry.clone())
+                .collect(),
         }
     }
 
     /// Get entries matching current filters
     #[allow(dead_code)]
     pub fn get_filtered(&self) -> Vec<LogEntry> {
+        self.purge_expired();
         let inner = self.inner.read();
         let all_entries = get_all_from_inner(&inner);
 
@@ -132,30 +319,254 @@ impl LogBuffer {
     pub fn clear(&self) {
         let mut inner = self.inner.write();
         inner.entries.clear();
-        inner.start_index = 0;
-        inner.count = 0;
+        inner.total_bytes = 0;
+        inner.ttl_entries = 0;
+        drop(inner);
+
+        // Let live views wipe their rows immediately
+        let _ = self.broadcast_tx.send(BufferEvent::Clear);
+    }
+
+    /// Change the entry capacity, returning how many entries were dropped
+    ///
+    /// Growing keeps every entry; shrinking keeps the newest `capacity`
+    /// entries. Dropped entries count as evictions.
+    pub fn resize(&self, capacity: usize) -> usize {
+        let capacity = capacity.max(1);
+        let mut inner = self.inner.write();
+        inner.capacity = capacity;
+
+        let excess = inner.entries.len().saturating_sub(capacity);
+        if excess > 0 {
+            inner.entries.drain(..excess);
+            inner.recount();
+            inner.evictions += excess as u64;
+        }
+
+        excess
+    }
+
+    /// Maximum number of entries, as last set by `new` or `resize`
+    pub fn capacity(&self) -> usize {
+        self.inner.read().capacity
+    }
+
+    /// Keep only entries matching the predicate, returning how many were removed
+    pub fn retain<F>(&self, mut keep: F) -> usize
+    where
+        F: FnMut(&LogEntry) -> bool,
+    {
+        let mut inner = self.inner.write();
+        let before = inner.entries.len();
+
+        inner.entries.retain(|buffered| keep(&buffered.entry));
+        inner.recount();
+
+        before - inner.entries.len()
+    }
+
+    /// Write the buffered entries to `path` as JSONL, returning how many were saved
+    ///
+    /// Writes a sibling temp file and renames it into place, so a crash
+    /// mid-write leaves the previous file intact.
+    pub fn save_to_file(&self, path: &Path) -> io::Result<usize> {
+        let entries = self.snapshot();
+        write_jsonl(path, &entries)?;
+        Ok(entries.len())
+    }
+
+    /// Append the entries saved in a JSONL file, returning how many were loaded
+    ///
+    /// A missing file loads nothing; unparseable lines are skipped.
+    pub fn load_from_file(&self, path: &Path) -> io::Result<usize> {
+        let entries = read_jsonl(path)?;
+        let loaded = entries.len();
+        for entry in entries {
+            self.append(entry);
+        }
+        Ok(loaded)
+    }
+
+    /// Fill a new buffer with previously saved entries, oldest first
+    ///
+    /// Capacity and byte limits apply as if the entries had just arrived.
+    pub fn load_from(self, entries: Vec<LogEntry>) -> Self {
+        for entry in entries {
+            self.append(entry);
+        }
+        self
+    }
+
+    /// Copy of the buffered entries, oldest first, for persisting
+    pub fn snapshot(&self) -> Vec<LogEntry> {
+        self.get_all()
     }
 
     /// Get current number of entries
-    #[allow(dead_code)]
     pub fn count(&self) -> usize {
+        self.purge_expired();
         let inner = self.inner.read();
-        inner.count
+        inner.entries.len()
+    }
+
+    /// Get occupancy counters (high-water mark and evictions)
+    pub fn stats(&self) -> BufferStats {
+        self.purge_expired();
+        let inner = self.inner.read();
+        BufferStats {
+            count: inner.entries.len(),
+            capacity: inner.capacity,
+            high_water_mark: inner.high_water_mark,
+            evictions: inner.evictions,
+            approx_bytes: inner.total_bytes,
+            max_bytes: inner.max_bytes,
+        }
     }
 }
 
-/// Helper function to get all entries from inner buffer
-fn get_all_from_inner(inner: &BufferInner) -> Vec<LogEntry> {
-    if inner.count < inner.capacity {
-        inner.entries.clone()
-    } else {
-        let mut result = Vec::with_capacity(inner.capacity);
-        let tail = &inner.entries[inner.start_index..];
-        let head = &inner.entries[..inner.start_index];
-        result.extend(tail.iter().cloned());
-        result.extend(head.iter().cloned());
-        result
+impl BufferInner {
+    /// Whether the buffer holds more than its byte budget or entry capacity
+    fn is_over_limit(&self) -> bool {
+        match self.max_bytes {
+            Some(max_bytes) => self.total_bytes > max_bytes,
+            None => self.entries.len() > self.capacity,
+        }
+    }
+
+    /// Count `entry` against the last entry if it repeats it, returning the
+    /// updated last entry
+    fn collapse_repeat(&mut self, entry: &LogEntry) -> Option<LogEntry> {
+        let last = &mut self.entries.back_mut()?.entry;
+        if last.level != entry.level || last.source != entry.source || last.message != entry.message
+        {
+            return None;
+        }
+
+        let count: u64 = last
+            .metadata
+            .get(DEDUP_COUNT_METADATA_KEY)
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(1);
+        let size_before = approx_entry_size(last);
+        last.metadata.insert(
+            DEDUP_COUNT_METADATA_KEY.to_string(),
+            (count + 1).to_string(),
+        );
+        let collapsed = last.clone();
+
+        self.total_bytes = self.total_bytes - size_before + approx_entry_size(&collapsed);
+        Some(collapsed)
+    }
+
+    /// Drop entries whose TTL has elapsed at `now`
+    fn remove_expired(&mut self, now: Instant) -> usize {
+        if self.ttl_entries == 0 {
+            return 0;
+        }
+
+        let before = self.entries.len();
+        self.entries
+            .retain(|buffered| buffered.expires_at.map_or(true, |at| at > now));
+        self.recount();
+
+        before - self.entries.len()
+    }
+
+    /// Recompute byte and TTL totals after removing arbitrary entries
+    fn recount(&mut self) {
+        self.total_bytes = self
+            .entries
+            .iter()
+            .map(|buffered| approx_entry_size(&buffered.entry))
+            .sum();
+        self.ttl_entries = self
+            .entries
+            .iter()
+            .filter(|buffered| buffered.expires_at.is_some())
+            .count();
+    }
+}
+
+/// Time-to-live requested by an entry's `ttlSeconds` metadata, if valid
+fn entry_ttl(entry: &LogEntry) -> Option<Duration> {
+    entry
+        .metadata
+        .get(TTL_METADATA_KEY)
+        .and_then(|ttl| ttl.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Read entries saved as JSONL; a missing file reads as empty and
+/// unparseable lines are skipped
+pub fn read_jsonl(path: &Path) -> io::Result<Vec<LogEntry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        match serde_json::from_str::<LogEntry>(&line?) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => tracing::warn!(error = %e, "Skipping unreadable saved entry"),
+        }
     }
+    Ok(entries)
+}
+
+/// Write entries to `path` as JSONL via a sibling temp file and rename
+pub fn write_jsonl(path: &Path, entries: &[LogEntry]) -> io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = Path::new(&tmp_name);
+
+    let mut writer = BufWriter::new(File::create(tmp_path)?);
+    for entry in entries {
+        serde_json::to_writer(&mut writer, entry)?;
+        writer.write_all(b"\n")?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+
+    fs::rename(tmp_path, path)
+}
+
+fn get_all_from_inner(inner: &BufferInner) -> Vec<LogEntry> {
+    inner
+        .entries
+        .iter()
+        .map(|buffered| buffered.entry.clone())
+        .collect()
+}
+
+/// Approximate serialized size of an entry in bytes
+///
+/// Sums the variable-length fields plus a fixed allowance for JSON keys,
+/// the timestamp and the line number; cheap enough to run on every append.
+fn approx_entry_size(entry: &LogEntry) -> usize {
+    const FIXED_OVERHEAD: usize = 160;
+
+    let metadata: usize = entry
+        .metadata
+        .iter()
+        .map(|(k, v)| k.len() + v.len() + 6)
+        .sum();
+    let tags: usize = entry.tags.iter().map(|t| t.len() + 3).sum();
+
+    FIXED_OVERHEAD
+        + entry.id.len()
+        + entry.level.len()
+        + entry.message.len()
+        + entry.user_id.as_ref().map_or(0, String::len)
+        + entry.device_id.len()
+        + entry.source.len()
+        + entry.file.len()
+        + entry.function.len()
+        + metadata
+        + tags
 }
 
 #[cfg(test)]
@@ -194,6 +605,113 @@ mod tests {
         assert_eq!(entries[1].id, "2");
     }
 
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("buffer.jsonl");
+        let buffer = LogBuffer::new(10);
+        for (id, level) in [("1", "info"), ("2", "error"), ("3", "debug")] {
+            buffer.append(create_entry(id, level));
+        }
+
+        let snapshot = buffer.snapshot();
+        assert_eq!(buffer.save_to_file(&path).unwrap(), 3);
+
+        let reloaded = LogBuffer::new(2).load_from(read_jsonl(&path).unwrap());
+        let ids: Vec<String> = reloaded.get_all().into_iter().map(|e| e.id).collect();
+        // The smaller buffer keeps the newest entries, as at ingest
+        assert_eq!(ids, ["2", "3"]);
+        assert_eq!(reloaded.get_all()[0].level, snapshot[1].level);
+
+        let restored = LogBuffer::new(10).load_from(snapshot);
+        assert_eq!(restored.count(), 3);
+    }
+
+    #[test]
+    fn test_chunks_stay_stable_across_appends_and_evictions() {
+        let buffer = LogBuffer::new(5);
+        for id in ["1", "2", "3", "4"] {
+            buffer.append(create_entry(id, "info"));
+        }
+
+        let first = buffer.chunk_from(0, 2);
+        let ids: Vec<&str> = first.entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2"]);
+        assert!(first.has_more);
+
+        // "1" to "3" are evicted while the client holds its cursor
+        for id in ["5", "6", "7", "8"] {
+            buffer.append(create_entry(id, "info"));
+        }
+        let second = buffer.chunk_from(first.next_seq, 2);
+        let ids: Vec<&str> = second.entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["4", "5"]);
+
+        let rest = buffer.chunk_from(second.next_seq, 10);
+        assert_eq!(rest.entries.len(), 3);
+        assert!(!rest.has_more);
+
+        // An exhausted cursor keeps its place for later arrivals
+        let empty = buffer.chunk_from(rest.next_seq, 10);
+        assert!(empty.entries.is_empty());
+        assert_eq!(empty.next_seq, rest.next_seq);
+        buffer.append(create_entry("9", "info"));
+        assert_eq!(buffer.chunk_from(rest.next_seq, 10).entries[0].id, "9");
+    }
+
+    #[test]
+    fn test_dedup_collapses_consecutive_repeats() {
+        let repeat = |i: usize| {
+            let mut entry = create_entry(&format!("repeat-{}", i), "error");
+            entry.message = "Disk full".to_string();
+            entry
+        };
+        let buffer = LogBuffer::new(10).with_dedup();
+        let mut rx = buffer.subscribe();
+
+        for i in 0..5 {
+            buffer.append(repeat(i));
+        }
+
+        let entries = buffer.get_all();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "repeat-0");
+        assert_eq!(entries[0].metadata[DEDUP_COUNT_METADATA_KEY], "5");
+
+        // Every repeat is still broadcast, carrying the running count
+        let mut counts = Vec::new();
+        while let Ok(BufferEvent::Log(entry)) = rx.try_recv() {
+            assert_eq!(entry.id, "repeat-0");
+            counts.push(entry.metadata.get(DEDUP_COUNT_METADATA_KEY).cloned());
+        }
+        let expected: Vec<Option<String>> = [None, Some("2"), Some("3"), Some("4"), Some("5")]
+            .into_iter()
+            .map(|count| count.map(String::from))
+            .collect();
+        assert_eq!(counts, expected);
+
+        // A different entry breaks the run
+        buffer.append(create_entry("other", "error"));
+        buffer.append(repeat(5));
+        assert_eq!(buffer.count(), 3);
+
+        // Without dedup every repeat is kept
+        let buffer = LogBuffer::new(10);
+        for i in 0..3 {
+            buffer.append(repeat(i));
+        }
+        assert_eq!(buffer.count(), 3);
+    }
+
+    #[test]
+    fn test_append_reports_eviction() {
+        let buffer = LogBuffer::new(2);
+
+        assert!(!buffer.append(create_entry("1", "info")));
+        assert!(!buffer.append(create_entry("2", "info")));
+        assert!(buffer.append(create_entry("3", "info")));
+    }
+
     #[test]
     fn test_buffer_circular_behavior() {
         let buffer = LogBuffer::new(3);
@@ -210,6 +728,40 @@ mod tests {
         assert_eq!(entries[2].id, "4"); // Newest
     }
 
+    #[test]
+    fn test_resize_grow_keeps_entries_in_order() {
+        let buffer = LogBuffer::new(3);
+        for id in ["1", "2", "3", "4"] {
+            buffer.append(create_entry(id, "info"));
+        }
+
+        assert_eq!(buffer.resize(5), 0);
+        assert_eq!(buffer.capacity(), 5);
+        buffer.append(create_entry("5", "info"));
+        buffer.append(create_entry("6", "info"));
+        buffer.append(create_entry("7", "info"));
+
+        let ids: Vec<String> = buffer.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["3", "4", "5", "6", "7"]);
+    }
+
+    #[test]
+    fn test_resize_shrink_keeps_newest() {
+        let buffer = LogBuffer::new(5);
+        for id in ["1", "2", "3", "4", "5"] {
+            buffer.append(create_entry(id, "info"));
+        }
+
+        assert_eq!(buffer.resize(2), 3);
+        let ids: Vec<String> = buffer.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["4", "5"]);
+        assert_eq!(buffer.stats().evictions, 3);
+
+        buffer.append(create_entry("6", "info"));
+        let ids: Vec<String> = buffer.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["5", "6"]);
+    }
+
     #[test]
     fn test_buffer_clear() {
         let buffer = LogBuffer::new(10);
@@ -238,4 +790,190 @@ mod tests {
         assert_eq!(filtered[0].id, "2");
         assert_eq!(filtered[1].id, "3");
     }
+
+    #[test]
+    fn test_buffer_eviction_counter() {
+        let buffer = LogBuffer::new(2);
+
+        buffer.append(create_entry("1", "info"));
+        buffer.append(create_entry("2", "info"));
+        assert_eq!(buffer.stats().evictions, 0);
+        assert_eq!(buffer.stats().high_water_mark, 2);
+
+        buffer.append(create_entry("3", "info"));
+        buffer.append(create_entry("4", "info"));
+
+        let stats = buffer.stats();
+        assert_eq!(stats.evictions, 2);
+        assert_eq!(stats.high_water_mark, 2);
+
+        buffer.clear();
+        assert_eq!(buffer.stats().count, 0);
+        assert_eq!(buffer.stats().high_water_mark, 2);
+    }
+
+    #[test]
+    fn test_buffer_byte_bound() {
+        let buffer = LogBuffer::new(1000).with_max_bytes(2_000);
+
+        for (i, len) in [10, 900, 50, 600, 5, 1200, 30, 400].iter().enumerate() {
+            let mut entry = create_entry(&i.to_string(), "info");
+            entry.message = "x".repeat(*len);
+            buffer.append(entry);
+
+            let stats = buffer.stats();
+            assert!(
+                stats.approx_bytes <= 2_000,
+                "over budget: {}",
+                stats.approx_bytes
+            );
+        }
+
+        // Oldest entries were evicted, newest kept in order
+        let entries = buffer.get_all();
+        assert!(entries.len() < 8);
+        assert_eq!(entries.last().unwrap().id, "7");
+        assert!(buffer.stats().evictions > 0);
+    }
+
+    #[test]
+    fn test_buffer_byte_bound_keeps_oversized_newest() {
+        let buffer = LogBuffer::new(1000).with_max_bytes(100);
+
+        let mut entry = create_entry("big", "info");
+        entry.message = "x".repeat(500);
+        buffer.append(entry);
+
+        assert_eq!(buffer.count(), 1);
+        assert_eq!(buffer.get_all()[0].id, "big");
+    }
+
+    #[test]
+    fn test_buffer_retain() {
+        let buffer = LogBuffer::new(10);
+
+        buffer.append(create_entry("1", "debug"));
+        buffer.append(create_entry("2", "error"));
+        buffer.append(create_entry("3", "debug"));
+
+        let removed = buffer.retain(|e| e.level == "error");
+        assert_eq!(removed, 2);
+        assert_eq!(buffer.count(), 1);
+        assert_eq!(
+            buffer.stats().approx_bytes,
+            approx_entry_size(&buffer.get_all()[0])
+        );
+    }
+
+    #[test]
+    fn test_concurrent_append_read_clear() {
+        const CAPACITY: usize = 50;
+        const WRITERS: usize = 8;
+        const APPENDS_PER_WRITER: usize = 2_000;
+
+        let buffer = LogBuffer::new(CAPACITY);
+
+        std::thread::scope(|scope| {
+            for writer in 0..WRITERS {
+                let buffer = &buffer;
+                scope.spawn(move || {
+                    for i in 0..APPENDS_PER_WRITER {
+                        buffer.append(create_entry(&format!("{}-{}", writer, i), "info"));
+                    }
+                });
+            }
+
+            for _ in 0..4 {
+                let buffer = &buffer;
+                scope.spawn(move || {
+                    for _ in 0..1_000 {
+                        let entries = buffer.get_all();
+                        assert!(entries.len() <= CAPACITY);
+
+                        let stats = buffer.stats();
+                        assert!(stats.count <= CAPACITY);
+                        assert!(stats.high_water_mark <= CAPACITY);
+                    }
+                });
+            }
+
+            let buffer = &buffer;
+            scope.spawn(move || {
+                for _ in 0..20 {
+                    buffer.clear();
+                    std::thread::yield_now();
+                }
+            });
+        });
+
+        // Each writer's surviving entries are still in append order
+        let entries = buffer.get_all();
+        assert!(entries.len() <= CAPACITY);
+        for writer in 0..WRITERS {
+            let prefix = format!("{}-", writer);
+            let sequence: Vec<usize> = entries
+                .iter()
+                .filter_map(|e| e.id.strip_prefix(&prefix))
+                .map(|i| i.parse().unwrap())
+                .collect();
+            assert!(sequence.windows(2).all(|w| w[0] < w[1]));
+        }
+
+        // Byte accounting stays consistent with the surviving entries
+        let expected_bytes: usize = entries.iter().map(approx_entry_size).sum();
+        assert_eq!(buffer.stats().approx_bytes, expected_bytes);
+
+        // Every append was counted as either surviving, evicted or cleared
+        let stats = buffer.stats();
+        assert!(stats.evictions as usize + stats.count <= WRITERS * APPENDS_PER_WRITER);
+    }
+
+    #[test]
+    fn test_ttl_entry_expires() {
+        let buffer = LogBuffer::new(10);
+        let start = Instant::now();
+
+        let mut ephemeral = create_entry("ephemeral", "debug");
+        ephemeral
+            .metadata
+            .insert(TTL_METADATA_KEY.to_string(), "2".to_string());
+        buffer.append_at(ephemeral, start);
+        buffer.append_at(create_entry("durable", "info"), start);
+
+        assert_eq!(buffer.purge_expired_at(start + Duration::from_secs(1)), 0);
+        assert_eq!(buffer.get_all().len(), 2);
+
+        assert_eq!(buffer.purge_expired_at(start + Duration::from_secs(3)), 1);
+        let entries = buffer.get_all();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "durable");
+        assert_eq!(buffer.stats().approx_bytes, approx_entry_size(&entries[0]));
+    }
+
+    #[test]
+    fn test_ttl_zero_expires_immediately() {
+        let buffer = LogBuffer::new(10);
+
+        let mut entry = create_entry("1", "info");
+        entry
+            .metadata
+            .insert(TTL_METADATA_KEY.to_string(), "0".to_string());
+        buffer.append(entry);
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert!(buffer.get_all().is_empty());
+        assert_eq!(buffer.count(), 0);
+    }
+
+    #[test]
+    fn test_subscriber_receives_clear_signal() {
+        let buffer = LogBuffer::new(10);
+        let mut receiver = buffer.subscribe();
+
+        buffer.append(create_entry("1", "info"));
+        buffer.clear();
+
+        assert!(matches!(receiver.try_recv(), Ok(BufferEvent::Log(e)) if e.id == "1"));
+        assert!(matches!(receiver.try_recv(), Ok(BufferEvent::Clear)));
+    }
 }
@@ -0,0 +1,98 @@
+//! Scheduled housekeeping: expire stale log requests and delete stored
+//! uploads older than the retention period.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::AppState;
+
+/// Run a cleanup pass every `period`, starting right away, until aborted
+///
+/// Each pass runs to completion between ticks, so aborting the task on
+/// shutdown never stops it halfway through deleting an upload.
+pub async fn run(state: Arc<AppState>, period: Duration, retention_days: i64) {
+    let mut ticker = tokio::time::interval(period);
+
+    loop {
+        ticker.tick().await;
+        run_once(&state, retention_days);
+    }
+}
+
+/// Expire log requests and delete uploads older than `retention_days`
+///
+/// Returns the number of requests and upload files removed.
+pub fn run_once(state: &AppState, retention_days: i64) -> (usize, usize) {
+    let requests = state.request_manager.cleanup_expired();
+    let files = match state.storage.cleanup_old_uploads(retention_days) {
+        Ok(files) => files,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to clean up old uploads");
+            0
+        }
+    };
+
+    if requests > 0 || files > 0 {
+        tracing::info!(
+            requests,
+            files,
+            retention_days,
+            "Periodic cleanup removed items"
+        );
+    } else {
+        tracing::debug!("Periodic cleanup found nothing to remove");
+    }
+
+    (requests, files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::models::LogEntry;
+    use crate::storage::{LogStorage, UploadStore};
+    use std::fs::File;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_run_once_removes_uploads_past_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf()).unwrap();
+        let entries: Vec<LogEntry> = vec![serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "timestamp": "2024-03-01T12:00:00Z",
+            "level": "info",
+            "message": "hello",
+            "deviceId": "device-1",
+        }))
+        .unwrap()];
+
+        let user_id = Uuid::new_v4();
+        let save = |age_days: u64| {
+            let request_id = Uuid::new_v4();
+            storage
+                .save_upload(user_id, "device-1", request_id, &entries)
+                .unwrap();
+            let path = dir.path().join(
+                storage
+                    .locate_upload(user_id, "device-1", request_id)
+                    .unwrap(),
+            );
+            let modified =
+                std::time::SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60);
+            File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        save(10);
+        save(1);
+
+        let state = AppState::new(Config::default(), Arc::new(storage), None);
+        assert_eq!(run_once(&state, 7), (0, 1));
+        assert_eq!(state.storage.list_uploads(user_id).unwrap().len(), 1);
+    }
+}
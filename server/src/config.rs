@@ -1,8 +1,24 @@
 //! Configuration management for the log server.
 //!
-//! Loads configuration from environment variables with sensible defaults.
+//! Loads configuration from environment variables with sensible defaults,
+//! optionally layered over a JSON config file.
 
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono_tz::Tz;
+use jsonwebtoken::Algorithm;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::auth;
+use crate::ingest::MAX_BODY_BYTES;
+use crate::models::LogLevel;
+use crate::presets::{self, FilterPreset};
+use crate::storage::DEFAULT_PATH_TEMPLATE;
+use crate::upload_cache::DEFAULT_MAX_CACHED_ENTRIES;
 
 /// Default port for the log server
 const DEFAULT_PORT: u16 = 9006;
@@ -13,6 +29,66 @@ const DEFAULT_CAPACITY: usize = 10_000;
 /// Default upload directory for client log uploads
 const DEFAULT_UPLOAD_DIR: &str = "./uploads";
 
+/// Default storage backend for uploaded logs
+const DEFAULT_STORAGE_BACKEND: &str = "files";
+
+/// Default lifetime of a log request (hours)
+const DEFAULT_REQUEST_TTL_HOURS: i64 = 24;
+
+/// Default window for reusing a just-created log request (seconds; 0 = off)
+const DEFAULT_REQUEST_DEDUP_WINDOW_SECS: i64 = 0;
+
+/// Default time after a request expires that its upload is still accepted (seconds)
+const DEFAULT_UPLOAD_GRACE_SECS: i64 = 300;
+
+/// Default minimum spacing between polls from one device (milliseconds; 0 = off)
+const DEFAULT_POLL_MIN_INTERVAL_MS: u64 = 0;
+
+/// Default number of polls a device may make back to back
+const DEFAULT_POLL_BURST: u32 = 1;
+
+/// Default entries per second accepted from one source on POST /logs (0 = off)
+const DEFAULT_INGEST_RATE_PER_SEC: u32 = 0;
+
+/// Default number of entries a source may send back to back
+const DEFAULT_INGEST_BURST: u32 = 50;
+
+/// Default SSE reconnect interval suggested to browsers (milliseconds)
+const DEFAULT_SSE_RETRY_MS: u64 = 5_000;
+
+/// Default interval between background cleanup passes (seconds)
+const DEFAULT_CLEANUP_INTERVAL_SECS: u64 = 3_600;
+
+/// Default age beyond which stored uploads are deleted (days)
+const DEFAULT_RETENTION_DAYS: i64 = 7;
+
+/// Default interval between SSE keep-alive pings and heartbeat events (seconds)
+const DEFAULT_SSE_KEEPALIVE_SECS: u64 = 15;
+
+/// Default age beyond which a flushed buffer isn't reloaded (seconds)
+const DEFAULT_PERSIST_BUFFER_MAX_AGE_SECS: u64 = 86_400;
+
+/// Default share of the buffer that must be unsaved before an auto-save
+const DEFAULT_AUTO_SAVE_THRESHOLD: f64 = 0.9;
+
+/// Default size at which LOG_FILE is rotated (bytes)
+const DEFAULT_LOG_FILE_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Default number of rotated LOG_FILE copies kept
+const DEFAULT_LOG_FILE_MAX_FILES: usize = 5;
+
+/// Default cap on an entry's message before it is truncated
+const DEFAULT_MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Default number of sources listed in the SIGUSR1 summary
+const DEFAULT_SUMMARY_TOP_SOURCES: usize = 5;
+
+/// Default silence allowed from a watched source before alerting (seconds)
+const DEFAULT_DEADMAN_INTERVAL_SECS: u64 = 300;
+
+/// Default allowed clock skew for future timestamps (seconds)
+const DEFAULT_FUTURE_TIMESTAMP_TOLERANCE_SECS: i64 = 60;
+
 /// Server configuration
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -22,60 +98,890 @@ pub struct Config {
     /// Buffer capacity (number of entries)
     pub capacity: usize,
 
+    /// Bound the buffer by approximate bytes instead of entry count
+    pub max_buffer_bytes: Option<usize>,
+
+    /// Collapse consecutive repeats of an entry into one with a `count`
+    pub dedup: bool,
+
+    /// Entries below this level are dropped on ingest, before buffering or archiving
+    pub min_ingest_level: LogLevel,
+
+    /// Capacity of a secondary "cold" buffer for historical search (disabled if unset)
+    pub cold_capacity: Option<usize>,
+
+    /// Save the buffer to a snapshot file this often, reloading it on startup (disabled if unset)
+    pub snapshot_interval_secs: Option<u64>,
+
+    /// Snapshot file (defaults to `buffer-snapshot.jsonl` in the upload directory)
+    pub snapshot_path: Option<PathBuf>,
+
+    /// Flush the buffer to `buffer.jsonl` in the upload directory on shutdown and reload it on start
+    pub persist_buffer: bool,
+
+    /// A flushed buffer older than this is not reloaded (seconds)
+    pub persist_buffer_max_age_secs: u64,
+
+    /// Save unsaved buffer entries to timestamped files as the buffer fills
+    pub auto_save: bool,
+
+    /// Fraction of capacity that must be unsaved to trigger an auto-save (0-1]
+    pub auto_save_threshold: f64,
+
     /// Verbose mode (show file/line metadata)
     pub verbose: bool,
 
+    /// Levels shown verbosely in the terminal, others compactly (overrides `verbose` if set)
+    pub verbose_levels: Vec<LogLevel>,
+
+    /// Busiest sources listed in the summary printed on SIGUSR1
+    pub summary_top_sources: usize,
+
     /// Directory for storing uploaded client logs
     pub upload_dir: PathBuf,
 
+    /// Layout of upload files within the upload directory
+    pub storage_path_template: String,
+
+    /// Store new uploads gzip-compressed as `.jsonl.gz`
+    pub compress_uploads: bool,
+
+    /// Store uploads with more entries than this as numbered part files (file storage only)
+    pub upload_split_entries: Option<usize>,
+
+    /// Keep recently read uploads parsed in memory (file storage only)
+    pub upload_cache: bool,
+
+    /// Log entries held across all cached uploads
+    pub upload_cache_max_entries: usize,
+
+    /// Upload storage backend: "files" (JSONL tree) or "sqlite"
+    pub storage_backend: String,
+
+    /// Directory for the append-only archive of received entries (disabled if unset)
+    pub archive_dir: Option<PathBuf>,
+
+    /// Partition archive files into one directory per entry source
+    pub archive_by_source: bool,
+
+    /// Minimum level written to the archive (the buffer still keeps everything)
+    pub archive_min_level: LogLevel,
+
+    /// Gzip earlier days' archive files when a new day starts
+    pub archive_compress: bool,
+
+    /// JSONL file every received entry is appended to (disabled if unset)
+    pub log_file: Option<PathBuf>,
+
+    /// Rotate LOG_FILE once the next entry would take it past this size (bytes)
+    pub log_file_max_bytes: u64,
+
+    /// Rotated LOG_FILE copies kept as `<name>.1` ... `<name>.N`
+    pub log_file_max_files: usize,
+
+    /// Named filters selectable with `?preset=` on `/logs` and `/stream`
+    pub filter_presets: HashMap<String, FilterPreset>,
+
+    /// Timezone for `localTimestamp` on downloaded uploads (IANA name)
+    pub display_timezone: Option<Tz>,
+
     /// Path to JWT public key for authentication
     pub jwt_public_key_path: Option<String>,
+
+    /// Signing algorithm of accepted JWTs; the public key type must match
+    pub jwt_algorithm: Algorithm,
+
+    /// PEM certificate chain served over HTTPS (requires `tls_key_path`)
+    pub tls_cert_path: Option<PathBuf>,
+
+    /// PEM private key for `tls_cert_path`
+    pub tls_key_path: Option<PathBuf>,
+
+    /// JSON file the log request map is saved to and reloaded from
+    pub requests_persist_path: Option<PathBuf>,
+
+    /// How long a log request stays pending before it expires (hours)
+    pub request_ttl_hours: i64,
+
+    /// Repeat log requests for a device within this many seconds reuse the pending one
+    pub request_dedup_window_secs: i64,
+
+    /// Reject uploads whose earliest log is older than this many days (unlimited if unset)
+    pub max_upload_age_days: Option<i64>,
+
+    /// Reject uploads carrying more entries than this with 413 (unlimited if unset)
+    pub max_upload_logs: Option<usize>,
+
+    /// Largest accepted upload body, as sent (compressed size for gzip bodies)
+    pub max_upload_bytes: usize,
+
+    /// Uploads for a request that expired less than this long ago are still accepted (seconds)
+    pub upload_grace_secs: i64,
+
+    /// Drop a request's record once its upload has been downloaded (the file is kept)
+    pub delete_request_on_download: bool,
+
+    /// Polls from the same device closer together than this get 429 (milliseconds)
+    pub poll_min_interval_ms: u64,
+
+    /// Polls a device may make back to back before the interval applies
+    pub poll_burst: u32,
+
+    /// Entries per second each source may send to POST /logs (0 = unlimited)
+    pub ingest_rate_per_sec: u32,
+
+    /// Entries a source may send back to back before the rate applies
+    pub ingest_burst: u32,
+
+    /// Reconnect interval sent to SSE clients via the `retry:` field (milliseconds)
+    pub sse_retry_ms: u64,
+
+    /// Interval between background passes expiring requests and deleting old uploads (seconds)
+    pub cleanup_interval_secs: u64,
+
+    /// Stored uploads older than this are deleted by the background cleanup (days)
+    pub retention_days: i64,
+
+    /// Interval between SSE `ping` comments and `heartbeat` events (seconds)
+    pub sse_keepalive_secs: u64,
+
+    /// Shared secret for signing outgoing webhook payloads
+    pub webhook_signing_secret: Option<String>,
+
+    /// Webhook receiving alerts (e.g. dead-man's switch), alerts are only logged if unset
+    pub alert_webhook_url: Option<String>,
+
+    /// Sources expected to log regularly; silence beyond the interval raises an alert
+    pub deadman_sources: Vec<String>,
+
+    /// Silence allowed from a watched source before alerting (seconds)
+    pub deadman_interval_secs: u64,
+
+    /// Metadata keys every ingested entry must carry
+    pub required_metadata_keys: Vec<String>,
+
+    /// Tags added to every ingested entry (e.g. the deployment environment)
+    pub global_tags: Vec<String>,
+
+    /// Client-supplied tags kept per entry; extras are dropped (unlimited if unset)
+    pub max_tags_per_entry: Option<usize>,
+
+    /// CIDR ranges allowed to submit logs (empty = everyone)
+    pub ingest_ip_allowlist: Vec<String>,
+
+    /// Use `X-Forwarded-For` from a trusted reverse proxy as the client address
+    pub trust_forwarded_for: bool,
+
+    /// MaxMind country database for `geo_country` enrichment (disabled if unset)
+    pub geoip_database: Option<PathBuf>,
+
+    /// Reject submitted entries containing fields the server doesn't recognize
+    pub strict_json: bool,
+
+    /// Reject device ids that aren't UUIDs on ingest, log requests, polls and uploads
+    pub strict_device_ids: bool,
+
+    /// Reject entries with an unknown level instead of storing them as info
+    pub strict_levels: bool,
+
+    /// Longer messages are truncated to this many bytes, ending in an ellipsis
+    pub max_message_bytes: usize,
+
+    /// Strip control characters and trailing whitespace from messages
+    pub normalize_messages: bool,
+
+    /// Set a missing source from the first path segment of the entry's file
+    pub infer_source_from_file: bool,
+
+    /// List fields the server filled in or changed under the `_server` metadata key
+    pub track_server_fields: bool,
+
+    /// Reject entries whose timestamp is too far in the future
+    pub reject_future_timestamps: bool,
+
+    /// Clamp future timestamps to the current time instead of rejecting
+    pub clamp_future_timestamps: bool,
+
+    /// Allowed clock skew before a timestamp counts as "future" (seconds)
+    pub future_timestamp_tolerance_secs: i64,
 }
 
 impl Config {
     /// Load configuration from environment variables
-    pub fn from_env() -> Self {
-        let port = std::env::var("PORT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(DEFAULT_PORT);
-
-        let capacity = std::env::var("CAPACITY")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(DEFAULT_CAPACITY);
-
-        let verbose = std::env::var("VERBOSE")
-            .map(|v| v == "1" || v.to_lowercase() == "true")
-            .unwrap_or(false);
+    ///
+    /// Fails on values that don't parse (`PORT=abc`) instead of quietly
+    /// using the default, and on settings [`Config::validate`] rejects.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_settings(&Settings::from_env())
+    }
+
+    /// Load configuration from a JSON file, with environment variables
+    /// overriding any setting the file also gives
+    ///
+    /// Keys are the environment variable names in lowercase (`port`,
+    /// `capacity`, `filter_presets`, ...); lists may be written as arrays.
+    /// Keys no setting reads are rejected, so a typo can't go unnoticed.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(env_vars(), &json)
+    }
+
+    fn from_json(env: HashMap<String, String>, json: &str) -> Result<Self, ConfigError> {
+        let settings = Settings::new(env).with_file(json)?;
+        let config = Self::from_settings(&settings)?;
+
+        let unknown = settings.unread_file_keys();
+        if !unknown.is_empty() {
+            return Err(ConfigError::UnknownKeys(unknown.join(", ")));
+        }
+        Ok(config)
+    }
+
+    fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let config = Self::load(settings);
+
+        let invalid = settings.invalid.take();
+        if !invalid.is_empty() {
+            return Err(ConfigError::InvalidValues(invalid.join(", ")));
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Certificate and key paths when HTTPS is configured
+    pub fn tls(&self) -> Option<(&Path, &Path)> {
+        Some((
+            self.tls_cert_path.as_deref()?,
+            self.tls_key_path.as_deref()?,
+        ))
+    }
+
+    /// Reject settings the server can't run with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.capacity == 0 {
+            return Err(ConfigError::ZeroCapacity);
+        }
+
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err(ConfigError::IncompleteTls);
+        }
+
+        // `uploads` has an empty parent, meaning the working directory
+        if let Some(parent) = self.upload_dir.parent() {
+            if !parent.as_os_str().is_empty() && !parent.is_dir() {
+                return Err(ConfigError::MissingUploadDirParent(parent.to_path_buf()));
+            }
+        }
+        Ok(())
+    }
+
+    fn load(settings: &Settings) -> Self {
+        let port = settings.parse("PORT").unwrap_or(DEFAULT_PORT);
+
+        let capacity = settings.parse("CAPACITY").unwrap_or(DEFAULT_CAPACITY);
+
+        let max_buffer_bytes = settings.parse("MAX_BUFFER_BYTES");
+
+        let dedup = settings.flag("DEDUP");
+
+        let min_ingest_level = settings
+            .parse_with("MIN_INGEST_LEVEL", LogLevel::parse)
+            .unwrap_or(LogLevel::Trace);
+
+        let cold_capacity = settings.parse("COLD_CAPACITY");
+
+        let snapshot_interval_secs = settings
+            .parse("SNAPSHOT_INTERVAL_SECS")
+            .filter(|&secs| secs > 0);
+
+        let snapshot_path = settings.get("SNAPSHOT_PATH").map(PathBuf::from);
+
+        let persist_buffer = settings.flag("PERSIST_BUFFER");
+
+        let persist_buffer_max_age_secs = settings
+            .parse("PERSIST_BUFFER_MAX_AGE_SECS")
+            .unwrap_or(DEFAULT_PERSIST_BUFFER_MAX_AGE_SECS);
+
+        let auto_save = settings.flag("AUTO_SAVE");
+
+        let auto_save_threshold = settings
+            .parse("AUTO_SAVE_THRESHOLD")
+            .filter(|&fraction: &f64| fraction > 0.0 && fraction <= 1.0)
+            .unwrap_or(DEFAULT_AUTO_SAVE_THRESHOLD);
+
+        let verbose = settings.flag("VERBOSE");
+
+        let verbose_levels = settings
+            .parse_with("VERBOSE_LEVELS", |list| {
+                list.split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(LogLevel::parse)
+                    .collect()
+            })
+            .unwrap_or_default();
 
-        let upload_dir = std::env::var("UPLOAD_DIR")
-            .ok()
+        let summary_top_sources = settings
+            .parse("SUMMARY_TOP_SOURCES")
+            .unwrap_or(DEFAULT_SUMMARY_TOP_SOURCES);
+
+        let upload_dir = settings
+            .get("UPLOAD_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|| PathBuf::from(DEFAULT_UPLOAD_DIR));
 
-        let jwt_public_key_path = std::env::var("JWT_PUBLIC_KEY_PATH").ok();
+        let storage_path_template = settings
+            .get("STORAGE_PATH_TEMPLATE")
+            .unwrap_or_else(|| DEFAULT_PATH_TEMPLATE.to_string());
+
+        let compress_uploads = settings.flag("COMPRESS_UPLOADS");
+
+        let upload_split_entries = settings
+            .parse("UPLOAD_SPLIT_ENTRIES")
+            .filter(|&entries| entries > 0);
+
+        let upload_cache = settings.flag("UPLOAD_CACHE");
+
+        let upload_cache_max_entries = settings
+            .parse("UPLOAD_CACHE_MAX_ENTRIES")
+            .unwrap_or(DEFAULT_MAX_CACHED_ENTRIES);
+
+        let storage_backend = settings
+            .get("STORAGE_BACKEND")
+            .map(|s| s.to_lowercase())
+            .unwrap_or_else(|| DEFAULT_STORAGE_BACKEND.to_string());
+
+        let archive_dir = settings.get("ARCHIVE_DIR").map(PathBuf::from);
+
+        let archive_by_source = settings.flag("ARCHIVE_BY_SOURCE");
+
+        let archive_min_level = settings
+            .parse_with("ARCHIVE_MIN_LEVEL", LogLevel::parse)
+            .unwrap_or(LogLevel::Trace);
+
+        let archive_compress = settings.flag("ARCHIVE_COMPRESS");
+
+        let log_file = settings.get("LOG_FILE").map(PathBuf::from);
+
+        let log_file_max_bytes = settings
+            .parse("LOG_FILE_MAX_BYTES")
+            .unwrap_or(DEFAULT_LOG_FILE_MAX_BYTES);
+
+        let log_file_max_files = settings
+            .parse("LOG_FILE_MAX_FILES")
+            .unwrap_or(DEFAULT_LOG_FILE_MAX_FILES);
+
+        let filter_presets = settings
+            .parse_with("FILTER_PRESETS", |json| presets::parse_presets(json).ok())
+            .unwrap_or_default();
+
+        let display_timezone = settings.parse("DISPLAY_TIMEZONE");
+
+        let jwt_public_key_path = settings.get("JWT_PUBLIC_KEY_PATH");
+
+        // A typo here would silently reject every token, so refuse to start
+        let jwt_algorithm = settings
+            .parse_with("JWT_ALGORITHM", |s| auth::parse_algorithm(s).ok())
+            .unwrap_or(Algorithm::RS256);
+
+        let tls_cert_path = settings.get("TLS_CERT_PATH").map(PathBuf::from);
+
+        let tls_key_path = settings.get("TLS_KEY_PATH").map(PathBuf::from);
+
+        let requests_persist_path = settings.get("REQUESTS_PERSIST_PATH").map(PathBuf::from);
+
+        let request_ttl_hours = settings
+            .parse("REQUEST_TTL_HOURS")
+            .unwrap_or(DEFAULT_REQUEST_TTL_HOURS);
+
+        let request_dedup_window_secs = settings
+            .parse("REQUEST_DEDUP_WINDOW_SECS")
+            .unwrap_or(DEFAULT_REQUEST_DEDUP_WINDOW_SECS);
+
+        let max_upload_age_days = settings.parse("MAX_UPLOAD_AGE_DAYS");
+
+        let max_upload_logs = settings.parse("MAX_UPLOAD_LOGS").filter(|&max| max > 0);
+
+        let max_upload_bytes = settings
+            .parse("MAX_UPLOAD_BYTES")
+            .filter(|&max| max > 0)
+            .unwrap_or(MAX_BODY_BYTES);
+
+        let upload_grace_secs = settings
+            .parse("UPLOAD_GRACE_SECS")
+            .unwrap_or(DEFAULT_UPLOAD_GRACE_SECS);
+
+        let delete_request_on_download = settings.flag("DELETE_REQUEST_ON_DOWNLOAD");
+
+        let poll_min_interval_ms = settings
+            .parse("POLL_MIN_INTERVAL_MS")
+            .unwrap_or(DEFAULT_POLL_MIN_INTERVAL_MS);
+
+        let poll_burst = settings.parse("POLL_BURST").unwrap_or(DEFAULT_POLL_BURST);
+
+        let ingest_rate_per_sec = settings
+            .parse("INGEST_RATE_PER_SEC")
+            .unwrap_or(DEFAULT_INGEST_RATE_PER_SEC);
+
+        let ingest_burst = settings
+            .parse("INGEST_BURST")
+            .unwrap_or(DEFAULT_INGEST_BURST);
+
+        let sse_retry_ms = settings
+            .parse("SSE_RETRY_MS")
+            .unwrap_or(DEFAULT_SSE_RETRY_MS);
+
+        let cleanup_interval_secs = settings
+            .parse("CLEANUP_INTERVAL_SECS")
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_CLEANUP_INTERVAL_SECS);
+
+        let retention_days = settings
+            .parse("RETENTION_DAYS")
+            .filter(|&days| days > 0)
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+
+        let sse_keepalive_secs = settings
+            .parse("SSE_KEEPALIVE_SECS")
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_SSE_KEEPALIVE_SECS);
+
+        let webhook_signing_secret = settings.get("WEBHOOK_SIGNING_SECRET");
+
+        let alert_webhook_url = settings.get("ALERT_WEBHOOK_URL");
+
+        let deadman_sources = settings.list("DEADMAN_SOURCES");
+
+        let deadman_interval_secs = settings
+            .parse("DEADMAN_INTERVAL_SECS")
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_DEADMAN_INTERVAL_SECS);
+
+        let required_metadata_keys = settings.list("REQUIRED_METADATA_KEYS");
+
+        let global_tags = settings.list("GLOBAL_TAGS");
+
+        let max_tags_per_entry = settings.parse("MAX_TAGS_PER_ENTRY");
+
+        let ingest_ip_allowlist = settings.list("INGEST_IP_ALLOWLIST");
+
+        let trust_forwarded_for = settings.flag("TRUST_FORWARDED_FOR");
+
+        let geoip_database = settings.get("GEOIP_DATABASE").map(PathBuf::from);
+
+        let strict_json = settings.flag("STRICT_JSON");
+
+        let strict_device_ids = settings.flag("STRICT_DEVICE_IDS");
+
+        let strict_levels = settings.flag("STRICT_LEVELS");
+
+        let max_message_bytes = settings
+            .parse("MAX_MESSAGE_BYTES")
+            .filter(|&bytes| bytes > 0)
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES);
+
+        let normalize_messages = settings.flag("NORMALIZE_MESSAGES");
+
+        let infer_source_from_file = settings.flag("INFER_SOURCE_FROM_FILE");
+
+        let track_server_fields = settings.flag("TRACK_SERVER_FIELDS");
+
+        let reject_future_timestamps = settings.flag("REJECT_FUTURE_TIMESTAMPS");
+
+        let clamp_future_timestamps = settings.flag("CLAMP_FUTURE_TIMESTAMPS");
+
+        let future_timestamp_tolerance_secs = settings
+            .parse("FUTURE_TIMESTAMP_TOLERANCE_SECS")
+            .unwrap_or(DEFAULT_FUTURE_TIMESTAMP_TOLERANCE_SECS);
 
         Self {
             port,
             capacity,
+            max_buffer_bytes,
+            dedup,
+            min_ingest_level,
+            cold_capacity,
+            snapshot_interval_secs,
+            snapshot_path,
+            persist_buffer,
+            persist_buffer_max_age_secs,
+            auto_save,
+            auto_save_threshold,
             verbose,
+            verbose_levels,
+            summary_top_sources,
             upload_dir,
+            storage_path_template,
+            compress_uploads,
+            upload_split_entries,
+            upload_cache,
+            upload_cache_max_entries,
+            storage_backend,
+            archive_dir,
+            archive_by_source,
+            archive_min_level,
+            archive_compress,
+            log_file,
+            log_file_max_bytes,
+            log_file_max_files,
+            filter_presets,
+            display_timezone,
             jwt_public_key_path,
+            jwt_algorithm,
+            tls_cert_path,
+            tls_key_path,
+            requests_persist_path,
+            request_ttl_hours,
+            request_dedup_window_secs,
+            max_upload_age_days,
+            max_upload_logs,
+            max_upload_bytes,
+            upload_grace_secs,
+            delete_request_on_download,
+            poll_min_interval_ms,
+            poll_burst,
+            ingest_rate_per_sec,
+            ingest_burst,
+            sse_retry_ms,
+            cleanup_interval_secs,
+            retention_days,
+            sse_keepalive_secs,
+            webhook_signing_secret,
+            alert_webhook_url,
+            deadman_sources,
+            deadman_interval_secs,
+            required_metadata_keys,
+            global_tags,
+            max_tags_per_entry,
+            ingest_ip_allowlist,
+            trust_forwarded_for,
+            geoip_database,
+            strict_json,
+            strict_device_ids,
+            strict_levels,
+            max_message_bytes,
+            normalize_messages,
+            infer_source_from_file,
+            track_server_fields,
+            reject_future_timestamps,
+            clamp_future_timestamps,
+            future_timestamp_tolerance_secs,
+        }
+    }
+
+    /// Ingest limits clients can check against before sending
+    pub fn limits(&self) -> ServerLimits {
+        let enforces_future_timestamps =
+            self.reject_future_timestamps || self.clamp_future_timestamps;
+
+        ServerLimits {
+            max_body_bytes: MAX_BODY_BYTES,
+            buffer_capacity: self.capacity,
+            max_buffer_bytes: self.max_buffer_bytes,
+            max_message_bytes: self.max_message_bytes,
+            min_ingest_level: self.min_ingest_level.as_str(),
+            required_metadata_keys: self.required_metadata_keys.clone(),
+            max_tags_per_entry: self.max_tags_per_entry,
+            max_upload_age_days: self.max_upload_age_days,
+            max_upload_logs: self.max_upload_logs,
+            max_upload_bytes: self.max_upload_bytes,
+            future_timestamp_tolerance_secs: enforces_future_timestamps
+                .then_some(self.future_timestamp_tolerance_secs),
+            poll_min_interval_ms: self.poll_min_interval_ms,
+            poll_burst: self.poll_burst,
+            ingest_rate_per_sec: self.ingest_rate_per_sec,
+            ingest_burst: self.ingest_burst,
         }
     }
 }
 
+/// Configured ingest limits, as advertised by `GET /limits`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServerLimits {
+    /// Largest accepted request body
+    #[serde(rename = "maxBodyBytes")]
+    pub max_body_bytes: usize,
+
+    /// Entries kept in the live buffer
+    #[serde(rename = "bufferCapacity")]
+    pub buffer_capacity: usize,
+
+    /// Memory bound of the live buffer, if any
+    #[serde(rename = "maxBufferBytes")]
+    pub max_buffer_bytes: Option<usize>,
+
+    /// Longest message stored without truncation
+    #[serde(rename = "maxMessageBytes")]
+    pub max_message_bytes: usize,
+
+    /// Lowest level stored; lower entries are accepted with 202 and dropped
+    #[serde(rename = "minIngestLevel")]
+    pub min_ingest_level: &'static str,
+
+    /// Metadata keys every entry must carry
+    #[serde(rename = "requiredMetadataKeys")]
+    pub required_metadata_keys: Vec<String>,
+
+    /// Client tags kept per entry, if limited
+    #[serde(rename = "maxTagsPerEntry")]
+    pub max_tags_per_entry: Option<usize>,
+
+    /// Oldest accepted `fromTimestamp` on uploads, if limited
+    #[serde(rename = "maxUploadAgeDays")]
+    pub max_upload_age_days: Option<i64>,
+
+    /// Most entries accepted in one upload, if limited
+    #[serde(rename = "maxUploadLogs")]
+    pub max_upload_logs: Option<usize>,
+
+    /// Largest accepted upload body
+    #[serde(rename = "maxUploadBytes")]
+    pub max_upload_bytes: usize,
+
+    /// Allowed clock skew when future timestamps are rejected or clamped
+    #[serde(rename = "futureTimestampToleranceSecs")]
+    pub future_timestamp_tolerance_secs: Option<i64>,
+
+    /// Minimum spacing of polls per device (0 = unlimited)
+    #[serde(rename = "pollMinIntervalMs")]
+    pub poll_min_interval_ms: u64,
+
+    /// Polls allowed back to back
+    #[serde(rename = "pollBurst")]
+    pub poll_burst: u32,
+
+    /// Entries accepted per second from one source on POST /logs (0 = unlimited)
+    #[serde(rename = "ingestRatePerSec")]
+    pub ingest_rate_per_sec: u32,
+
+    /// Entries a source may send back to back
+    #[serde(rename = "ingestBurst")]
+    pub ingest_burst: u32,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             port: DEFAULT_PORT,
             capacity: DEFAULT_CAPACITY,
+            max_buffer_bytes: None,
+            dedup: false,
+            min_ingest_level: LogLevel::Trace,
+            cold_capacity: None,
+            snapshot_interval_secs: None,
+            snapshot_path: None,
+            persist_buffer: false,
+            persist_buffer_max_age_secs: DEFAULT_PERSIST_BUFFER_MAX_AGE_SECS,
+            auto_save: false,
+            auto_save_threshold: DEFAULT_AUTO_SAVE_THRESHOLD,
             verbose: false,
+            verbose_levels: Vec::new(),
+            summary_top_sources: DEFAULT_SUMMARY_TOP_SOURCES,
             upload_dir: PathBuf::from(DEFAULT_UPLOAD_DIR),
+            storage_path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+            compress_uploads: false,
+            upload_split_entries: None,
+            upload_cache: false,
+            upload_cache_max_entries: DEFAULT_MAX_CACHED_ENTRIES,
+            storage_backend: DEFAULT_STORAGE_BACKEND.to_string(),
+            archive_dir: None,
+            archive_by_source: false,
+            archive_min_level: LogLevel::Trace,
+            archive_compress: false,
+            log_file: None,
+            log_file_max_bytes: DEFAULT_LOG_FILE_MAX_BYTES,
+            log_file_max_files: DEFAULT_LOG_FILE_MAX_FILES,
+            filter_presets: HashMap::new(),
+            display_timezone: None,
             jwt_public_key_path: None,
+            jwt_algorithm: Algorithm::RS256,
+            tls_cert_path: None,
+            tls_key_path: None,
+            requests_persist_path: None,
+            request_ttl_hours: DEFAULT_REQUEST_TTL_HOURS,
+            request_dedup_window_secs: DEFAULT_REQUEST_DEDUP_WINDOW_SECS,
+            max_upload_age_days: None,
+            max_upload_logs: None,
+            max_upload_bytes: MAX_BODY_BYTES,
+            upload_grace_secs: DEFAULT_UPLOAD_GRACE_SECS,
+            delete_request_on_download: false,
+            poll_min_interval_ms: DEFAULT_POLL_MIN_INTERVAL_MS,
+            poll_burst: DEFAULT_POLL_BURST,
+            ingest_rate_per_sec: DEFAULT_INGEST_RATE_PER_SEC,
+            ingest_burst: DEFAULT_INGEST_BURST,
+            sse_retry_ms: DEFAULT_SSE_RETRY_MS,
+            cleanup_interval_secs: DEFAULT_CLEANUP_INTERVAL_SECS,
+            retention_days: DEFAULT_RETENTION_DAYS,
+            sse_keepalive_secs: DEFAULT_SSE_KEEPALIVE_SECS,
+            webhook_signing_secret: None,
+            alert_webhook_url: None,
+            deadman_sources: Vec::new(),
+            deadman_interval_secs: DEFAULT_DEADMAN_INTERVAL_SECS,
+            required_metadata_keys: Vec::new(),
+            global_tags: Vec::new(),
+            max_tags_per_entry: None,
+            ingest_ip_allowlist: Vec::new(),
+            trust_forwarded_for: false,
+            geoip_database: None,
+            strict_json: false,
+            strict_device_ids: false,
+            strict_levels: false,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            normalize_messages: false,
+            infer_source_from_file: false,
+            track_server_fields: false,
+            reject_future_timestamps: false,
+            clamp_future_timestamps: false,
+            future_timestamp_tolerance_secs: DEFAULT_FUTURE_TIMESTAMP_TOLERANCE_SECS,
+        }
+    }
+}
+
+/// Errors loading or validating the configuration
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("config file must hold a JSON object")]
+    NotAnObject,
+
+    #[error("unsupported value for '{0}'; expected a string, number, boolean, array or object")]
+    UnsupportedValue(String),
+
+    #[error("unknown keys: {0}")]
+    UnknownKeys(String),
+
+    #[error("invalid values: {0}")]
+    InvalidValues(String),
+
+    #[error("CAPACITY must be at least 1")]
+    ZeroCapacity,
+
+    #[error("TLS_CERT_PATH and TLS_KEY_PATH must be set together")]
+    IncompleteTls,
+
+    #[error("parent directory of UPLOAD_DIR does not exist: {}", .0.display())]
+    MissingUploadDirParent(PathBuf),
+}
+
+/// Raw setting values by environment variable name
+///
+/// Environment values win over file values.
+struct Settings {
+    env: HashMap<String, String>,
+    file: HashMap<String, String>,
+    /// Names looked up so far, to spot file keys no setting reads
+    read: RefCell<HashSet<String>>,
+    /// `NAME="value"` for each value that failed to parse
+    invalid: RefCell<Vec<String>>,
+}
+
+impl Settings {
+    fn from_env() -> Self {
+        Self::new(env_vars())
+    }
+
+    fn new(env: HashMap<String, String>) -> Self {
+        Self {
+            env,
+            file: HashMap::new(),
+            read: RefCell::new(HashSet::new()),
+            invalid: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Add the settings of a JSON config file, keyed by lowercase variable name
+    fn with_file(mut self, json: &str) -> Result<Self, ConfigError> {
+        let Value::Object(object) = serde_json::from_str(json)? else {
+            return Err(ConfigError::NotAnObject);
+        };
+
+        for (key, value) in object {
+            let value = match value {
+                Value::Null => continue,
+                Value::String(s) => s,
+                Value::Number(_) | Value::Bool(_) | Value::Object(_) => value.to_string(),
+                // Lists are comma-separated in the environment
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| match item {
+                        Value::String(s) => Ok(s.clone()),
+                        Value::Number(_) | Value::Bool(_) => Ok(item.to_string()),
+                        _ => Err(ConfigError::UnsupportedValue(key.clone())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(","),
+            };
+            self.file.insert(key.to_uppercase(), value);
+        }
+        Ok(self)
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        self.read.borrow_mut().insert(name.to_string());
+        self.env.get(name).or_else(|| self.file.get(name)).cloned()
+    }
+
+    /// Parse a setting; an empty value counts as unset and one that doesn't
+    /// parse is recorded as invalid
+    fn parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.parse_with(name, |value| value.parse().ok())
+    }
+
+    /// Like [`parse`](Self::parse), with a parser for types that aren't `FromStr`
+    fn parse_with<T>(&self, name: &str, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+        let value = self.get(name).filter(|value| !value.is_empty())?;
+        let parsed = parse(&value);
+        if parsed.is_none() {
+            self.invalid
+                .borrow_mut()
+                .push(format!("{}={:?}", name, value));
         }
+        parsed
     }
+
+    /// Read a boolean flag ("1" or "true" enables it)
+    fn flag(&self, name: &str) -> bool {
+        self.get(name)
+            .map(|v| v == "1" || v.to_lowercase() == "true")
+            .unwrap_or(false)
+    }
+
+    /// Read a comma-separated list, skipping empty items
+    fn list(&self, name: &str) -> Vec<String> {
+        self.get(name)
+            .map(|s| {
+                s.split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// File keys (in lowercase, as written) that no setting looked up
+    fn unread_file_keys(&self) -> Vec<String> {
+        let read = self.read.borrow();
+        let mut unread: Vec<String> = self
+            .file
+            .keys()
+            .filter(|name| !read.contains(*name))
+            .map(|name| name.to_lowercase())
+            .collect();
+        unread.sort();
+        unread
+    }
+}
+
+/// The process environment, skipping variables that aren't valid UTF-8
+fn env_vars() -> HashMap<String, String> {
+    std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
 }
 
 #[cfg(test)]
@@ -87,8 +993,224 @@ mod tests {
         let config = Config::default();
         assert_eq!(config.port, 9006);
         assert_eq!(config.capacity, 10_000);
+        assert!(config.max_buffer_bytes.is_none());
+        assert!(!config.dedup);
+        assert_eq!(config.min_ingest_level, LogLevel::Trace);
+        assert!(config.cold_capacity.is_none());
+        assert!(config.snapshot_interval_secs.is_none());
+        assert!(config.snapshot_path.is_none());
+        assert!(!config.persist_buffer);
+        assert_eq!(config.persist_buffer_max_age_secs, 86_400);
+        assert!(!config.auto_save);
+        assert_eq!(config.auto_save_threshold, 0.9);
         assert!(!config.verbose);
+        assert!(config.verbose_levels.is_empty());
+        assert_eq!(config.summary_top_sources, 5);
         assert_eq!(config.upload_dir, PathBuf::from("./uploads"));
+        assert_eq!(
+            config.storage_path_template,
+            "{user}/{device}/{request}.jsonl"
+        );
+        assert!(!config.compress_uploads);
+        assert!(config.upload_split_entries.is_none());
+        assert!(!config.upload_cache);
+        assert_eq!(config.upload_cache_max_entries, 100_000);
+        assert_eq!(config.storage_backend, "files");
+        assert!(config.archive_dir.is_none());
+        assert!(!config.archive_by_source);
+        assert_eq!(config.archive_min_level, LogLevel::Trace);
+        assert!(!config.archive_compress);
+        assert!(config.log_file.is_none());
+        assert_eq!(config.log_file_max_bytes, 10 * 1024 * 1024);
+        assert_eq!(config.log_file_max_files, 5);
+        assert!(config.filter_presets.is_empty());
+        assert!(config.display_timezone.is_none());
         assert!(config.jwt_public_key_path.is_none());
+        assert_eq!(config.jwt_algorithm, Algorithm::RS256);
+        assert!(config.tls().is_none());
+        assert!(config.requests_persist_path.is_none());
+        assert_eq!(config.request_ttl_hours, 24);
+        assert_eq!(config.request_dedup_window_secs, 0);
+        assert!(config.max_upload_age_days.is_none());
+        assert!(config.max_upload_logs.is_none());
+        assert_eq!(config.max_upload_bytes, 2 * 1024 * 1024);
+        assert_eq!(config.upload_grace_secs, 300);
+        assert!(!config.delete_request_on_download);
+        assert_eq!(config.poll_min_interval_ms, 0);
+        assert_eq!(config.poll_burst, 1);
+        assert_eq!(config.ingest_rate_per_sec, 0);
+        assert_eq!(config.ingest_burst, 50);
+        assert_eq!(config.sse_retry_ms, 5_000);
+        assert_eq!(config.cleanup_interval_secs, 3_600);
+        assert_eq!(config.retention_days, 7);
+        assert_eq!(config.sse_keepalive_secs, 15);
+        assert!(config.webhook_signing_secret.is_none());
+        assert!(config.alert_webhook_url.is_none());
+        assert!(config.deadman_sources.is_empty());
+        assert_eq!(config.deadman_interval_secs, 300);
+        assert!(config.required_metadata_keys.is_empty());
+        assert!(config.global_tags.is_empty());
+        assert!(config.max_tags_per_entry.is_none());
+        assert!(config.ingest_ip_allowlist.is_empty());
+        assert!(!config.trust_forwarded_for);
+        assert!(config.geoip_database.is_none());
+        assert!(!config.strict_json);
+        assert!(!config.strict_device_ids);
+        assert!(!config.strict_levels);
+        assert_eq!(config.max_message_bytes, 65_536);
+        assert!(!config.normalize_messages);
+        assert!(!config.infer_source_from_file);
+        assert!(!config.track_server_fields);
+        assert!(!config.reject_future_timestamps);
+        assert!(!config.clamp_future_timestamps);
+        assert_eq!(config.future_timestamp_tolerance_secs, 60);
+    }
+
+    #[test]
+    fn test_file_settings_under_env() {
+        let json = r#"{
+            "port": 9100,
+            "capacity": 500,
+            "verbose": true,
+            "global_tags": ["dev", "local"],
+            "filter_presets": { "errors": { "minLevel": "error" } },
+            "max_upload_logs": null
+        }"#;
+        let env = HashMap::from([("CAPACITY".to_string(), "2000".to_string())]);
+
+        let config = Config::from_json(env, json).unwrap();
+        assert_eq!(config.port, 9100);
+        assert_eq!(config.capacity, 2_000);
+        assert!(config.verbose);
+        assert_eq!(config.global_tags, ["dev", "local"]);
+        assert!(config.filter_presets.contains_key("errors"));
+        assert!(config.max_upload_logs.is_none());
+        assert_eq!(config.poll_burst, DEFAULT_POLL_BURST);
+    }
+
+    #[test]
+    fn test_config_file_rejects_unknown_keys() {
+        let err =
+            Config::from_json(HashMap::new(), r#"{"port": 9100, "capcity": 10}"#).unwrap_err();
+        assert!(matches!(err, ConfigError::UnknownKeys(ref keys) if keys == "capcity"));
+
+        assert!(matches!(
+            Config::from_json(HashMap::new(), "[]").unwrap_err(),
+            ConfigError::NotAnObject
+        ));
+    }
+
+    fn load_with(vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let env = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Config::from_settings(&Settings::new(env))
+    }
+
+    #[test]
+    fn test_unparseable_values_rejected() {
+        let err = load_with(&[("PORT", "abc"), ("POLL_BURST", "-1")]).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::InvalidValues(ref values) if values == r#"PORT="abc", POLL_BURST="-1""#),
+            "{err}"
+        );
+
+        // Empty values fall back to the default
+        assert_eq!(load_with(&[("PORT", "")]).unwrap().port, DEFAULT_PORT);
+    }
+
+    #[test]
+    fn test_unknown_levels_rejected() {
+        for name in ["MIN_INGEST_LEVEL", "ARCHIVE_MIN_LEVEL"] {
+            let err = load_with(&[(name, "verbose")]).unwrap_err();
+            assert!(
+                matches!(err, ConfigError::InvalidValues(ref values) if *values == format!(r#"{name}="verbose""#)),
+                "{err}"
+            );
+        }
+
+        let config = load_with(&[
+            ("MIN_INGEST_LEVEL", "WARNING"),
+            ("ARCHIVE_MIN_LEVEL", "error"),
+        ])
+        .unwrap();
+        assert_eq!(config.min_ingest_level, LogLevel::Warning);
+        assert_eq!(config.archive_min_level, LogLevel::Error);
+    }
+
+    #[test]
+    fn test_unknown_verbose_level_rejected() {
+        let err = load_with(&[("VERBOSE_LEVELS", "error, verbose")]).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::InvalidValues(ref values) if values == r#"VERBOSE_LEVELS="error, verbose""#),
+            "{err}"
+        );
+
+        let config = load_with(&[("VERBOSE_LEVELS", "error, critical")]).unwrap();
+        assert_eq!(config.verbose_levels, [LogLevel::Error, LogLevel::Critical]);
+    }
+
+    #[test]
+    fn test_invalid_filter_presets_rejected() {
+        for json in [r#"{"auth":"#, r#"{"auth":{"minLevel":"verbose"}}"#] {
+            let err = load_with(&[("FILTER_PRESETS", json)]).unwrap_err();
+            assert!(
+                matches!(err, ConfigError::InvalidValues(ref values) if values.starts_with("FILTER_PRESETS=")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unsupported_jwt_algorithm_rejected() {
+        let err = load_with(&[("JWT_ALGORITHM", "HS256")]).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::InvalidValues(ref values) if values == r#"JWT_ALGORITHM="HS256""#),
+            "{err}"
+        );
+
+        let config = load_with(&[("JWT_ALGORITHM", "es256")]).unwrap();
+        assert_eq!(config.jwt_algorithm, Algorithm::ES256);
+    }
+
+    #[test]
+    fn test_zero_capacity_rejected() {
+        assert!(matches!(
+            load_with(&[("CAPACITY", "0")]).unwrap_err(),
+            ConfigError::ZeroCapacity
+        ));
+        assert_eq!(load_with(&[("CAPACITY", "1")]).unwrap().capacity, 1);
+    }
+
+    #[test]
+    fn test_upload_dir_parent_must_exist() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing/uploads");
+        let err = load_with(&[("UPLOAD_DIR", missing.to_str().unwrap())]).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::MissingUploadDirParent(ref parent) if parent == &dir.path().join("missing"))
+        );
+
+        let nested = dir.path().join("uploads");
+        assert!(load_with(&[("UPLOAD_DIR", nested.to_str().unwrap())]).is_ok());
+        assert!(load_with(&[("UPLOAD_DIR", "uploads")]).is_ok());
+    }
+
+    #[test]
+    fn test_tls_needs_cert_and_key() {
+        let config =
+            load_with(&[("TLS_CERT_PATH", "cert.pem"), ("TLS_KEY_PATH", "key.pem")]).unwrap();
+        assert_eq!(
+            config.tls(),
+            Some((Path::new("cert.pem"), Path::new("key.pem")))
+        );
+
+        for half in [("TLS_CERT_PATH", "cert.pem"), ("TLS_KEY_PATH", "key.pem")] {
+            assert!(matches!(
+                load_with(&[half]).unwrap_err(),
+                ConfigError::IncompleteTls
+            ));
+        }
     }
 }
@@ -0,0 +1,222 @@
+//! Dead-man's switch for sources that should log regularly.
+//!
+//! Tracks when each watched source was last heard from and alerts once a
+//! source stays silent longer than the configured interval.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::webhook::WebhookClient;
+
+/// Event name in the alert payload
+pub const DEADMAN_EVENT: &str = "deadman";
+
+/// Alert sent when a watched source goes quiet
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceAlert {
+    pub event: &'static str,
+    pub source: String,
+    /// Last entry from the source, `None` if it hasn't logged since startup
+    pub last_seen: Option<String>,
+    pub silent_for_secs: i64,
+    pub interval_secs: u64,
+}
+
+/// Last-seen tracking for the watched sources
+pub struct DeadManSwitch {
+    interval: Duration,
+    started_at: DateTime<Utc>,
+    last_seen: Mutex<HashMap<String, Option<DateTime<Utc>>>>,
+    /// Sources already alerted for their current silence
+    alerted: Mutex<HashSet<String>>,
+}
+
+impl DeadManSwitch {
+    pub fn new(sources: &[String], interval: Duration, now: DateTime<Utc>) -> Self {
+        Self {
+            interval,
+            started_at: now,
+            last_seen: Mutex::new(sources.iter().map(|s| (s.clone(), None)).collect()),
+            alerted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Note an entry from `source`; unwatched sources are ignored
+    pub fn record(&self, source: &str, now: DateTime<Utc>) {
+        if let Some(last_seen) = self.last_seen.lock().get_mut(source) {
+            *last_seen = Some(now);
+            self.alerted.lock().remove(source);
+        }
+    }
+
+    /// Alerts for sources silent longer than the interval
+    ///
+    /// Each silence alerts once; a source alerts again only after it has
+    /// logged and then gone quiet again. Sources that never logged are timed
+    /// from startup.
+    pub fn check(&self, now: DateTime<Utc>) -> Vec<SilenceAlert> {
+        let interval = chrono::Duration::from_std(self.interval).unwrap_or(chrono::Duration::MAX);
+        let last_seen = self.last_seen.lock();
+        let mut alerted = self.alerted.lock();
+
+        let mut alerts: Vec<SilenceAlert> = last_seen
+            .iter()
+            .filter_map(|(source, seen)| {
+                let silent_for = now - seen.unwrap_or(self.started_at);
+                if silent_for <= interval || !alerted.insert(source.clone()) {
+                    return None;
+                }
+                Some(SilenceAlert {
+                    event: DEADMAN_EVENT,
+                    source: source.clone(),
+                    last_seen: seen.map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true)),
+                    silent_for_secs: silent_for.num_seconds(),
+                    interval_secs: self.interval.as_secs(),
+                })
+            })
+            .collect();
+        alerts.sort_by(|a, b| a.source.cmp(&b.source));
+        alerts
+    }
+
+    /// How often the background checker runs
+    fn check_period(&self) -> Duration {
+        (self.interval / 4).max(Duration::from_millis(10))
+    }
+}
+
+/// Check for silent sources until the process exits
+///
+/// Alerts are always logged, and posted to `webhook` when one is configured.
+pub async fn run(switch: Arc<DeadManSwitch>, webhook: Option<WebhookClient>) {
+    let mut ticker = tokio::time::interval(switch.check_period());
+    loop {
+        ticker.tick().await;
+
+        for alert in switch.check(Utc::now()) {
+            tracing::warn!(
+                source = %alert.source,
+                silent_for_secs = alert.silent_for_secs,
+                "No logs from watched source"
+            );
+            if let Some(webhook) = &webhook {
+                if let Err(e) = webhook.send(&alert).await {
+                    tracing::warn!(error = %e, source = %alert.source, "Failed to deliver dead-man alert");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::HeaderMap, routing::post, Json, Router};
+    use tokio::sync::mpsc;
+
+    fn sources(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_alerts_once_per_silence() {
+        let start = Utc::now();
+        let switch = DeadManSwitch::new(&sources(&["ios"]), Duration::from_secs(60), start);
+
+        assert!(switch
+            .check(start + chrono::Duration::seconds(30))
+            .is_empty());
+
+        let alerts = switch.check(start + chrono::Duration::seconds(61));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].source, "ios");
+        assert_eq!(alerts[0].last_seen, None);
+        assert!(switch
+            .check(start + chrono::Duration::seconds(90))
+            .is_empty());
+
+        // Logging again re-arms the switch
+        switch.record("ios", start + chrono::Duration::seconds(100));
+        assert!(switch
+            .check(start + chrono::Duration::seconds(150))
+            .is_empty());
+        assert_eq!(
+            switch.check(start + chrono::Duration::seconds(161)).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_ignores_unwatched_sources() {
+        let start = Utc::now();
+        let switch = DeadManSwitch::new(&sources(&["ios"]), Duration::from_secs(60), start);
+
+        switch.record("cli", start + chrono::Duration::seconds(10));
+
+        let alerts = switch.check(start + chrono::Duration::seconds(120));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].source, "ios");
+    }
+
+    async fn capture_webhook(
+        State(tx): State<mpsc::UnboundedSender<(HeaderMap, serde_json::Value)>>,
+        headers: HeaderMap,
+        Json(body): Json<serde_json::Value>,
+    ) {
+        tx.send((headers, body)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_alert_fires_after_silence() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new()
+            .route("/hook", post(capture_webhook))
+            .with_state(tx);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let switch = Arc::new(DeadManSwitch::new(
+            &sources(&["ios", "cli"]),
+            Duration::from_millis(100),
+            Utc::now(),
+        ));
+        let webhook = WebhookClient::new(format!("http://{}/hook", addr), Some("secret".into()));
+        let checker = tokio::spawn(run(switch.clone(), Some(webhook)));
+
+        // "cli" keeps logging while "ios" stays silent
+        let keep_alive = {
+            let switch = switch.clone();
+            tokio::spawn(async move {
+                loop {
+                    switch.record("cli", Utc::now());
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+            })
+        };
+
+        let (headers, body) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("alert should fire")
+            .unwrap();
+        assert_eq!(body["event"], "deadman");
+        assert_eq!(body["source"], "ios");
+        assert!(body["lastSeen"].is_null());
+        assert!(headers
+            .get(crate::webhook::SIGNATURE_HEADER)
+            .is_some_and(|v| v.to_str().unwrap().starts_with("sha256=")));
+
+        // No repeat alert for the same silence, and none for the live source
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(rx.try_recv().is_err());
+
+        checker.abort();
+        keep_alive.abort();
+    }
+}
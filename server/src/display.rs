@@ -6,10 +6,26 @@ use std::path::Path;
 
 use colored::Colorize;
 
-use crate::models::LogEntry;
+use crate::metrics::Metrics;
+use crate::models::{LogEntry, LogLevel};
+
+/// Width of level labels, wide enough for the longest name (CRITICAL)
+const LEVEL_LABEL_WIDTH: usize = 8;
+
+/// Width of the label column in the stats summary table
+const SUMMARY_LABEL_WIDTH: usize = 20;
 
 /// Display a log entry in the terminal with color coding
-pub fn display_log(entry: &LogEntry, verbose: bool) {
+///
+/// When `verbose_levels` is non-empty it decides the layout per entry: those
+/// levels are shown verbosely and the rest compactly, whatever `verbose` says.
+pub fn display_log(entry: &LogEntry, verbose: bool, verbose_levels: &[LogLevel]) {
+    let verbose = shows_verbose(&entry.level, verbose, verbose_levels);
+    println!("{}", render_log(entry, verbose));
+}
+
+/// Terminal lines for an entry, without the trailing newline
+fn render_log(entry: &LogEntry, verbose: bool) -> String {
     let timestamp = format_timestamp(&entry.timestamp);
     let level_colored = colorize_level(&entry.level);
     let source_label = format_source(&entry.source);
@@ -17,23 +33,91 @@ pub fn display_log(entry: &LogEntry, verbose: bool) {
     if verbose {
         // Verbose: [timestamp] LEVEL [source] [file:line] message
         let location = format_location(&entry.file, entry.line);
-        println!(
+        let mut output = format!(
             "{} {} {} {} {}",
             timestamp, level_colored, source_label, location, entry.message
         );
 
         // Print metadata if present
-        if !entry.metadata.is_empty() {
-            for (key, value) in &entry.metadata {
-                println!("{}", format!("  {}={}", key, value).bright_black());
-            }
+        for (key, value) in &entry.metadata {
+            output.push('\n');
+            output.push_str(&format!("  {}={}", key, value).bright_black().to_string());
         }
+        output
     } else {
         // Compact: [timestamp] LEVEL [source] message
-        println!(
+        format!(
             "{} {} {} {}",
             timestamp, level_colored, source_label, entry.message
-        );
+        )
+    }
+}
+
+/// Stats table printed to the terminal on SIGUSR1
+///
+/// Plain text so it reads the same when stdout is piped to a file.
+pub fn render_summary(metrics: &Metrics, top_sources: &[(String, usize)]) -> String {
+    let mut lines = vec![format!(
+        "{:<2$}{}",
+        "Buffered entries",
+        format!("{} / {}", metrics.count, metrics.capacity),
+        SUMMARY_LABEL_WIDTH
+    )];
+
+    lines.push(String::new());
+    lines.push("Levels".to_string());
+    let mut levels: Vec<(&&str, &usize)> = metrics.levels.iter().collect();
+    levels.sort_by_key(|(level, _)| LogLevel::from_str(level));
+    for (level, count) in levels {
+        lines.push(summary_row(&level.to_uppercase(), *count));
+    }
+
+    if !top_sources.is_empty() {
+        lines.push(String::new());
+        lines.push("Top sources".to_string());
+        for (source, count) in top_sources {
+            lines.push(summary_row(source, *count));
+        }
+    }
+
+    let requests = &metrics.requests;
+    lines.push(String::new());
+    lines.push("Log requests".to_string());
+    for (status, count) in [
+        ("pending", requests.pending),
+        ("fulfilled", requests.fulfilled),
+        ("expired", requests.expired),
+        ("cancelled", requests.cancelled),
+        ("total", requests.total),
+    ] {
+        lines.push(summary_row(status, count));
+    }
+
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let rule = "─".repeat(width + 2);
+    let mut table = format!("┌{}┐\n", rule);
+    for line in &lines {
+        table.push_str(&format!("│ {:<1$} │\n", line, width));
+    }
+    table.push_str(&format!("└{}┘", rule));
+    table
+}
+
+/// Indented `label    count` row of the summary table
+fn summary_row(label: &str, count: usize) -> String {
+    format!("  {:<1$}{2:>6}", label, SUMMARY_LABEL_WIDTH - 2, count)
+}
+
+/// Whether an entry at `level` is rendered verbosely
+fn shows_verbose(level: &str, verbose: bool, verbose_levels: &[LogLevel]) -> bool {
+    if verbose_levels.is_empty() {
+        verbose
+    } else {
+        verbose_levels.contains(&LogLevel::from_str(level))
     }
 }
 
@@ -59,9 +143,14 @@ fn format_location(file: &str, line: u32) -> String {
         .to_string()
 }
 
+/// Uppercase level name padded to a fixed width so columns line up
+fn level_label(level: &str) -> String {
+    format!("{:<1$}", level.to_uppercase(), LEVEL_LABEL_WIDTH)
+}
+
 /// Colorize log level based on severity
 fn colorize_level(level: &str) -> String {
-    let level_upper = level.to_uppercase();
+    let level_upper = level_label(level);
     match level.to_lowercase().as_str() {
         "trace" | "debug" => level_upper.bright_black().to_string(),
         "info" => level_upper.green().to_string(),
@@ -85,6 +174,90 @@ mod tests {
         assert!(location.contains("42"));
     }
 
+    #[test]
+    fn test_render_summary_table() {
+        let metrics = Metrics {
+            count: 3,
+            capacity: 100,
+            levels: std::collections::BTreeMap::from([("error", 1), ("info", 2)]),
+            requests: crate::request_manager::RequestStats {
+                total: 2,
+                pending: 1,
+                fulfilled: 1,
+                ..Default::default()
+            },
+        };
+        let sources = [("ios".to_string(), 2), ("cli".to_string(), 1)];
+
+        let table = render_summary(&metrics, &sources);
+        let lines: Vec<&str> = table.lines().collect();
+
+        // Every row is boxed to the same width
+        let width = lines[0].chars().count();
+        assert!(lines.iter().all(|line| line.chars().count() == width));
+        assert!(lines[0].starts_with('┌') && lines[lines.len() - 1].starts_with('└'));
+
+        assert!(table.contains("│ Buffered entries    3 / 100"));
+        assert!(table.contains("  ERROR                  1 "));
+        assert!(table.contains("  ios                    2 "));
+        assert!(table.contains("  pending                1 "));
+        assert!(table.find("INFO") < table.find("ERROR"));
+        assert!(table.find("Top sources") < table.find("Log requests"));
+
+        assert!(!render_summary(&metrics, &[]).contains("Top sources"));
+    }
+
+    #[test]
+    fn test_level_label_fixed_width() {
+        for level in ["trace", "info", "warning", "critical", "custom"] {
+            assert_eq!(level_label(level).len(), LEVEL_LABEL_WIDTH);
+        }
+        assert_eq!(level_label("info"), "INFO    ");
+        assert_eq!(level_label("critical"), "CRITICAL");
+    }
+
+    fn create_entry(level: &str) -> LogEntry {
+        LogEntry {
+            id: "1".to_string(),
+            timestamp: chrono::Utc::now(),
+            level: level.to_string(),
+            message: "hello".to_string(),
+            user_id: None,
+            device_id: "device-1".to_string(),
+            source: "ios".to_string(),
+            metadata: std::collections::HashMap::from([("request".to_string(), "42".to_string())]),
+            tags: vec![],
+            file: "/app/Network/Client.swift".to_string(),
+            function: "send()".to_string(),
+            line: 17,
+        }
+    }
+
+    fn render_with(level: &str, verbose: bool, verbose_levels: &[LogLevel]) -> String {
+        let entry = create_entry(level);
+        render_log(&entry, shows_verbose(&entry.level, verbose, verbose_levels))
+    }
+
+    #[test]
+    fn test_verbose_levels_render_per_level() {
+        let verbose_levels = [LogLevel::Error, LogLevel::Critical];
+
+        let error = render_with("error", false, &verbose_levels);
+        assert!(error.contains("Client.swift:17"));
+        assert!(error.contains("request=42"));
+
+        let info = render_with("info", true, &verbose_levels);
+        assert!(!info.contains("Client.swift"));
+        assert!(!info.contains("request=42"));
+        assert!(info.ends_with("hello"));
+    }
+
+    #[test]
+    fn test_global_verbose_without_verbose_levels() {
+        assert!(render_with("info", true, &[]).contains("Client.swift:17"));
+        assert!(!render_with("error", false, &[]).contains("Client.swift"));
+    }
+
     #[test]
     fn test_format_source() {
         let source = format_source("cli");
@@ -0,0 +1,400 @@
+//! Text export formats for buffered log entries.
+//!
+//! Shared by the endpoints that serve logs as something other than JSON, and
+//! by CSV uploads, which use the same columns as the CSV export.
+
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, SecondsFormat, Utc};
+use uuid::Uuid;
+
+use crate::models::LogEntry;
+
+/// Columns written by [`to_csv`], in order
+const CSV_HEADER: &str =
+    "id,timestamp,level,source,deviceId,userId,message,file,function,line,tags";
+
+/// Columns written by [`to_spreadsheet_csv`], in order
+const SPREADSHEET_CSV_HEADER: &str = "timestamp,level,source,message,file,function,line,metadata";
+
+/// Response formats offered for log listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Text,
+}
+
+impl ExportFormat {
+    /// Pick a format from the `Accept` header, defaulting to JSON
+    ///
+    /// Media ranges are considered in the order given; the first one we can
+    /// produce wins.
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) else {
+            return Self::Json;
+        };
+
+        accept
+            .split(',')
+            .filter_map(|range| {
+                let media_type = range.split(';').next()?.trim();
+                match media_type.to_ascii_lowercase().as_str() {
+                    "application/json" | "application/*" | "*/*" => Some(Self::Json),
+                    "text/csv" => Some(Self::Csv),
+                    "text/plain" | "text/*" => Some(Self::Text),
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap_or(Self::Json)
+    }
+
+    /// `Content-Type` for responses in this format
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// Render entries as CSV with a header row
+pub fn to_csv(entries: &[LogEntry]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+
+    for entry in entries {
+        let fields = [
+            entry.id.clone(),
+            format_timestamp(entry),
+            entry.level.clone(),
+            entry.source.clone(),
+            entry.device_id.clone(),
+            entry.user_id.clone().unwrap_or_default(),
+            entry.message.clone(),
+            entry.file.clone(),
+            entry.function.clone(),
+            entry.line.to_string(),
+            entry.tags.join(";"),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Render entries as RFC 4180 CSV for opening in a spreadsheet
+///
+/// Unlike [`to_csv`] this leaves out ids and device fields and carries the
+/// metadata instead, as `key=value` pairs sorted by key and joined with `;`.
+pub fn to_spreadsheet_csv(entries: &[LogEntry]) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(Vec::new());
+    write_spreadsheet_rows(&mut writer, entries).expect("CSV writes to memory cannot fail");
+
+    let bytes = writer
+        .into_inner()
+        .expect("CSV writes to memory cannot fail");
+    String::from_utf8(bytes).expect("CSV fields are valid UTF-8")
+}
+
+fn write_spreadsheet_rows(
+    writer: &mut csv::Writer<Vec<u8>>,
+    entries: &[LogEntry],
+) -> csv::Result<()> {
+    writer.write_record(SPREADSHEET_CSV_HEADER.split(','))?;
+    for entry in entries {
+        let mut metadata: Vec<_> = entry.metadata.iter().collect();
+        metadata.sort();
+        let metadata: Vec<String> = metadata
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+
+        writer.write_record([
+            format_timestamp(entry),
+            entry.level.clone(),
+            entry.source.clone(),
+            entry.message.clone(),
+            entry.file.clone(),
+            entry.function.clone(),
+            entry.line.to_string(),
+            metadata.join(";"),
+        ])?;
+    }
+    Ok(())
+}
+
+/// Entries read from an uploaded CSV file
+#[derive(Debug, Default)]
+pub struct CsvImport {
+    pub entries: Vec<LogEntry>,
+    /// Rows that couldn't be parsed and were left out
+    pub skipped: usize,
+}
+
+/// Parse CSV rows in the [`to_csv`] column order
+///
+/// A leading header row is detected and ignored. Rows that are malformed or
+/// lack a valid timestamp are skipped rather than failing the whole upload;
+/// an empty `deviceId` falls back to `device_id` and an empty id is generated.
+pub fn from_csv(data: &str, device_id: &str) -> CsvImport {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data.as_bytes());
+
+    let mut import = CsvImport::default();
+    for (index, record) in reader.records().enumerate() {
+        let Ok(record) = record else {
+            import.skipped += 1;
+            continue;
+        };
+
+        if index == 0 && record.get(0) == Some("id") {
+            continue;
+        }
+
+        match csv_entry(&record, device_id) {
+            Some(entry) => import.entries.push(entry),
+            None => import.skipped += 1,
+        }
+    }
+
+    import
+}
+
+/// Build an entry from one CSV row
+fn csv_entry(record: &csv::StringRecord, device_id: &str) -> Option<LogEntry> {
+    // Everything up to the message column is required
+    if record.len() < 7 {
+        return None;
+    }
+    let field = |index: usize| record.get(index).unwrap_or("").trim();
+    let non_empty = |index: usize, fallback: &str| match field(index) {
+        "" => fallback.to_string(),
+        value => value.to_string(),
+    };
+
+    let timestamp = DateTime::parse_from_rfc3339(field(1))
+        .ok()?
+        .with_timezone(&Utc);
+
+    Some(LogEntry {
+        id: non_empty(0, &Uuid::new_v4().to_string()),
+        timestamp,
+        level: non_empty(2, "info"),
+        message: record.get(6).unwrap_or("").to_string(),
+        user_id: Some(field(5).to_string()).filter(|id| !id.is_empty()),
+        device_id: non_empty(4, device_id),
+        source: field(3).to_string(),
+        metadata: Default::default(),
+        tags: field(10)
+            .split(';')
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        file: field(7).to_string(),
+        function: field(8).to_string(),
+        line: field(9).parse().unwrap_or(0),
+    })
+}
+
+/// Render entries as `[timestamp] [LEVEL] [source] message` lines
+///
+/// Matches the dashboard's "Copy as text" output.
+pub fn to_text(entries: &[LogEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "[{}] [{}] [{}] {}\n",
+                format_timestamp(entry),
+                entry.level.to_uppercase(),
+                entry.source,
+                entry.message
+            )
+        })
+        .collect()
+}
+
+/// Render entries as logfmt lines (`ts=... level=... source=... msg=...`)
+pub fn to_logfmt(entries: &[LogEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            let pairs = [
+                ("ts", format_timestamp(entry)),
+                ("level", entry.level.clone()),
+                ("source", entry.source.clone()),
+                ("device", entry.device_id.clone()),
+                ("msg", entry.message.clone()),
+            ];
+            let line: Vec<String> = pairs
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, logfmt_value(value)))
+                .collect();
+            line.join(" ") + "\n"
+        })
+        .collect()
+}
+
+fn format_timestamp(entry: &LogEntry) -> String {
+    entry.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Quote a logfmt value when it is empty or contains spaces, `=` or quotes
+fn logfmt_value(value: &str) -> String {
+    let needs_quotes =
+        value.is_empty() || value.contains(|c: char| c == '=' || c == '"' || c <= ' ');
+    if !needs_quotes {
+        return value.to_string();
+    }
+
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+
+    fn create_entry(message: &str) -> LogEntry {
+        LogEntry {
+            id: "1".to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+            level: "warning".to_string(),
+            message: message.to_string(),
+            user_id: None,
+            device_id: "device-1".to_string(),
+            source: "ios".to_string(),
+            metadata: HashMap::new(),
+            tags: vec!["net".to_string(), "retry".to_string()],
+            file: "Api.swift".to_string(),
+            function: "fetch()".to_string(),
+            line: 7,
+        }
+    }
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(
+            ExportFormat::negotiate(&HeaderMap::new()),
+            ExportFormat::Json
+        );
+        assert_eq!(
+            ExportFormat::negotiate(&accept("text/csv")),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            ExportFormat::negotiate(&accept("text/plain; charset=utf-8")),
+            ExportFormat::Text
+        );
+        assert_eq!(
+            ExportFormat::negotiate(&accept("image/png, text/csv;q=0.9, */*;q=0.1")),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            ExportFormat::negotiate(&accept("image/png")),
+            ExportFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let csv = to_csv(&[create_entry("said \"hi\", then\nleft")]);
+        let (header, row) = csv.split_once('\n').unwrap();
+
+        assert_eq!(header, CSV_HEADER);
+        assert_eq!(
+            row,
+            "1,2024-03-01T12:00:00.000Z,warning,ios,device-1,,\"said \"\"hi\"\", then\nleft\",\
+             Api.swift,fetch(),7,net;retry\n"
+        );
+    }
+
+    #[test]
+    fn test_spreadsheet_csv_escaping() {
+        let mut entry = create_entry("said \"hi\", then left");
+        entry.metadata = [("screen", "home"), ("attempt", "2")]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        assert_eq!(
+            to_spreadsheet_csv(&[entry]),
+            "timestamp,level,source,message,file,function,line,metadata\r\n\
+             2024-03-01T12:00:00.000Z,warning,ios,\"said \"\"hi\"\", then left\",\
+             Api.swift,fetch(),7,attempt=2;screen=home\r\n"
+        );
+    }
+
+    #[test]
+    fn test_logfmt_quoting() {
+        assert_eq!(
+            to_logfmt(&[create_entry("retry count=3 after \"timeout\"")]),
+            "ts=2024-03-01T12:00:00.000Z level=warning source=ios device=device-1 \
+             msg=\"retry count=3 after \\\"timeout\\\"\"\n"
+        );
+        assert_eq!(logfmt_value("plain"), "plain");
+        assert_eq!(logfmt_value(""), "\"\"");
+        assert_eq!(logfmt_value("a\nb"), "\"a\\nb\"");
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = to_csv(&[create_entry("said \"hi\", then\nleft")]);
+
+        let import = from_csv(&csv, "fallback");
+        assert_eq!(import.skipped, 0);
+        assert_eq!(import.entries.len(), 1);
+        assert_eq!(to_csv(&import.entries), csv);
+    }
+
+    #[test]
+    fn test_csv_malformed_rows_skipped() {
+        let data = "2,2024-03-01T12:00:00Z,info,ios,,,first\n\
+                    3,not-a-date,info,ios,,,second\n\
+                    4,2024-03-01T12:00:01Z\n";
+
+        let import = from_csv(data, "device-9");
+        assert_eq!(import.skipped, 2);
+        assert_eq!(import.entries.len(), 1);
+        assert_eq!(import.entries[0].device_id, "device-9");
+        assert_eq!(import.entries[0].message, "first");
+        assert!(import.entries[0].tags.is_empty());
+    }
+
+    #[test]
+    fn test_text_lines() {
+        assert_eq!(
+            to_text(&[create_entry("Request failed")]),
+            "[2024-03-01T12:00:00.000Z] [WARNING] [ios] Request failed\n"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Opt-in JSONL log file for received entries, rotated by size.
+//!
+//! Entries are handed to a dedicated writer thread over a channel so disk
+//! I/O never delays the HTTP response.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use tokio::sync::mpsc;
+
+use crate::models::LogEntry;
+
+/// Sends entries to the background writer
+pub struct FileSink {
+    tx: mpsc::UnboundedSender<LogEntry>,
+}
+
+impl FileSink {
+    /// Start the writer thread for `path`
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn spawn(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let mut writer = RotatingWriter::open(path, max_bytes, max_files)?;
+        let (tx, mut rx) = mpsc::unbounded_channel::<LogEntry>();
+
+        tokio::task::spawn_blocking(move || {
+            while let Some(entry) = rx.blocking_recv() {
+                let result = serde_json::to_vec(&entry)
+                    .map_err(io::Error::from)
+                    .and_then(|mut line| {
+                        line.push(b'\n');
+                        writer.write_line(&line)
+                    });
+                if let Err(e) = result {
+                    tracing::warn!(error = %e, "Failed to write log file entry");
+                }
+            }
+        });
+
+        Ok(Self { tx })
+    }
+
+    /// Queue an entry for writing
+    pub fn send(&self, entry: LogEntry) {
+        // The writer only stops when the runtime shuts down
+        let _ = self.tx.send(entry);
+    }
+}
+
+/// Appends lines to a file, rotating it to `<name>.1`, `<name>.2`, ... once
+/// the next line would take it past `max_bytes`
+struct RotatingWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Rotated files kept; older ones are deleted
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingWriter {
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.needs_rotation(line.len() as u64) {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Whether `incoming` bytes would overflow the current file
+    ///
+    /// An empty file always takes the line, so an oversized entry is still
+    /// written instead of rotating forever.
+    fn needs_rotation(&self, incoming: u64) -> bool {
+        self.size > 0 && self.size + incoming > self.max_bytes
+    }
+
+    /// Shift `<name>.N` to `<name>.N+1` (dropping the oldest) and start a new file
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = rotated_path(&self.path, self.max_files);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for n in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, n);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// `<path>.<n>`
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_rotates_when_next_line_exceeds_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("received.jsonl");
+        let mut writer = RotatingWriter::open(path.clone(), 10, 5).unwrap();
+
+        writer.write_line(b"aaaa\n").unwrap();
+        writer.write_line(b"bbbb\n").unwrap();
+        // Exactly at the limit: no rotation yet
+        assert!(!rotated_path(&path, 1).exists());
+
+        writer.write_line(b"cccc\n").unwrap();
+        assert_eq!(read(&rotated_path(&path, 1)), "aaaa\nbbbb\n");
+        assert_eq!(read(&path), "cccc\n");
+    }
+
+    #[test]
+    fn test_rotated_files_shift_and_oldest_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("received.jsonl");
+        let mut writer = RotatingWriter::open(path.clone(), 5, 2).unwrap();
+
+        for line in ["1111\n", "2222\n", "3333\n", "4444\n"] {
+            writer.write_line(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(read(&path), "4444\n");
+        assert_eq!(read(&rotated_path(&path, 1)), "3333\n");
+        assert_eq!(read(&rotated_path(&path, 2)), "2222\n");
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn test_oversized_line_written_to_fresh_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("received.jsonl");
+        let mut writer = RotatingWriter::open(path.clone(), 4, 5).unwrap();
+
+        writer.write_line(b"0123456789\n").unwrap();
+        assert_eq!(read(&path), "0123456789\n");
+        assert!(!rotated_path(&path, 1).exists());
+    }
+
+    #[test]
+    fn test_existing_file_size_counts_toward_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("received.jsonl");
+        fs::write(&path, "existing\n").unwrap();
+
+        let mut writer = RotatingWriter::open(path.clone(), 12, 5).unwrap();
+        writer.write_line(b"next\n").unwrap();
+
+        assert_eq!(read(&rotated_path(&path, 1)), "existing\n");
+        assert_eq!(read(&path), "next\n");
+    }
+
+    #[tokio::test]
+    async fn test_sink_writes_entries_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("received.jsonl");
+        let sink = FileSink::spawn(path.clone(), 1024 * 1024, 5).unwrap();
+
+        let entry: LogEntry = serde_json::from_str(
+            r#"{"id":"a","timestamp":"2024-01-15T10:30:00Z","level":"info","message":"hi","deviceId":"d"}"#,
+        )
+        .unwrap();
+        sink.send(entry);
+
+        let mut contents = String::new();
+        for _ in 0..100 {
+            contents = fs::read_to_string(&path).unwrap_or_default();
+            if !contents.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let written: LogEntry = serde_json::from_str(contents.trim_end()).unwrap();
+        assert_eq!(written.id, "a");
+    }
+}
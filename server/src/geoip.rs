@@ -0,0 +1,212 @@
+//! GeoIP enrichment for ingested entries.
+//!
+//! Looks up the submitting client's country in a MaxMind database and records
+//! it in the entry's metadata.
+
+use std::net::IpAddr;
+use std::path::Path;
+
+use maxminddb::{geoip2, Reader};
+
+use crate::models::LogEntry;
+
+/// Metadata key holding the client's ISO country code
+pub const GEO_COUNTRY_KEY: &str = "geo_country";
+
+/// Country lookups backed by a MaxMind (GeoLite2/GeoIP2) database
+pub struct GeoIp {
+    reader: Reader<Vec<u8>>,
+}
+
+impl GeoIp {
+    /// Load a database file into memory
+    pub fn open(path: &Path) -> Result<Self, GeoIpError> {
+        let reader = Reader::open_readfile(path)
+            .map_err(|e| GeoIpError::Database(format!("{}: {}", path.display(), e)))?;
+        Ok(Self { reader })
+    }
+
+    /// ISO country code for a public address
+    ///
+    /// Private, loopback and other unroutable addresses are skipped, as are
+    /// addresses missing from the database.
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let ip = ip.to_canonical();
+        if !is_routable(ip) {
+            return None;
+        }
+
+        let record: geoip2::Country = self.reader.lookup(ip).ok()?;
+        record.country?.iso_code.map(str::to_string)
+    }
+
+    /// Add `geo_country` to the entry's metadata when the address resolves
+    pub fn enrich(&self, entry: &mut LogEntry, ip: IpAddr) {
+        if let Some(country) = self.country(ip) {
+            entry.metadata.insert(GEO_COUNTRY_KEY.to_string(), country);
+        }
+    }
+}
+
+/// Whether an address can belong to a real client on the public internet
+fn is_routable(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            let shared = a == 100 && (64..128).contains(&b);
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_unspecified()
+                || shared)
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            let unique_local = first & 0xfe00 == 0xfc00;
+            let link_local = first & 0xffc0 == 0xfe80;
+            !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+        }
+    }
+}
+
+/// GeoIP errors
+#[derive(Debug, thiserror::Error)]
+pub enum GeoIpError {
+    #[error("Failed to open GeoIP database {0}")]
+    Database(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Encode a MaxMind DB string
+    fn mmdb_string(s: &str) -> Vec<u8> {
+        let mut out = vec![0x40 | s.len() as u8];
+        out.extend_from_slice(s.as_bytes());
+        out
+    }
+
+    /// Encode a MaxMind DB map header with `len` key/value pairs
+    fn mmdb_map(len: u8) -> u8 {
+        0xE0 | len
+    }
+
+    /// Build a minimal IPv4 country database mapping `network/24` to `iso_code`
+    fn country_database(network: [u8; 3], iso_code: &str) -> Vec<u8> {
+        const NODE_COUNT: u32 = 24;
+        const NOT_FOUND: u32 = NODE_COUNT;
+        const FIRST_RECORD: u32 = NODE_COUNT + 16;
+
+        // One node per prefix bit; the other branch at each node is empty
+        let mut db = Vec::new();
+        for bit_index in 0..24 {
+            let bit = (network[bit_index / 8] >> (7 - bit_index % 8)) & 1;
+            let next = if bit_index == 23 {
+                FIRST_RECORD
+            } else {
+                bit_index as u32 + 1
+            };
+            let records = if bit == 0 {
+                [next, NOT_FOUND]
+            } else {
+                [NOT_FOUND, next]
+            };
+            for record in records {
+                db.extend_from_slice(&record.to_be_bytes()[1..]);
+            }
+        }
+        db.extend_from_slice(&[0; 16]);
+
+        // {"country": {"iso_code": ...}}
+        db.push(mmdb_map(1));
+        db.extend(mmdb_string("country"));
+        db.push(mmdb_map(1));
+        db.extend(mmdb_string("iso_code"));
+        db.extend(mmdb_string(iso_code));
+
+        db.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+        db.push(mmdb_map(9));
+        db.extend(mmdb_string("binary_format_major_version"));
+        db.extend([0xA1, 2]);
+        db.extend(mmdb_string("binary_format_minor_version"));
+        db.push(0xA0);
+        db.extend(mmdb_string("build_epoch"));
+        db.extend([0x01, 0x02, 1]);
+        db.extend(mmdb_string("database_type"));
+        db.extend(mmdb_string("Test-Country"));
+        db.extend(mmdb_string("description"));
+        db.push(mmdb_map(1));
+        db.extend(mmdb_string("en"));
+        db.extend(mmdb_string("Test"));
+        db.extend(mmdb_string("ip_version"));
+        db.extend([0xA1, 4]);
+        db.extend(mmdb_string("languages"));
+        db.extend([0x01, 0x04]);
+        db.extend(mmdb_string("en"));
+        db.extend(mmdb_string("node_count"));
+        db.extend([0xC1, NODE_COUNT as u8]);
+        db.extend(mmdb_string("record_size"));
+        db.extend([0xA1, 24]);
+        db
+    }
+
+    fn test_geoip() -> GeoIp {
+        let reader = Reader::from_source(country_database([81, 2, 69], "GB")).unwrap();
+        GeoIp { reader }
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_public_ip_country() {
+        let geoip = test_geoip();
+        assert_eq!(geoip.country(ip("81.2.69.160")).as_deref(), Some("GB"));
+        assert_eq!(geoip.country(ip("::ffff:81.2.69.1")).as_deref(), Some("GB"));
+        assert_eq!(geoip.country(ip("81.2.70.1")), None);
+    }
+
+    #[test]
+    fn test_unroutable_ips_skipped() {
+        let geoip = test_geoip();
+        for addr in [
+            "10.0.0.1",
+            "192.168.1.5",
+            "127.0.0.1",
+            "100.64.0.1",
+            "fd00::1",
+            "::1",
+        ] {
+            assert!(!is_routable(ip(addr)), "{}", addr);
+            assert_eq!(geoip.country(ip(addr)), None);
+        }
+    }
+
+    #[test]
+    fn test_enrich_sets_metadata() {
+        let geoip = test_geoip();
+        let mut entry: LogEntry = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "timestamp": "2024-03-01T12:00:00Z",
+            "level": "info",
+            "message": "hello",
+            "deviceId": "device-1",
+            "source": "test",
+        }))
+        .unwrap();
+
+        geoip.enrich(&mut entry, ip("192.168.1.5"));
+        assert!(entry.metadata.is_empty());
+
+        geoip.enrich(&mut entry, ip("81.2.69.160"));
+        assert_eq!(
+            entry.metadata,
+            HashMap::from([(GEO_COUNTRY_KEY.to_string(), "GB".to_string())])
+        );
+    }
+}
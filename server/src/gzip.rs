@@ -0,0 +1,130 @@
+//! JSON bodies that may arrive gzip-compressed (`Content-Encoding: gzip`).
+
+use std::io::Read;
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+
+/// Largest body accepted after inflating, so a small gzip bomb can't exhaust memory
+pub const MAX_INFLATED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Like [`Json`], but inflates the body first when it is sent with
+/// `Content-Encoding: gzip`
+///
+/// Corrupt gzip is rejected with 400 and an oversized inflated body with 413;
+/// uncompressed bodies behave exactly as with `Json`.
+pub struct GzipJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for GzipJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_gzip(request.headers()) {
+            let Json(value) = Json::<T>::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Self(value));
+        }
+
+        let compressed = Bytes::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let body = inflate(&compressed).map_err(IntoResponse::into_response)?;
+        let Json(value) = Json::<T>::from_bytes(&body).map_err(IntoResponse::into_response)?;
+        Ok(Self(value))
+    }
+}
+
+fn is_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("gzip"))
+}
+
+/// Decompress a gzip body, enforcing [`MAX_INFLATED_BYTES`]
+fn inflate(compressed: &[u8]) -> Result<Vec<u8>, (StatusCode, String)> {
+    let mut body = Vec::new();
+    GzDecoder::new(compressed)
+        .take(MAX_INFLATED_BYTES as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid gzip body: {}", e)))?;
+
+    if body.len() > MAX_INFLATED_BYTES {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Inflated body exceeds {} bytes", MAX_INFLATED_BYTES),
+        ));
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use flate2::{write::GzEncoder, Compression};
+    use serde_json::{json, Value};
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn request(body: Vec<u8>, gzipped: bool) -> Request {
+        let mut builder = Request::builder()
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json");
+        if gzipped {
+            builder = builder.header(header::CONTENT_ENCODING, "gzip");
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_gzipped_body_round_trips() {
+        let payload = json!({ "requestId": "abc", "logs": [{ "message": "hello" }] });
+        let body = gzip(payload.to_string().as_bytes());
+
+        let GzipJson(decoded) = GzipJson::<Value>::from_request(request(body, true), &())
+            .await
+            .unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[tokio::test]
+    async fn test_plain_body_still_accepted() {
+        let payload = json!({ "requestId": "abc" });
+        let body = payload.to_string().into_bytes();
+
+        let GzipJson(decoded) = GzipJson::<Value>::from_request(request(body, false), &())
+            .await
+            .unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_gzip_rejected() {
+        let body = b"definitely not gzip".to_vec();
+
+        let Err(response) = GzipJson::<Value>::from_request(request(body, true), &()).await else {
+            panic!("corrupt gzip was accepted");
+        };
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -2,117 +2,962 @@
 //!
 //! Implements the REST API endpoints for log management.
 
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{
         sse::{Event, KeepAlive, Sse},
         Html, IntoResponse, Response,
     },
     Json,
 };
+use chrono::{DateTime, Utc};
 use futures::stream::Stream;
-use serde::Deserialize;
-use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_stream::{
+    wrappers::{BroadcastStream, IntervalStream},
+    StreamExt,
+};
 use tracing::info;
 use uuid::Uuid;
 
 use crate::{
-    auth::AuthUser,
+    allowlist::ClientIp,
+    auth::{AdminUser, AuthUser},
+    buffer::{BufferEvent, BufferStats, UploadNotice},
+    config::ServerLimits,
     display,
-    models::{LogEntry, LogPollResponse, LogRequest, LogUploadRequest},
+    export::{self, ExportFormat},
+    gzip::GzipJson,
+    health::{HealthSummary, Liveness},
+    ingest::{self, EntryJson},
+    metrics::{self, LogSummary, Metrics, SourceErrorRate},
+    models::{
+        LocalizedLogEntry, LogEntry, LogLevel, LogPollResponse, LogRequest, LogUploadMetadata,
+        LogUploadRequest, UploadFormat,
+    },
+    openapi,
+    oslog::OsLogEntry,
+    presets::FilterPreset,
+    request_manager::MAX_REQUEST_TTL_HOURS,
+    storage::{sanitize_filename, StorageError, UploadStore},
     AppState,
 };
 
+/// Query parameters for the dashboard
+#[derive(Deserialize)]
+pub struct RootQuery {
+    /// `light` or `dark`; without it the browser's preference applies
+    pub theme: Option<String>,
+}
+
 /// GET / - Serve the React SPA
-pub async fn handle_root() -> Response {
-    match std::fs::read_to_string("static/app/index.html") {
+///
+/// `?theme=dark` marks the page dark before any script runs, so it doesn't
+/// flash the light palette first; the SPA reads the same parameter.
+pub async fn handle_root(Query(params): Query<RootQuery>) -> Response {
+    let html = match std::fs::read_to_string("static/app/index.html") {
+        Ok(html) => html,
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SPA not built. Run 'npm run build' in web/",
+            )
+                .into_response()
+        }
+    };
+
+    match apply_theme(html, params.theme.as_deref()) {
         Ok(html) => Html(html).into_response(),
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "SPA not built. Run 'npm run build' in web/",
-        )
-            .into_response(),
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+/// Mark the dashboard's root element for the requested theme
+///
+/// The SPA's stylesheet switches its color variables on the `dark` class;
+/// the light palette is the default and needs no marker.
+fn apply_theme(html: String, theme: Option<&str>) -> Result<String, (StatusCode, String)> {
+    match theme {
+        None | Some("light") => Ok(html),
+        Some("dark") => Ok(html.replacen("<html", "<html class=\"dark\"", 1)),
+        Some(other) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown theme '{}' (expected light or dark)", other),
+        )),
     }
 }
 
-/// GET /info - Endpoint documentation in plain text
-pub async fn handle_info() -> &'static str {
-    r#"Log Server (Rust)
+/// Endpoint list shown by `GET /info`
+const INFO_ENDPOINTS: &str = r#"Log Server (Rust)
 ==========
 
 Endpoints:
 - GET /              - HTML dashboard (visual log viewer)
 - POST /logs         - Submit a log entry
-- GET /logs          - Retrieve all logs (JSON)
-- DELETE /logs       - Clear all logs
+- GET /logs          - Retrieve all logs (JSON, or CSV/text via Accept header)
+- DELETE /logs       - Clear all logs (?before=<rfc3339> removes only older ones)
+- POST /logs/delta   - Logs whose ids are not in the posted JSON array
+- GET /logs/chunk    - One page of logs (?cursor=&size=) plus the nextCursor
+- GET /logs/search   - Search history for ?q= (cold buffer when configured)
+- GET /logs/export    - Logs as newline-delimited JSON (?level=&since=)
+- GET /logs/export.logfmt - Logs as logfmt lines
+- GET /logs/export.csv - Logs as a spreadsheet-friendly CSV (?level=)
+- GET /logs/stats    - Counts by level and source, oldest/newest timestamps
+- POST /logs/oslog   - Submit Apple OSLog JSON (log show --style json)
+- GET /stats         - Buffer occupancy (high-water mark, evictions)
+- GET /stats/error-rates - Per-source error/critical share, highest first
+- GET /limits        - Configured ingest limits
+- GET /health        - Storage, auth and buffer checks with an overall status
+- GET /healthz       - Liveness probe: {"status": "ok", "uptime_secs": N}
+- GET /metrics       - Buffer, per-level and log request counts
+- GET /metrics/prometheus - The same in Prometheus text format
+- GET /openapi.json  - OpenAPI 3 description of the API
 
 Visit / for the interactive web dashboard, or /logs for JSON API access.
+"#;
 
-Server is listening on port 9006
-"#
+/// GET /info - Endpoint documentation plus the running configuration in plain text
+pub async fn handle_info(State(state): State<Arc<AppState>>) -> String {
+    let auth = if state.jwt_validator.is_some() {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    format!(
+        "{INFO_ENDPOINTS}\nServer is listening on port {}\nBuffer: {} / {} entries\nAuthentication: {auth}\n",
+        state.config.port,
+        state.buffer.count(),
+        state.buffer.capacity(),
+    )
 }
 
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Response header set on `POST /logs` when the buffer is full and dropping old entries
+pub const BUFFER_EVICTING_HEADER: &str = "X-Buffer-Evicting";
+
 /// POST /logs - Receive and store a log entry
+///
+/// Responds with the entry as stored, after id generation and normalization.
 pub async fn handle_receive_log(
     State(state): State<Arc<AppState>>,
-    Json(entry): Json<LogEntry>,
+    client_ip: Option<Extension<ClientIp>>,
+    EntryJson(entry): EntryJson<LogEntry>,
 ) -> Response {
-    // Store in buffer
-    state.buffer.append(entry.clone());
+    if let Err(rejection) = ensure_ingest_enabled(&state) {
+        return rejection.into_response();
+    }
+
+    let original = state.config.track_server_fields.then(|| entry.clone());
+
+    let mut entry = match ingest::prepare_entry(entry, &state.config) {
+        Ok(entry) => entry,
+        Err(e) => return (e.status_code(), e.to_string()).into_response(),
+    };
+
+    // A noisy source would otherwise push everyone else out of the buffer.
+    // Keyed on the prepared source, which is what ends up stored.
+    if let Err(wait) = state.ingest_limiter.check(&entry.source) {
+        return too_many_requests(wait, "Ingest rate exceeded for this source; entry dropped");
+    }
+
+    if !meets_min_ingest_level(&state, &entry) {
+        let message = format!(
+            "Entry below MIN_INGEST_LEVEL ({}); not stored",
+            state.config.min_ingest_level
+        );
+        return (StatusCode::ACCEPTED, message).into_response();
+    }
+
+    if let (Some(geoip), Some(Extension(ClientIp(Some(ip))))) = (&state.geoip, client_ip) {
+        geoip.enrich(&mut entry, ip);
+    }
+
+    if let Some(original) = &original {
+        ingest::mark_server_fields(original, &mut entry);
+    }
+
+    let evicted = record_entry(&state, entry.clone());
+
+    let mut response = (StatusCode::CREATED, Json(entry)).into_response();
+    if evicted {
+        response
+            .headers_mut()
+            .insert(BUFFER_EVICTING_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// 429 with `Retry-After` rounded up to whole seconds
+fn too_many_requests(wait: Duration, message: &'static str) -> Response {
+    let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.to_string())],
+        message,
+    )
+        .into_response()
+}
+
+/// Whether an entry is at or above MIN_INGEST_LEVEL and should be kept
+fn meets_min_ingest_level(state: &AppState, entry: &LogEntry) -> bool {
+    LogLevel::from_str(&entry.level) >= state.config.min_ingest_level
+}
+
+/// Reject submissions while ingestion is paused
+fn ensure_ingest_enabled(state: &AppState) -> Result<(), (StatusCode, String)> {
+    if state.ingest_enabled.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "ingestion paused".to_string(),
+        ))
+    }
+}
+
+/// Reject malformed device ids when STRICT_DEVICE_IDS is set
+fn ensure_valid_device_id(state: &AppState, device_id: &str) -> Result<(), (StatusCode, String)> {
+    ingest::check_device_id(device_id, &state.config)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Buffer, archive and display an accepted entry
+///
+/// Returns whether the live buffer was full and evicted older entries.
+fn record_entry(state: &AppState, entry: LogEntry) -> bool {
+    // Archive failures shouldn't lose the entry for live viewing
+    if let Some(archive) = &state.archive {
+        if let Err(e) = archive.append(&entry) {
+            tracing::warn!(error = %e, "Failed to archive log entry");
+        }
+    }
+
+    if let Some(log_file) = &state.log_file {
+        log_file.send(entry.clone());
+    }
+
+    if let Some(deadman) = &state.deadman {
+        deadman.record(&entry.source, Utc::now());
+    }
 
     // Display in terminal
-    display::display_log(&entry, state.verbose);
+    display::display_log(&entry, state.verbose, &state.config.verbose_levels);
+
+    // Store in buffers
+    if let Some(cold_buffer) = &state.cold_buffer {
+        cold_buffer.append(entry.clone());
+    }
+    state.buffer.append(entry)
+}
+
+/// Query parameters for OSLog ingestion
+#[derive(Deserialize)]
+pub struct OsLogQuery {
+    #[serde(rename = "deviceId", default)]
+    pub device_id: String,
+}
+
+/// POST /logs/oslog - Receive entries exported with `log show --style json`
+pub async fn handle_receive_oslog(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OsLogQuery>,
+    EntryJson(entries): EntryJson<Vec<OsLogEntry>>,
+) -> Response {
+    if let Err(rejection) = ensure_ingest_enabled(&state) {
+        return rejection.into_response();
+    }
+
+    // Convert and check everything first so a bad entry rejects the whole batch
+    let mut converted = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.into_iter().enumerate() {
+        let entry = entry
+            .into_log_entry(&params.device_id)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+            .and_then(|entry| {
+                ingest::prepare_entry(entry, &state.config)
+                    .map_err(|e| (e.status_code(), e.to_string()))
+            });
+
+        match entry {
+            Ok(entry) => converted.push(entry),
+            Err((status, e)) => return (status, format!("Entry {}: {}", index, e)).into_response(),
+        }
+    }
+
+    // Same per-source limit as POST /logs, so this route isn't a way around it;
+    // the batch is charged as a whole, so a dropped one costs nothing
+    let sources = converted.iter().map(|entry| entry.source.as_str());
+    if let Err(wait) = state.ingest_limiter.check_batch(sources) {
+        return too_many_requests(wait, "Ingest rate exceeded for this source; batch dropped");
+    }
+
+    for entry in converted {
+        if meets_min_ingest_level(&state, &entry) {
+            record_entry(&state, entry);
+        }
+    }
 
     StatusCode::CREATED.into_response()
 }
 
+/// Query parameters for log listings and the live stream
+#[derive(Deserialize, Default)]
+pub struct LogsQuery {
+    /// Name of a filter preset from FILTER_PRESETS
+    pub preset: Option<String>,
+    /// Only entries from this source file, compared by basename (GET /logs only)
+    pub file: Option<String>,
+    /// Only entries logged at this line (GET /logs only)
+    pub line: Option<u32>,
+    /// Only entries at or above this severity
+    pub level: Option<String>,
+    /// Only entries from exactly this source (GET /stream only)
+    pub source: Option<String>,
+    /// Number of recent buffered entries to send before live ones (GET /stream only)
+    pub replay: Option<usize>,
+    /// Case-insensitive text to find in the searched fields (GET /logs only)
+    pub q: Option<String>,
+    /// Comma-separated fields `q` searches: message, source, metadata (default all)
+    pub fields: Option<String>,
+    /// Only entries timestamped at or after this RFC 3339 time (GET /logs and /logs/export)
+    pub since: Option<String>,
+    /// Only entries timestamped at or before this RFC 3339 time (GET /logs only)
+    pub until: Option<String>,
+}
+
+impl LogsQuery {
+    /// Whether an entry matches the `file`/`line` filters
+    fn matches_location(&self, entry: &LogEntry) -> bool {
+        let file_ok = self
+            .file
+            .as_deref()
+            .map_or(true, |file| basename(&entry.file) == basename(file));
+        let line_ok = self.line.map_or(true, |line| entry.line == line);
+
+        file_ok && line_ok
+    }
+}
+
+/// Last component of a `/` or `\\` separated path
+fn basename(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Entry fields `?q=` can search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchField {
+    Message,
+    Source,
+    /// Every metadata value
+    Metadata,
+}
+
+impl SearchField {
+    const ALL: [SearchField; 3] = [Self::Message, Self::Source, Self::Metadata];
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "message" => Some(Self::Message),
+            "source" => Some(Self::Source),
+            "metadata" => Some(Self::Metadata),
+            _ => None,
+        }
+    }
+}
+
+/// Case-insensitive substring search over selected entry fields
+struct TextSearch {
+    needle: String,
+    fields: Vec<SearchField>,
+}
+
+impl TextSearch {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        let found = |text: &str| text.to_lowercase().contains(&self.needle);
+
+        self.fields.iter().any(|field| match field {
+            SearchField::Message => found(&entry.message),
+            SearchField::Source => found(&entry.source),
+            SearchField::Metadata => entry.metadata.values().any(|value| found(value)),
+        })
+    }
+}
+
+/// Build the `?q=`/`?fields=` search; an empty `q` searches for nothing
+fn resolve_text_search(
+    q: Option<&str>,
+    fields: Option<&str>,
+) -> Result<Option<TextSearch>, (StatusCode, String)> {
+    let mut selected = Vec::new();
+    for name in fields.unwrap_or_default().split(',').map(str::trim) {
+        if name.is_empty() {
+            continue;
+        }
+        let field = SearchField::parse(name).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Unknown search field '{}'", name),
+            )
+        })?;
+        if !selected.contains(&field) {
+            selected.push(field);
+        }
+    }
+    if selected.is_empty() {
+        selected = SearchField::ALL.to_vec();
+    }
+
+    let needle = q.unwrap_or_default().to_lowercase();
+    Ok((!needle.is_empty()).then_some(TextSearch {
+        needle,
+        fields: selected,
+    }))
+}
+
+/// Parse the `?level=` threshold, rejecting unknown level names
+fn resolve_min_level(level: Option<&str>) -> Result<Option<LogLevel>, (StatusCode, String)> {
+    level
+        .map(|name| {
+            LogLevel::parse(name).ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown log level '{}'", name),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Look up the preset named in the query, rejecting unknown names
+fn resolve_preset(
+    state: &AppState,
+    name: Option<&str>,
+) -> Result<Option<FilterPreset>, (StatusCode, String)> {
+    name.map(|name| {
+        state
+            .config
+            .filter_presets
+            .get(name)
+            .cloned()
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown filter preset '{}'", name),
+                )
+            })
+    })
+    .transpose()
+}
+
 /// GET /logs - Retrieve all logs in chronological order
-pub async fn handle_get_all_logs(State(state): State<Arc<AppState>>) -> Json<Vec<LogEntry>> {
-    let entries = state.buffer.get_all();
-    Json(entries)
+///
+/// Responds with JSON, CSV or plain text depending on the `Accept` header.
+/// `?preset=` narrows the entries to a configured filter preset,
+/// `?file=`/`?line=` to a source location, `?level=` to entries at or above a
+/// severity, `?since=`/`?until=` to an inclusive time range, and `?q=` to
+/// entries containing some text in the `?fields=` searched (message, source
+/// and metadata values by default).
+pub async fn handle_get_all_logs(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LogsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let preset = match resolve_preset(&state, params.preset.as_deref()) {
+        Ok(preset) => preset,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let min_level = match resolve_min_level(params.level.as_deref()) {
+        Ok(min_level) => min_level,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let search = match resolve_text_search(params.q.as_deref(), params.fields.as_deref()) {
+        Ok(search) => search,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let since = match parse_range_bound("since", params.since.as_deref()) {
+        Ok(since) => since,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let until = match parse_range_bound("until", params.until.as_deref()) {
+        Ok(until) => until,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let mut entries = state.buffer.get_all();
+    if let Some(preset) = preset {
+        entries.retain(|entry| preset.matches(entry));
+    }
+    if let Some(min_level) = min_level {
+        entries.retain(|entry| LogLevel::from_str(&entry.level) >= min_level);
+    }
+    if let Some(search) = search {
+        entries.retain(|entry| search.matches(entry));
+    }
+    entries.retain(|entry| {
+        since.map_or(true, |since| entry.timestamp >= since)
+            && until.map_or(true, |until| entry.timestamp <= until)
+    });
+    entries.retain(|entry| params.matches_location(entry));
+
+    let format = ExportFormat::negotiate(&headers);
+    let body = match format {
+        ExportFormat::Json => return Json(entries).into_response(),
+        ExportFormat::Csv => export::to_csv(&entries),
+        ExportFormat::Text => export::to_text(&entries),
+    };
+
+    ([(header::CONTENT_TYPE, format.content_type())], body).into_response()
+}
+
+/// GET /logs/export - Buffered entries as newline-delimited JSON
+///
+/// Each entry is serialized as its line is sent rather than as one large JSON
+/// array. `?level=` and `?since=` narrow the entries as on `GET /logs`.
+pub async fn handle_export_ndjson(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LogsQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let min_level = resolve_min_level(params.level.as_deref())?;
+    let since = parse_range_bound("since", params.since.as_deref())?;
+
+    let entries = state.buffer.get_all().into_iter().filter(move |entry| {
+        min_level.map_or(true, |min| LogLevel::from_str(&entry.level) >= min)
+            && since.map_or(true, |since| entry.timestamp >= since)
+    });
+    let lines = futures::stream::iter(entries).map(|entry| {
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        Ok::<_, serde_json::Error>(line)
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+/// GET /logs/export.logfmt - Buffered entries as logfmt lines
+pub async fn handle_export_logfmt(State(state): State<Arc<AppState>>) -> Response {
+    let body = export::to_logfmt(&state.buffer.get_all());
+    ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response()
+}
+
+/// GET /logs/export.csv - Buffered entries as CSV for spreadsheets
+///
+/// `?level=` keeps entries at or above a severity as on `GET /logs`.
+pub async fn handle_export_csv(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LogsQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let min_level = resolve_min_level(params.level.as_deref())?;
+
+    let mut entries = state.buffer.get_all();
+    if let Some(min_level) = min_level {
+        entries.retain(|entry| LogLevel::from_str(&entry.level) >= min_level);
+    }
+
+    let body = export::to_spreadsheet_csv(&entries);
+    let headers = [
+        (header::CONTENT_TYPE, ExportFormat::Csv.content_type()),
+        (
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"logs.csv\"",
+        ),
+    ];
+    Ok((headers, body).into_response())
+}
+
+/// POST /logs/delta - Buffered entries whose ids the client doesn't already have
+pub async fn handle_logs_delta(
+    State(state): State<Arc<AppState>>,
+    Json(known_ids): Json<Vec<String>>,
+) -> Json<Vec<LogEntry>> {
+    let known: HashSet<String> = known_ids.into_iter().collect();
+
+    let missing = state
+        .buffer
+        .get_all()
+        .into_iter()
+        .filter(|entry| !known.contains(&entry.id))
+        .collect();
+
+    Json(missing)
+}
+
+/// Page size for `GET /logs/chunk` when `?size=` is omitted
+pub const DEFAULT_CHUNK_SIZE: usize = 500;
+
+/// Largest page `GET /logs/chunk` returns, whatever `?size=` asks for
+pub const MAX_CHUNK_SIZE: usize = 5000;
+
+/// Query parameters for paging through the buffer
+#[derive(Deserialize)]
+pub struct ChunkQuery {
+    /// `nextCursor` from the previous page (starts at the oldest entry if omitted)
+    pub cursor: Option<String>,
+    /// Entries per page, capped at [`MAX_CHUNK_SIZE`]
+    pub size: Option<usize>,
+}
+
+/// A page of buffered entries from `GET /logs/chunk`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogChunk {
+    pub entries: Vec<LogEntry>,
+
+    /// Opaque cursor for the following page; also picks up entries that
+    /// arrive after the buffer has been read to the end
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: String,
+
+    /// Whether more entries were already buffered past this page
+    #[serde(rename = "hasMore")]
+    pub has_more: bool,
+}
+
+/// GET /logs/chunk - Page through the buffer without one huge response
+///
+/// The cursor wraps the buffer's sequence numbers, so pages neither repeat
+/// nor skip entries as new ones arrive.
+pub async fn handle_get_log_chunk(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ChunkQuery>,
+) -> Result<Json<LogChunk>, (StatusCode, String)> {
+    let from = match params.cursor.as_deref() {
+        None | Some("") => 0,
+        Some(cursor) => u64::from_str_radix(cursor, 16)
+            .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))?,
+    };
+    let size = params
+        .size
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+        .clamp(1, MAX_CHUNK_SIZE);
+
+    let chunk = state.buffer.chunk_from(from, size);
+    Ok(Json(LogChunk {
+        entries: chunk.entries,
+        next_cursor: format!("{:x}", chunk.next_seq),
+        has_more: chunk.has_more,
+    }))
+}
+
+/// Query parameters for searching history
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    /// Case-insensitive text to find in messages (matches everything if omitted)
+    #[serde(default)]
+    pub q: String,
+
+    /// Regex the message must also match; its match spans are returned
+    pub highlight: Option<String>,
+}
+
+/// Byte range of a `?highlight=` match within an entry's message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A search hit, with highlight spans when `?highlight=` was given
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub entry: LogEntry,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<Vec<MatchSpan>>,
+}
+
+/// GET /logs/search - Entries whose message contains `?q=`
+///
+/// Reads the cold buffer when one is configured, so results reach further back
+/// than the live view. With `?highlight=<regex>` only messages matching the
+/// regex are returned, each with the byte offsets of every match.
+pub async fn handle_search_logs(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>, (StatusCode, String)> {
+    let needle = params.q.to_lowercase();
+    let highlight = params
+        .highlight
+        .as_deref()
+        .map(regex::Regex::new)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid highlight: {}", e)))?;
+
+    let matches = state
+        .search_buffer()
+        .get_all()
+        .into_iter()
+        .filter(|entry| entry.message.to_lowercase().contains(&needle))
+        .filter_map(|entry| {
+            let highlights = match &highlight {
+                Some(regex) => {
+                    let spans: Vec<MatchSpan> = regex
+                        .find_iter(&entry.message)
+                        .map(|m| MatchSpan {
+                            start: m.start(),
+                            end: m.end(),
+                        })
+                        .collect();
+                    if spans.is_empty() {
+                        return None;
+                    }
+                    Some(spans)
+                }
+                None => None,
+            };
+            Some(SearchResult { entry, highlights })
+        })
+        .collect();
+
+    Ok(Json(matches))
+}
+
+/// Query parameters for clearing logs
+#[derive(Deserialize)]
+pub struct ClearLogsQuery {
+    /// Only remove entries timestamped before this RFC 3339 time
+    pub before: Option<String>,
+}
+
+/// Number of entries removed by a partial clear
+#[derive(Debug, Serialize)]
+pub struct ClearLogsResponse {
+    pub removed: usize,
 }
 
-/// DELETE /logs - Clear all logs
-pub async fn handle_clear_logs(State(state): State<Arc<AppState>>) -> StatusCode {
-    state.buffer.clear();
-    info!("Cleared all logs");
-    StatusCode::NO_CONTENT
+/// DELETE /logs - Clear all logs, or only those before `?before=`
+pub async fn handle_clear_logs(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ClearLogsQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let Some(cutoff) = parse_range_bound("before", params.before.as_deref())? else {
+        if let Some(cold_buffer) = &state.cold_buffer {
+            cold_buffer.clear();
+        }
+        state.buffer.clear();
+        info!("Cleared all logs");
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+
+    if let Some(cold_buffer) = &state.cold_buffer {
+        cold_buffer.retain(|entry| entry.timestamp >= cutoff);
+    }
+    let removed = state.buffer.retain(|entry| entry.timestamp >= cutoff);
+    info!(removed = removed, before = %cutoff, "Cleared old logs");
+
+    Ok(Json(ClearLogsResponse { removed }).into_response())
 }
 
 /// GET /stream - Server-Sent Events stream for real-time log updates
+///
+/// With `?preset=`, only entries matching the preset are sent; `?level=` and
+/// `?source=` further narrow them to a minimum severity and an exact source.
+/// `?replay=N` first sends the last N buffered entries passing those filters.
+/// Every SSE_KEEPALIVE_SECS a `heartbeat` event carries the server time.
 pub async fn handle_stream(
     State(state): State<Arc<AppState>>,
-) -> Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>> {
+    Query(params): Query<LogsQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>>, (StatusCode, String)>
+{
+    let filter = StreamFilter {
+        preset: resolve_preset(&state, params.preset.as_deref())?,
+        min_level: resolve_min_level(params.level.as_deref())?,
+        source: params.source,
+    };
+
     info!("New SSE client connected");
 
-    // Subscribe to broadcast channel
+    // Subscribe before taking the snapshot so nothing slips in between
     let receiver = state.buffer.subscribe();
+    let mut replayed: Vec<LogEntry> = state
+        .buffer
+        .get_all()
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    let skipped = replayed.len().saturating_sub(params.replay.unwrap_or(0));
+    replayed.drain(..skipped);
+
+    // Tell the browser how long to wait before reconnecting
+    let retry = Event::default().retry(Duration::from_millis(state.config.sse_retry_ms));
+    let retry = futures::stream::once(async move { Ok(retry) });
 
     // Convert broadcast receiver to stream
-    let stream = BroadcastStream::new(receiver)
-        .filter_map(|result| {
-            match result {
-                Ok(entry) => {
-                    // Serialize log entry to JSON
-                    match serde_json::to_string(&entry) {
-                        Ok(json) => Some(Ok(Event::default().event("log").data(json))),
-                        Err(e) => {
-                            tracing::error!("Failed to serialize log entry: {}", e);
-                            None
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Broadcast receive error: {}", e);
-                    None
-                }
-            }
-        });
+    let live = BroadcastStream::new(receiver).filter_map(|result| match result {
+        Ok(event) => Some(event),
+        Err(e) => {
+            tracing::warn!("Broadcast receive error: {}", e);
+            None
+        }
+    });
+    let events = replay_then_live(replayed, live).filter_map(move |event| {
+        if filter.allows(&event) {
+            stream_event(event).map(Ok)
+        } else {
+            None
+        }
+    });
+
+    let interval = Duration::from_secs(state.config.sse_keepalive_secs);
+    let keep_alive = KeepAlive::new().interval(interval).text("ping");
+
+    Ok(Sse::new(retry.chain(events.merge(heartbeats(interval)))).keep_alive(keep_alive))
+}
+
+/// GET /stats - Buffer occupancy (high-water mark and evictions)
+pub async fn handle_stats(State(state): State<Arc<AppState>>) -> Json<BufferStats> {
+    Json(state.buffer.stats())
+}
+
+/// GET /logs/stats - Buffered entries counted by level and source, with their time span
+pub async fn handle_log_stats(State(state): State<Arc<AppState>>) -> Json<LogSummary> {
+    Json(metrics::summarize(&state.buffer.get_all()))
+}
+
+/// GET /stats/error-rates - Per-source share of error and critical entries
+pub async fn handle_error_rates(State(state): State<Arc<AppState>>) -> Json<Vec<SourceErrorRate>> {
+    Json(metrics::error_rates(&state.buffer.get_all()))
+}
+
+/// GET /metrics - Buffer occupancy, per-level counts and log request stats
+pub async fn handle_metrics(State(state): State<Arc<AppState>>) -> Json<Metrics> {
+    Json(Metrics::collect(&state))
+}
+
+/// GET /metrics/prometheus - The `/metrics` snapshot in Prometheus text format
+pub async fn handle_metrics_prometheus(State(state): State<Arc<AppState>>) -> Response {
+    let body = Metrics::collect(&state).to_prometheus();
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body).into_response()
+}
+
+/// GET /healthz - Liveness probe: status and uptime, without touching the buffer
+pub async fn handle_healthz(State(state): State<Arc<AppState>>) -> Json<Liveness> {
+    Json(Liveness::since(state.started_at))
+}
+
+/// GET /health - Storage, auth and buffer checks; `status` is the worst of them
+pub async fn handle_health_summary(State(state): State<Arc<AppState>>) -> Json<HealthSummary> {
+    Json(HealthSummary::collect(&state))
+}
+
+/// GET /limits - Configured ingest limits for client-side pre-validation
+pub async fn handle_limits(State(state): State<Arc<AppState>>) -> Json<ServerLimits> {
+    let mut limits = state.config.limits();
+    // The buffer can be resized at runtime
+    limits.buffer_capacity = state.buffer.capacity();
+    Json(limits)
+}
+
+/// GET /openapi.json - OpenAPI 3 description of the API
+pub async fn handle_openapi() -> Json<serde_json::Value> {
+    Json(openapi::document())
+}
+
+/// Criteria a `GET /stream` client narrowed its entries to
+struct StreamFilter {
+    preset: Option<FilterPreset>,
+    min_level: Option<LogLevel>,
+    source: Option<String>,
+}
+
+impl StreamFilter {
+    /// Whether a buffer notification should reach the client
+    ///
+    /// Clears and upload notices always go through so filtered dashboards
+    /// still reset and announce uploads.
+    fn allows(&self, event: &BufferEvent) -> bool {
+        match event {
+            BufferEvent::Log(entry) => self.matches(entry),
+            _ => true,
+        }
+    }
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+    /// Whether an entry passes every criterion
+    fn matches(&self, entry: &LogEntry) -> bool {
+        let preset_ok = self
+            .preset
+            .as_ref()
+            .map_or(true, |preset| preset.matches(entry));
+        let level_ok = self
+            .min_level
+            .map_or(true, |min| LogLevel::from_str(&entry.level) >= min);
+        let source_ok = self
+            .source
+            .as_ref()
+            .map_or(true, |source| &entry.source == source);
+
+        preset_ok && level_ok && source_ok
+    }
+}
+
+/// Replayed entries as log events, then the live events not already replayed
+///
+/// The live subscription is opened before the snapshot is taken, so an entry
+/// appended in between arrives both ways; its first live copy is dropped.
+fn replay_then_live(
+    replayed: Vec<LogEntry>,
+    live: impl Stream<Item = BufferEvent>,
+) -> impl Stream<Item = BufferEvent> {
+    let mut pending: HashSet<String> = replayed.iter().map(|entry| entry.id.clone()).collect();
+    let replay = futures::stream::iter(
+        replayed
+            .into_iter()
+            .map(|entry| BufferEvent::Log(Box::new(entry))),
+    );
+    let live = live.filter(move |event| match event {
+        BufferEvent::Log(entry) => !pending.remove(&entry.id),
+        _ => true,
+    });
+
+    replay.chain(live)
+}
+
+/// `heartbeat` events carrying the server's UTC time, one per `interval`
+///
+/// The first is sent one interval after connecting.
+fn heartbeats(interval: Duration) -> impl Stream<Item = Result<Event, std::convert::Infallible>> {
+    let start = tokio::time::Instant::now() + interval;
+    IntervalStream::new(tokio::time::interval_at(start, interval)).map(|_| {
+        let data = serde_json::json!({ "timestamp": Utc::now() });
+        Ok(Event::default().event("heartbeat").data(data.to_string()))
+    })
+}
+
+/// Convert a buffer notification into an SSE event
+///
+/// New entries are sent as `log` events carrying the entry JSON; a buffer
+/// clear is sent as a `clear` event so dashboards can wipe their rows, and a
+/// stored upload as an `upload` event with its request, device and count.
+fn stream_event(event: BufferEvent) -> Option<Event> {
+    match event {
+        BufferEvent::Upload(notice) => match serde_json::to_string(&notice) {
+            Ok(json) => Some(Event::default().event("upload").data(json)),
+            Err(e) => {
+                tracing::error!("Failed to serialize upload notice: {}", e);
+                None
+            }
+        },
+        BufferEvent::Log(entry) => match serde_json::to_string(&entry) {
+            Ok(json) => Some(Event::default().event("log").data(json)),
+            Err(e) => {
+                tracing::error!("Failed to serialize log entry: {}", e);
+                None
+            }
+        },
+        BufferEvent::Clear => Some(Event::default().event("clear").data("{}")),
+    }
 }
 
 // MARK: - Protected Endpoints (Require JWT Authentication)
@@ -122,6 +967,10 @@ pub async fn handle_stream(
 pub struct CreateRequestBody {
     #[serde(rename = "device_id")]
     pub device_id: String,
+
+    /// Lifetime of this request instead of REQUEST_TTL_HOURS, capped at a week
+    #[serde(rename = "expiresInHours")]
+    pub expires_in_hours: Option<i64>,
 }
 
 /// POST /logs/request - Create a log request for a specific device (Admin/Server)
@@ -130,9 +979,18 @@ pub async fn handle_create_request(
     auth: AuthUser,
     Json(body): Json<CreateRequestBody>,
 ) -> Result<Json<LogRequest>, (StatusCode, String)> {
-    let request = state
-        .request_manager
-        .create_request(auth.user_id, body.device_id.clone());
+    ensure_valid_device_id(&state, &body.device_id)?;
+
+    let request = match body.expires_in_hours {
+        Some(hours) => state.request_manager.create_request_expiring(
+            auth.user_id,
+            body.device_id.clone(),
+            chrono::Duration::hours(hours.clamp(1, MAX_REQUEST_TTL_HOURS)),
+        ),
+        None => state
+            .request_manager
+            .create_request(auth.user_id, body.device_id.clone()),
+    };
 
     info!(
         user_id = %auth.user_id,
@@ -144,6 +1002,49 @@ pub async fn handle_create_request(
     Ok(Json(request))
 }
 
+/// Query parameters for cancelling a log request
+#[derive(Deserialize)]
+pub struct CancelRequestQuery {
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+}
+
+/// DELETE /logs/request?deviceId={uuid} - Call off a device's pending log request
+pub async fn handle_cancel_request(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Query(params): Query<CancelRequestQuery>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let request = state
+        .request_manager
+        .get_pending(&params.device_id)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "No pending request found for this device".to_string(),
+        ))?;
+
+    if request.user_id != auth.user_id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "This log request belongs to a different user".to_string(),
+        ));
+    }
+
+    state
+        .request_manager
+        .cancel(&params.device_id)
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+
+    info!(
+        user_id = %auth.user_id,
+        device_id = %params.device_id,
+        request_id = %request.id,
+        "Log request cancelled"
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// Query parameters for polling
 #[derive(Deserialize)]
 pub struct PollQuery {
@@ -156,15 +1057,24 @@ pub async fn handle_poll(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
     Query(params): Query<PollQuery>,
-) -> Result<Json<Option<LogPollResponse>>, (StatusCode, String)> {
+) -> Result<Json<Option<LogPollResponse>>, Response> {
+    ensure_valid_device_id(&state, &params.device_id).map_err(IntoResponse::into_response)?;
+
+    // Keep aggressive pollers off the request manager lock; the wait is the
+    // time until the device's bucket refills a token
+    if let Err(wait) = state.poll_limiter.check(&params.device_id) {
+        return Err(too_many_requests(wait, "Polling too frequently"));
+    }
+
     // Check if there's a pending request for this device
     if let Some(request) = state.request_manager.get_pending(&params.device_id) {
         // Verify the request belongs to the authenticated user
         if request.user_id != auth.user_id {
             return Err((
                 StatusCode::FORBIDDEN,
-                "This log request belongs to a different user".to_string(),
-            ));
+                "This log request belongs to a different user",
+            )
+                .into_response());
         }
 
         let response = LogPollResponse {
@@ -188,11 +1098,34 @@ pub async fn handle_poll(
 }
 
 /// POST /logs/upload - Client uploads logs in response to a request
+///
+/// The body may be sent gzip-compressed with `Content-Encoding: gzip`.
 pub async fn handle_upload(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
-    Json(upload): Json<LogUploadRequest>,
+    GzipJson(mut upload): GzipJson<LogUploadRequest>,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    ensure_ingest_enabled(&state)?;
+    ensure_valid_device_id(&state, &upload.device_id)?;
+
+    // Legacy clients send CSV; store it as JSONL like everything else
+    let mut received = upload.logs.len();
+    if upload.format == UploadFormat::Csv {
+        let data = upload.data.take().unwrap_or_default();
+        let import = export::from_csv(&data, &upload.device_id);
+        if import.skipped > 0 {
+            tracing::warn!(
+                device_id = %upload.device_id,
+                skipped = import.skipped,
+                "Skipped malformed CSV rows in upload"
+            );
+        }
+        received = import.entries.len() + import.skipped;
+        upload.logs = import.entries;
+    }
+    check_upload_counts(received, upload.total_count, state.config.max_upload_logs)?;
+    check_upload_range(&upload)?;
+
     // Parse request ID
     let request_id = Uuid::parse_str(&upload.request_id).map_err(|_| {
         (
@@ -201,9 +1134,11 @@ pub async fn handle_upload(
         )
     })?;
 
+    check_upload_age(&upload, state.config.max_upload_age_days, Utc::now())?;
+
     // Verify the request exists and belongs to this user
-    // We need to check the request manager to get the request
-    let pending = state.request_manager.get_pending(&upload.device_id);
+    // (requests that only just expired are still accepted within the grace period)
+    let pending = state.request_manager.get_uploadable(&upload.device_id);
 
     if let Some(request) = pending {
         if request.id != request_id {
@@ -238,10 +1173,15 @@ pub async fn handle_upload(
         })?;
 
     // Mark request as fulfilled
-    let file_path = format!(
-        "{}/{}/{}.jsonl",
-        auth.user_id, upload.device_id, request_id
-    );
+    let file_path = state
+        .storage
+        .upload_location(auth.user_id, &upload.device_id, request_id)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to locate saved logs: {}", e),
+            )
+        })?;
     state.request_manager.fulfill(request_id, file_path).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -257,22 +1197,287 @@ pub async fn handle_upload(
         "Logs uploaded successfully"
     );
 
+    // Let open dashboards know fresh logs are available
+    state.buffer.notify_upload(UploadNotice {
+        request_id,
+        device_id: upload.device_id,
+        count: upload.logs.len(),
+    });
+
     Ok(StatusCode::CREATED)
 }
 
+/// Whether the server currently accepts new logs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestState {
+    pub enabled: bool,
+}
+
+/// GET /admin/ingest - Report whether ingestion is enabled
+pub async fn handle_get_ingest_state(
+    State(state): State<Arc<AppState>>,
+    _auth: AdminUser,
+) -> Json<IngestState> {
+    Json(IngestState {
+        enabled: state.ingest_enabled.load(Ordering::Relaxed),
+    })
+}
+
+/// Body of `POST /admin/buffer/resize`
+#[derive(Debug, Deserialize)]
+pub struct BufferResize {
+    pub capacity: usize,
+}
+
+/// POST /admin/buffer/resize - Change the live buffer's capacity without a restart
+///
+/// Shrinking drops the oldest entries. Buffers bounded by MAX_BUFFER_BYTES
+/// ignore their entry capacity, so they can't be resized this way. Needs a
+/// token with the admin role.
+pub async fn handle_resize_buffer(
+    State(state): State<Arc<AppState>>,
+    auth: AdminUser,
+    Json(body): Json<BufferResize>,
+) -> Result<Json<BufferStats>, (StatusCode, String)> {
+    if body.capacity == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "capacity must be at least 1".to_string(),
+        ));
+    }
+    if state.config.max_buffer_bytes.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            "Buffer is bounded by MAX_BUFFER_BYTES; capacity does not apply".to_string(),
+        ));
+    }
+
+    let dropped = state.buffer.resize(body.capacity);
+
+    info!(
+        user_id = %auth.user_id,
+        capacity = body.capacity,
+        dropped,
+        "Buffer resized"
+    );
+
+    Ok(Json(state.buffer.stats()))
+}
+
+/// GET /admin/requests/export - Every log request made since startup, for audits
+///
+/// Covers all users and statuses, including requests superseded by a newer one
+/// for the same device; fulfilled requests carry their `log_file_path`. Only
+/// the most recent 10,000 superseded or removed requests are kept. Needs a
+/// token with the admin role.
+pub async fn handle_export_requests(
+    State(state): State<Arc<AppState>>,
+    auth: AdminUser,
+) -> Json<Vec<LogRequest>> {
+    let requests = state.request_manager.history();
+    info!(
+        user_id = %auth.user_id,
+        count = requests.len(),
+        "Exported log request history"
+    );
+    Json(requests)
+}
+
+/// POST /admin/ingest - Pause or resume ingestion for everyone; needs the admin role
+pub async fn handle_set_ingest_state(
+    State(state): State<Arc<AppState>>,
+    auth: AdminUser,
+    Json(body): Json<IngestState>,
+) -> Json<IngestState> {
+    state.ingest_enabled.store(body.enabled, Ordering::Relaxed);
+
+    info!(
+        user_id = %auth.user_id,
+        enabled = body.enabled,
+        "Ingestion state changed"
+    );
+
+    Json(body)
+}
+
+/// Reject uploads of more than `max_logs` entries with 413, and uploads
+/// whose `totalCount` disagrees with the entries received with 400
+///
+/// For CSV uploads, `received` counts skipped malformed rows too.
+fn check_upload_counts(
+    received: usize,
+    total_count: usize,
+    max_logs: Option<usize>,
+) -> Result<(), (StatusCode, String)> {
+    if let Some(max_logs) = max_logs.filter(|&max_logs| received > max_logs) {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Upload has {} entries, more than the {} allowed",
+                received, max_logs
+            ),
+        ));
+    }
+
+    if received != total_count {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "totalCount is {} but the upload has {} entries",
+                total_count, received
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reject uploads whose entries don't all fall within the stated
+/// `[fromTimestamp, toTimestamp]` range (inclusive) with 422
+///
+/// Malformed bounds are rejected with 400, and `fromTimestamp` later than
+/// `toTimestamp` with 422.
+fn check_upload_range(upload: &LogUploadRequest) -> Result<(), (StatusCode, String)> {
+    let from = parse_timestamp("fromTimestamp", &upload.from_timestamp)?;
+    let to = parse_timestamp("toTimestamp", &upload.to_timestamp)?;
+
+    if from > to {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "fromTimestamp {} is after toTimestamp {}",
+                upload.from_timestamp, upload.to_timestamp
+            ),
+        ));
+    }
+
+    if let Some(outside) = upload
+        .logs
+        .iter()
+        .find(|entry| entry.timestamp < from || entry.timestamp > to)
+    {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "Entry '{}' at {} is outside the upload's range {} to {}",
+                outside.id,
+                outside.timestamp.to_rfc3339(),
+                upload.from_timestamp,
+                upload.to_timestamp
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reject uploads whose earliest log is older than `max_age_days`
+fn check_upload_age(
+    upload: &LogUploadRequest,
+    max_age_days: Option<i64>,
+    now: DateTime<Utc>,
+) -> Result<(), (StatusCode, String)> {
+    let Some(max_age_days) = max_age_days else {
+        return Ok(());
+    };
+
+    let from = parse_range_bound("fromTimestamp", Some(&upload.from_timestamp))?.unwrap_or(now);
+    if from < now - chrono::Duration::days(max_age_days) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Upload starts at {}, older than the {}-day limit",
+                upload.from_timestamp, max_age_days
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Query parameters for listing uploads
+#[derive(Deserialize)]
+pub struct UploadListQuery {
+    /// Only include uploads at or after this RFC 3339 timestamp
+    pub from: Option<String>,
+    /// Only include uploads at or before this RFC 3339 timestamp
+    pub to: Option<String>,
+}
+
+/// GET /logs/requests - Every log request of the authenticated user, across devices
+///
+/// Includes fulfilled, expired and cancelled requests, so devices that never
+/// answered stand out.
+pub async fn handle_list_requests(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Json<Vec<LogRequest>> {
+    Json(state.request_manager.list_for_user(auth.user_id))
+}
+
 /// GET /logs/uploads - List all uploaded log files for the authenticated user
 pub async fn handle_list_uploads(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
+    Query(params): Query<UploadListQuery>,
 ) -> Result<Json<Vec<crate::models::LogUploadMetadata>>, (StatusCode, String)> {
-    let uploads = state.storage.list_uploads(auth.user_id).map_err(|e| {
+    let from = parse_range_bound("from", params.from.as_deref())?;
+    let to = parse_range_bound("to", params.to.as_deref())?;
+
+    let uploads = state
+        .storage
+        .list_uploads_between(auth.user_id, from, to)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to list uploads: {}", e),
+            )
+        })?;
+
+    Ok(Json(uploads))
+}
+
+/// GET /logs/uploads/usage - Disk space taken up by the authenticated user's uploads
+pub async fn handle_upload_usage(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<crate::models::UploadUsage>, (StatusCode, String)> {
+    let usage = state.storage.upload_usage(auth.user_id).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to list uploads: {}", e),
+            format!("Failed to measure uploads: {}", e),
         )
     })?;
 
-    Ok(Json(uploads))
+    Ok(Json(usage))
+}
+
+/// Parse an optional RFC 3339 query bound, rejecting malformed values
+fn parse_range_bound(
+    name: &str,
+    value: Option<&str>,
+) -> Result<Option<DateTime<Utc>>, (StatusCode, String)> {
+    value.map(|v| parse_timestamp(name, v)).transpose()
+}
+
+/// Parse the RFC 3339 timestamp `name`, rejecting malformed values with 400
+fn parse_timestamp(name: &str, value: &str) -> Result<DateTime<Utc>, (StatusCode, String)> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid '{}' timestamp: {}", name, e),
+            )
+        })
+}
+
+/// Query parameters for downloading an upload
+#[derive(Deserialize)]
+pub struct UploadDownloadQuery {
+    /// Add `localTimestamp` in the configured display timezone
+    #[serde(rename = "localTime", default)]
+    pub local_time: bool,
 }
 
 /// GET /logs/uploads/:request_id - Download a specific uploaded log file
@@ -280,9 +1485,131 @@ pub async fn handle_get_upload(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
     Path(request_id_str): Path<String>,
-) -> Result<Json<Vec<LogEntry>>, (StatusCode, String)> {
+    Query(params): Query<UploadDownloadQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let (request_id, device_id) = find_upload(&state, auth.user_id, &request_id_str)?;
+
+    // Read logs from storage
+    let logs = state
+        .storage
+        .read_upload(auth.user_id, &device_id, request_id)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read logs: {}", e),
+            )
+        })?;
+
+    if state.config.delete_request_on_download {
+        state.request_manager.remove(request_id);
+    }
+
+    match state.config.display_timezone {
+        Some(timezone) if params.local_time => {
+            let localized: Vec<LocalizedLogEntry> = logs
+                .into_iter()
+                .map(|entry| LocalizedLogEntry::new(entry, timezone))
+                .collect();
+            Ok(Json(localized).into_response())
+        }
+        _ => Ok(Json(logs).into_response()),
+    }
+}
+
+/// DELETE /logs/uploads/:request_id - Delete a stored upload
+///
+/// Responds 204 once the upload is gone, or 404 if it doesn't exist.
+pub async fn handle_delete_upload(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(request_id_str): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (request_id, device_id) = find_upload(&state, auth.user_id, &request_id_str)?;
+
+    state
+        .storage
+        .delete_upload(auth.user_id, &device_id, request_id)
+        .map_err(|e| match e {
+            StorageError::NotFound => (StatusCode::NOT_FOUND, "Upload not found".to_string()),
+            e => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to delete upload: {}", e),
+            ),
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /logs/uploads/:request_id/raw - Stream a stored upload as NDJSON
+///
+/// Sends the stored lines as-is instead of parsing them into entries, so
+/// large uploads are never held in memory.
+pub async fn handle_get_upload_raw(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(request_id_str): Path<String>,
+) -> Result<Response, (StatusCode, String)> {
+    let (request_id, device_id) = find_upload(&state, auth.user_id, &request_id_str)?;
+
+    let reader = state
+        .storage
+        .open_upload(auth.user_id, &device_id, request_id)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read logs: {}", e),
+            )
+        })?;
+
+    if state.config.delete_request_on_download {
+        state.request_manager.remove(request_id);
+    }
+
+    // File reads block, so they run on the blocking pool and feed the body
+    let (tx, rx) = tokio::sync::mpsc::channel(RAW_STREAM_CHANNEL_CHUNKS);
+    tokio::task::spawn_blocking(move || stream_reader(reader, tx));
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)),
+    )
+        .into_response())
+}
+
+/// Chunks read ahead of a slow `/raw` client
+const RAW_STREAM_CHANNEL_CHUNKS: usize = 4;
+
+/// Bytes read from storage per `/raw` body chunk
+const RAW_STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Pump a reader into a body channel until EOF, an error, or the client leaves
+fn stream_reader(
+    mut reader: Box<dyn std::io::Read + Send>,
+    tx: tokio::sync::mpsc::Sender<std::io::Result<axum::body::Bytes>>,
+) {
+    let mut buf = vec![0; RAW_STREAM_CHUNK_BYTES];
+    loop {
+        let chunk = match reader.read(&mut buf) {
+            Ok(0) => return,
+            Ok(n) => Ok(axum::body::Bytes::copy_from_slice(&buf[..n])),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => Err(e),
+        };
+        let failed = chunk.is_err();
+        if tx.blocking_send(chunk).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Resolve an upload's request id and device for the authenticated user
+fn find_upload(
+    state: &AppState,
+    user_id: Uuid,
+    request_id_str: &str,
+) -> Result<(Uuid, String), (StatusCode, String)> {
     // Parse request ID
-    let request_id = Uuid::parse_str(&request_id_str).map_err(|_| {
+    let request_id = Uuid::parse_str(request_id_str).map_err(|_| {
         (
             StatusCode::BAD_REQUEST,
             "Invalid request ID format".to_string(),
@@ -290,7 +1617,7 @@ pub async fn handle_get_upload(
     })?;
 
     // List all uploads to find the device_id for this request
-    let uploads = state.storage.list_uploads(auth.user_id).map_err(|e| {
+    let uploads = state.storage.list_uploads(user_id).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             format!("Failed to list uploads: {}", e),
@@ -303,31 +1630,1838 @@ pub async fn handle_get_upload(
         .map(|u| u.device_id.clone())
         .ok_or((StatusCode::NOT_FOUND, "Upload not found".to_string()))?;
 
-    // Read logs from storage
-    let logs = state
+    Ok((request_id, device_id))
+}
+
+/// GET /logs/uploads/device/:device_id/archive - Download all of a device's uploads
+///
+/// Concatenates the uploads into one JSONL response, reading them one at a
+/// time as the body is streamed so only a single upload is held in memory.
+pub async fn handle_download_device_archive(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(device_id): Path<String>,
+) -> Result<Response, (StatusCode, String)> {
+    let uploads = state
         .storage
-        .read_upload(auth.user_id, &device_id, request_id)
+        .list_uploads_for_device(auth.user_id, &device_id)
         .map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to read logs: {}", e),
+                format!("Failed to list uploads: {}", e),
             )
         })?;
 
-    Ok(Json(logs))
+    if uploads.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "No uploads for this device".to_string(),
+        ));
+    }
+
+    // File reads block, so they run on the blocking pool and feed the body
+    let (tx, rx) = tokio::sync::mpsc::channel(RAW_STREAM_CHANNEL_CHUNKS);
+    let storage = state.storage.clone();
+    let user_id = auth.user_id;
+    tokio::task::spawn_blocking(move || {
+        for upload in uploads {
+            let chunk = upload_chunk(storage.as_ref(), user_id, &upload)
+                .map(axum::body::Bytes::from)
+                .map_err(std::io::Error::other);
+            let failed = chunk.is_err();
+            if tx.blocking_send(chunk).is_err() || failed {
+                return;
+            }
+        }
+    });
+
+    let disposition = format!(
+        "attachment; filename=\"{}.jsonl\"",
+        sanitize_filename(&device_id)
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)),
+    )
+        .into_response())
+}
+
+/// One upload's entries as JSONL, for the device archive body
+fn upload_chunk(
+    storage: &dyn UploadStore,
+    user_id: Uuid,
+    upload: &LogUploadMetadata,
+) -> Result<String, StorageError> {
+    let request_id = Uuid::parse_str(&upload.request_id).map_err(|_| StorageError::NotFound)?;
+    let logs = storage.read_upload(user_id, &upload.device_id, request_id)?;
+
+    let mut chunk = String::new();
+    for entry in &logs {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+        chunk.push_str(&line);
+        chunk.push('\n');
+    }
+    Ok(chunk)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        config::Config,
+        health::HealthStatus,
+        models::{LogLevel, LogRequestStatus},
+        storage::LogStorage,
+    };
+    use tempfile::TempDir;
+
+    /// Build application state backed by a temporary upload directory
+    fn test_state(config: Config) -> (Arc<AppState>, TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf()).unwrap();
+        let storage = Arc::new(storage);
+        (Arc::new(AppState::new(config, storage, None)), dir)
+    }
+
+    #[test]
+    fn test_apply_theme() {
+        let index = r#"<!doctype html><html lang="en"><body></body></html>"#.to_string();
+
+        let dark = apply_theme(index.clone(), Some("dark")).unwrap();
+        assert!(dark.contains(r#"<html class="dark" lang="en">"#));
+
+        for theme in [None, Some("light")] {
+            let light = apply_theme(index.clone(), theme).unwrap();
+            assert!(!light.contains("dark"));
+        }
+
+        let (status, _) = apply_theme(index, Some("sepia")).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
 
     #[tokio::test]
     async fn test_info_handler() {
-        let response = handle_info().await;
+        let (state, _dir) = test_state(Config::default());
+        let response = handle_info(State(state)).await;
         assert!(response.contains("Log Server (Rust)"));
         assert!(response.contains("POST /logs"));
         assert!(response.contains("GET /logs"));
         assert!(response.contains("DELETE /logs"));
         assert!(response.contains("HTML dashboard"));
+        assert!(response.contains("Authentication: disabled"));
+    }
+
+    #[tokio::test]
+    async fn test_info_reflects_configuration() {
+        let (state, _dir) = test_state(Config {
+            port: 9123,
+            capacity: 1234,
+            ..Config::default()
+        });
+        for id in ["a", "b"] {
+            let entry = create_entry(id, "2024-03-01T12:00:00Z");
+            state.buffer.append(entry);
+        }
+
+        let response = handle_info(State(state)).await;
+        assert!(response.contains("port 9123"));
+        assert!(response.contains("Buffer: 2 / 1234 entries"));
+    }
+
+    fn create_entry(id: &str, timestamp: &str) -> LogEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "timestamp": timestamp,
+            "level": "info",
+            "message": "hello",
+            "deviceId": "device-1",
+            "source": "test",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_logs_delta_returns_unknown_entries() {
+        let (state, _dir) = test_state(Config::default());
+        for id in ["a", "b", "c", "d"] {
+            state
+                .buffer
+                .append(create_entry(id, "2024-03-01T12:00:00Z"));
+        }
+
+        let known = vec!["a".to_string(), "c".to_string(), "gone".to_string()];
+        let Json(missing) = handle_logs_delta(State(state), Json(known)).await;
+
+        let ids: Vec<_> = missing.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["b", "d"]);
+    }
+
+    #[tokio::test]
+    async fn test_entries_below_min_ingest_level_dropped() {
+        let (state, _dir) = test_state(Config {
+            min_ingest_level: LogLevel::Warning,
+            ..Config::default()
+        });
+        let submit = |id: &str, level: &str| {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.level = level.to_string();
+            handle_receive_log(State(state.clone()), None, EntryJson(entry))
+        };
+
+        let response = submit("debug", "debug").await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let response = submit("error", "error").await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let ids: Vec<String> = state.buffer.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["error"]);
+    }
+
+    #[tokio::test]
+    async fn test_log_chunk_cursor_walks_whole_buffer() {
+        let (state, _dir) = test_state(Config::default());
+        for i in 0..7 {
+            state
+                .buffer
+                .append(create_entry(&i.to_string(), "2024-03-01T12:00:00Z"));
+        }
+
+        let mut cursor = None;
+        let mut seen = Vec::new();
+        loop {
+            let query = ChunkQuery {
+                cursor: cursor.clone(),
+                size: Some(3),
+            };
+            let Json(chunk) = handle_get_log_chunk(State(state.clone()), Query(query))
+                .await
+                .unwrap();
+            assert!(chunk.entries.len() <= 3);
+            seen.extend(chunk.entries.into_iter().map(|e| e.id));
+            cursor = Some(chunk.next_cursor);
+            if !chunk.has_more {
+                break;
+            }
+            // Arrivals mid-walk land after the cursor instead of shifting it
+            if seen.len() == 3 {
+                state
+                    .buffer
+                    .append(create_entry("late", "2024-03-01T12:00:00Z"));
+            }
+        }
+        assert_eq!(seen, ["0", "1", "2", "3", "4", "5", "6", "late"]);
+
+        let query = ChunkQuery {
+            cursor: Some("not-a-cursor".to_string()),
+            size: None,
+        };
+        let err = handle_get_log_chunk(State(state), Query(query))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_clear_logs_before_cutoff() {
+        let (state, _dir) = test_state(Config::default());
+        for (id, hour) in [("old", 8), ("new", 12)] {
+            let timestamp = format!("2024-03-01T{:02}:00:00Z", hour);
+            state.buffer.append(create_entry(id, &timestamp));
+        }
+
+        let query = ClearLogsQuery {
+            before: Some("2024-03-01T10:00:00Z".to_string()),
+        };
+        let response = handle_clear_logs(State(state.clone()), Query(query))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let remaining = state.buffer.get_all();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "new");
+
+        let query = ClearLogsQuery {
+            before: Some("not-a-date".to_string()),
+        };
+        let (status, _) = handle_clear_logs(State(state), Query(query))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_archive_min_level_keeps_buffer_complete() {
+        let archive_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            archive_dir: Some(archive_dir.path().to_path_buf()),
+            archive_min_level: LogLevel::Warning,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+
+        for (id, level) in [("1", "debug"), ("2", "error"), ("3", "info")] {
+            let mut entry = create_entry(id, &Utc::now().to_rfc3339());
+            entry.level = level.to_string();
+            handle_receive_log(State(state.clone()), None, EntryJson(entry)).await;
+        }
+
+        assert_eq!(state.buffer.count(), 3);
+
+        let file = archive_dir
+            .path()
+            .join(format!("{}.jsonl", Utc::now().format("%Y-%m-%d")));
+        let archived: Vec<LogEntry> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].id, "2");
+    }
+
+    #[tokio::test]
+    async fn test_delete_upload() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        let entry = create_entry("1", "2024-03-01T12:00:00Z");
+        state
+            .storage
+            .save_upload(user_id, "device-1", request_id, &[entry])
+            .unwrap();
+
+        let delete = || {
+            handle_delete_upload(
+                State(state.clone()),
+                AuthUser { user_id },
+                Path(request_id.to_string()),
+            )
+        };
+        assert_eq!(delete().await.unwrap(), StatusCode::NO_CONTENT);
+        assert_eq!(delete().await.unwrap_err().0, StatusCode::NOT_FOUND);
+        assert!(state.storage.list_uploads(user_id).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_device_archive_combines_uploads() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+
+        let uploads = [
+            ("device-1", "first"),
+            ("device-1", "second"),
+            ("device-2", "other"),
+        ];
+        for (device_id, id) in uploads {
+            let entry = create_entry(id, "2024-03-01T12:00:00Z");
+            state
+                .storage
+                .save_upload(user_id, device_id, Uuid::new_v4(), &[entry])
+                .unwrap();
+        }
+
+        let response = handle_download_device_archive(
+            State(state.clone()),
+            AuthUser { user_id },
+            Path("device-1".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut ids: Vec<String> = String::from_utf8(body.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<LogEntry>(line).unwrap().id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["first", "second"]);
+
+        let (status, _) = handle_download_device_archive(
+            State(state),
+            AuthUser { user_id },
+            Path("device-3".to_string()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_counts_levels_and_requests() {
+        let config = Config {
+            capacity: 100,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        for (id, level) in [
+            ("1", "debug"),
+            ("2", "error"),
+            ("3", "error"),
+            ("4", "WARNING"),
+            ("5", "bogus"),
+        ] {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.level = level.to_string();
+            state.buffer.append(entry);
+        }
+        state
+            .request_manager
+            .create_request(Uuid::new_v4(), "device-1".to_string());
+
+        let Json(metrics) = handle_metrics(State(state)).await;
+
+        assert_eq!(metrics.count, 5);
+        assert_eq!(metrics.capacity, 100);
+        assert_eq!(metrics.levels["debug"], 1);
+        assert_eq!(metrics.levels["error"], 2);
+        assert_eq!(metrics.levels["warning"], 1);
+        assert_eq!(metrics.levels["info"], 1);
+        assert_eq!(metrics.levels["critical"], 0);
+        assert_eq!(metrics.levels.len(), LogLevel::ALL.len());
+        assert_eq!(metrics.requests.total, 1);
+        assert_eq!(metrics.requests.pending, 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_prometheus_content_type() {
+        let (state, _dir) = test_state(Config::default());
+        state
+            .buffer
+            .append(create_entry("1", "2024-03-01T12:00:00Z"));
+
+        let response = handle_metrics_prometheus(State(state)).await;
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            PROMETHEUS_CONTENT_TYPE
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("applog_entries_total{level=\"info\"} 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_limits_reflect_config() {
+        let config = Config {
+            capacity: 50,
+            required_metadata_keys: vec!["build".to_string()],
+            max_upload_age_days: Some(7),
+            max_upload_logs: Some(1_000),
+            clamp_future_timestamps: true,
+            future_timestamp_tolerance_secs: 30,
+            poll_min_interval_ms: 1_000,
+            poll_burst: 3,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+
+        let Json(limits) = handle_limits(State(state)).await;
+        let value = serde_json::to_value(&limits).unwrap();
+
+        assert_eq!(value["maxBodyBytes"], ingest::MAX_BODY_BYTES);
+        assert_eq!(value["bufferCapacity"], 50);
+        assert!(value["maxBufferBytes"].is_null());
+        assert_eq!(value["requiredMetadataKeys"], serde_json::json!(["build"]));
+        assert_eq!(value["maxUploadAgeDays"], 7);
+        assert_eq!(value["maxUploadLogs"], 1_000);
+        assert_eq!(value["maxUploadBytes"], ingest::MAX_BODY_BYTES);
+        assert_eq!(value["futureTimestampToleranceSecs"], 30);
+        assert_eq!(value["pollMinIntervalMs"], 1_000);
+        assert_eq!(value["pollBurst"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_buffer_evicting_header_only_when_full() {
+        let config = Config {
+            capacity: 2,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+
+        let mut evicting = Vec::new();
+        for id in ["a", "b", "c"] {
+            let entry = create_entry(id, &Utc::now().to_rfc3339());
+            let response = handle_receive_log(State(state.clone()), None, EntryJson(entry)).await;
+            evicting.push(response.headers().get(BUFFER_EVICTING_HEADER).cloned());
+        }
+
+        assert_eq!(
+            evicting,
+            [None, None, Some(HeaderValue::from_static("true"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hot_and_cold_buffers_receive_entries() {
+        let config = Config {
+            capacity: 2,
+            cold_capacity: Some(10),
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+
+        for id in ["a", "b", "c"] {
+            let entry = create_entry(id, &Utc::now().to_rfc3339());
+            handle_receive_log(State(state.clone()), None, EntryJson(entry)).await;
+        }
+
+        let hot: Vec<String> = state.buffer.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(hot, vec!["b", "c"]);
+        let cold = state.cold_buffer.as_ref().unwrap();
+        assert_eq!(cold.count(), 3);
+
+        let query = SearchQuery {
+            q: "HELLO".to_string(),
+            highlight: None,
+        };
+        let Json(found) = handle_search_logs(State(state), Query(query))
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].entry.id, "a");
+        assert!(found[0].highlights.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_highlight_spans_cover_matches() {
+        let (state, _dir) = test_state(Config::default());
+        for (id, message) in [
+            ("1", "retry 1 of 3 after 250ms"),
+            ("2", "no digits here"),
+            ("3", "payload 42 bytes"),
+        ] {
+            let mut entry = create_entry(id, &Utc::now().to_rfc3339());
+            entry.message = message.to_string();
+            handle_receive_log(State(state.clone()), None, EntryJson(entry)).await;
+        }
+
+        let query = SearchQuery {
+            q: String::new(),
+            highlight: Some(r"\d+".to_string()),
+        };
+        let Json(found) = handle_search_logs(State(state.clone()), Query(query))
+            .await
+            .unwrap();
+
+        let ids: Vec<&str> = found.iter().map(|r| r.entry.id.as_str()).collect();
+        assert_eq!(ids, ["1", "3"]);
+        let matched: Vec<&str> = found[0]
+            .highlights
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|span| &found[0].entry.message[span.start..span.end])
+            .collect();
+        assert_eq!(matched, ["1", "3", "250"]);
+        assert_eq!(
+            found[1].highlights,
+            Some(vec![MatchSpan { start: 8, end: 10 }])
+        );
+
+        let query = SearchQuery {
+            q: String::new(),
+            highlight: Some("(".to_string()),
+        };
+        let err = handle_search_logs(State(state), Query(query))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_poll_rate_limited_per_device() {
+        let config = Config {
+            poll_min_interval_ms: 60_000,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let user_id = Uuid::new_v4();
+        let poll = |device_id: &str| {
+            let query = PollQuery {
+                device_id: device_id.to_string(),
+            };
+            handle_poll(State(state.clone()), AuthUser { user_id }, Query(query))
+        };
+
+        assert!(poll("device-1").await.is_ok());
+
+        let response = poll("device-1").await.unwrap_err();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+
+        assert!(poll("device-2").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_strict_device_ids() {
+        let config = Config {
+            strict_device_ids: true,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let user_id = Uuid::new_v4();
+        let create = |device_id: &str| {
+            let body = CreateRequestBody {
+                device_id: device_id.to_string(),
+                expires_in_hours: None,
+            };
+            handle_create_request(State(state.clone()), AuthUser { user_id }, Json(body))
+        };
+
+        let err = create("my-iphone").await.unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        assert!(create(&Uuid::new_v4().to_string()).await.is_ok());
+
+        let query = PollQuery {
+            device_id: "my-iphone".to_string(),
+        };
+        let response = handle_poll(State(state.clone()), AuthUser { user_id }, Query(query))
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let mut entry = create_entry("1", &Utc::now().to_rfc3339());
+        entry.device_id = "my-iphone".to_string();
+        let response = handle_receive_log(State(state.clone()), None, EntryJson(entry)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(state.buffer.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_lenient_device_ids() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let body = CreateRequestBody {
+            device_id: "my-iphone".to_string(),
+            expires_in_hours: None,
+        };
+        let result = handle_create_request(State(state), AuthUser { user_id }, Json(body)).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_create_request_expiry_override_clamped() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let create = |expires_in_hours: Option<i64>| {
+            let body = CreateRequestBody {
+                device_id: "device-1".to_string(),
+                expires_in_hours,
+            };
+            handle_create_request(State(state.clone()), AuthUser { user_id }, Json(body))
+        };
+        let lifetime = |request: &LogRequest| request.expires_at - request.requested_at;
+
+        let Json(request) = create(None).await.unwrap();
+        assert_eq!(lifetime(&request), chrono::Duration::hours(24));
+
+        let Json(request) = create(Some(2)).await.unwrap();
+        assert_eq!(lifetime(&request), chrono::Duration::hours(2));
+
+        let Json(request) = create(Some(10_000)).await.unwrap();
+        assert_eq!(
+            lifetime(&request),
+            chrono::Duration::hours(MAX_REQUEST_TTL_HOURS)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_requests_includes_fulfilled_and_expired() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+
+        let fulfilled = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+        let upload = upload_for(&fulfilled, Utc::now());
+        handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(upload))
+            .await
+            .unwrap();
+        let expired = state.request_manager.create_request_expiring(
+            Uuid::new_v4(),
+            "device-2".to_string(),
+            chrono::Duration::hours(-1),
+        );
+        let superseded = state
+            .request_manager
+            .create_request(user_id, "device-3".to_string());
+        let newer = state
+            .request_manager
+            .create_request(user_id, "device-3".to_string());
+
+        let Json(exported) = handle_export_requests(State(state), AdminUser { user_id }).await;
+
+        assert_eq!(exported.len(), 4);
+        let find = |id: Uuid| exported.iter().find(|req| req.id == id).unwrap();
+        assert_eq!(find(fulfilled.id).status, LogRequestStatus::Fulfilled);
+        assert!(find(fulfilled.id).log_file_path.is_some());
+        assert_eq!(find(expired.id).status, LogRequestStatus::Expired);
+        assert_eq!(find(superseded.id).status, LogRequestStatus::Cancelled);
+        assert_eq!(find(newer.id).status, LogRequestStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_request() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let cancel = |user_id: Uuid| {
+            let query = CancelRequestQuery {
+                device_id: "device-1".to_string(),
+            };
+            handle_cancel_request(State(state.clone()), AuthUser { user_id }, Query(query))
+        };
+
+        assert_eq!(cancel(user_id).await.unwrap_err().0, StatusCode::NOT_FOUND);
+
+        state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+        let err = cancel(Uuid::new_v4()).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        assert_eq!(cancel(user_id).await.unwrap(), StatusCode::NO_CONTENT);
+
+        let query = PollQuery {
+            device_id: "device-1".to_string(),
+        };
+        let Json(pending) = handle_poll(State(state.clone()), AuthUser { user_id }, Query(query))
+            .await
+            .unwrap();
+        assert!(pending.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receive_log_tracks_server_fields() {
+        let config = Config {
+            track_server_fields: true,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+
+        let response = handle_receive_log(
+            State(state.clone()),
+            None,
+            EntryJson(create_entry("", "2024-03-01T12:00:00Z")),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let stored = &state.buffer.get_all()[0];
+        assert_eq!(stored.metadata[ingest::SERVER_FIELDS_KEY], "id");
+
+        // Fully client-supplied entries carry no marker
+        handle_receive_log(
+            State(state.clone()),
+            None,
+            EntryJson(create_entry("client-id", "2024-03-01T12:00:00Z")),
+        )
+        .await;
+        let stored = &state.buffer.get_all()[1];
+        assert!(!stored.metadata.contains_key(ingest::SERVER_FIELDS_KEY));
+    }
+
+    #[tokio::test]
+    async fn test_receive_log_returns_stored_entry() {
+        let (state, _dir) = test_state(Config::default());
+        let mut entry = create_entry("", "2024-03-01T12:00:00Z");
+        entry.message = "saved\n".to_string();
+
+        let response = handle_receive_log(State(state.clone()), None, EntryJson(entry)).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let stored: LogEntry = serde_json::from_slice(&body).unwrap();
+        assert!(Uuid::parse_str(&stored.id).is_ok());
+        assert_eq!(stored.message, "saved\n");
+        assert_eq!(state.buffer.get_all()[0].id, stored.id);
+    }
+
+    #[tokio::test]
+    async fn test_receive_log_rate_limited_per_source() {
+        let config = Config {
+            ingest_rate_per_sec: 1,
+            ingest_burst: 2,
+            infer_source_from_file: true,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let send = |id: &str, source: &str| {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.source = source.to_string();
+            handle_receive_log(State(state.clone()), None, EntryJson(entry))
+        };
+
+        assert_eq!(send("a", "ios").await.status(), StatusCode::CREATED);
+        assert_eq!(send("b", "ios").await.status(), StatusCode::CREATED);
+
+        let response = send("c", "ios").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        assert_eq!(send("d", "cli").await.status(), StatusCode::CREATED);
+
+        // Limited on the source as stored, here inferred from the file path
+        let mut entry = create_entry("e", "2024-03-01T12:00:00Z");
+        entry.source = String::new();
+        entry.file = "ios/Network/Client.swift".to_string();
+        let response = handle_receive_log(State(state.clone()), None, EntryJson(entry)).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Refill timing is covered by RateLimiter's own tests
+        let ids: Vec<String> = state.buffer.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec!["a", "b", "d"]);
+    }
+
+    #[tokio::test]
+    async fn test_receive_oslog_rate_limited_per_source() {
+        let config = Config {
+            ingest_rate_per_sec: 1,
+            ingest_burst: 2,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let send = |subsystems: &[&str]| {
+            let entries = subsystems
+                .iter()
+                .map(|subsystem| {
+                    serde_json::from_value(serde_json::json!({
+                        "timestamp": "2024-01-15 10:30:00.123456-0800",
+                        "eventMessage": "hello",
+                        "subsystem": subsystem,
+                    }))
+                    .unwrap()
+                })
+                .collect();
+            let query = OsLogQuery {
+                device_id: "device-1".to_string(),
+            };
+            handle_receive_oslog(State(state.clone()), Query(query), EntryJson(entries))
+        };
+
+        let response = send(&["com.example.app", "com.example.app", "com.example.app"]).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        assert_eq!(state.buffer.count(), 0);
+
+        let response = send(&["com.example.other"]).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(state.buffer.count(), 1);
+
+        // The dropped batch spent none of the source's tokens
+        let response = send(&["com.example.app", "com.example.app"]).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(state.buffer.count(), 3);
+    }
+
+    fn upload_for(request: &LogRequest, from_timestamp: DateTime<Utc>) -> LogUploadRequest {
+        LogUploadRequest {
+            request_id: request.id.to_string(),
+            device_id: request.device_id.clone(),
+            logs: vec![create_entry("a", &from_timestamp.to_rfc3339())],
+            format: UploadFormat::Json,
+            data: None,
+            from_timestamp: from_timestamp.to_rfc3339(),
+            to_timestamp: from_timestamp.to_rfc3339(),
+            total_count: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_max_age() {
+        let config = Config {
+            max_upload_age_days: Some(7),
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+
+        let stale = upload_for(&request, Utc::now() - chrono::Duration::days(8));
+        let (status, message) =
+            handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(stale))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("7-day limit"));
+
+        let recent = upload_for(&request, Utc::now() - chrono::Duration::days(6));
+        let status = handle_upload(State(state), AuthUser { user_id }, GzipJson(recent))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_upload_over_entry_cap_rejected() {
+        let config = Config {
+            max_upload_logs: Some(2),
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+
+        let mut upload = upload_for(&request, Utc::now());
+        upload.logs = ["a", "b", "c"]
+            .map(|id| create_entry(id, &upload.from_timestamp))
+            .into();
+        upload.total_count = 3;
+
+        let (status, message) =
+            handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(upload))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(message.contains("2 allowed"));
+        assert!(state.storage.list_uploads(user_id).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upload_entries_within_range_accepted() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+
+        let mut upload = upload_for(&request, Utc::now());
+        upload.from_timestamp = "2024-03-01T12:00:00Z".to_string();
+        upload.to_timestamp = "2024-03-01T13:00:00Z".to_string();
+        upload.logs = vec![
+            create_entry("first", "2024-03-01T12:00:00Z"),
+            create_entry("middle", "2024-03-01T12:30:00Z"),
+            create_entry("last", "2024-03-01T13:00:00Z"),
+        ];
+        upload.total_count = 3;
+
+        let status = handle_upload(State(state), AuthUser { user_id }, GzipJson(upload))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_upload_entry_outside_range_rejected() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+
+        let mut upload = upload_for(&request, Utc::now());
+        upload.from_timestamp = "2024-03-01T12:00:00Z".to_string();
+        upload.to_timestamp = "2024-03-01T13:00:00Z".to_string();
+        upload.logs = vec![
+            create_entry("inside", "2024-03-01T12:30:00Z"),
+            create_entry("late", "2024-03-01T13:00:01Z"),
+        ];
+        upload.total_count = 2;
+
+        let (status, message) = handle_upload(
+            State(state.clone()),
+            AuthUser { user_id },
+            GzipJson(upload.clone()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(message.contains("'late'"));
+
+        // Bounds given the wrong way round
+        upload.logs.truncate(1);
+        upload.total_count = 1;
+        upload.to_timestamp = "2024-03-01T11:00:00Z".to_string();
+        let (status, _) =
+            handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(upload))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(state.storage.list_uploads(user_id).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upload_count_mismatch_rejected() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+
+        let mut upload = upload_for(&request, Utc::now());
+        upload.total_count = 5;
+
+        let (status, message) =
+            handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(upload))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("totalCount is 5"));
+        assert!(state.storage.list_uploads(user_id).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_with_preset() {
+        let config = Config {
+            filter_presets: crate::presets::parse_presets(
+                r#"{"ios-warnings": {"minLevel": "warning", "sources": ["ios-device"]}}"#,
+            )
+            .unwrap(),
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+
+        for (id, level, source) in [
+            ("1", "error", "ios-device"),
+            ("2", "info", "ios-device"),
+            ("3", "error", "cli"),
+        ] {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.level = level.to_string();
+            entry.source = source.to_string();
+            state.buffer.append(entry);
+        }
+
+        let query = |preset: &str| {
+            Query(LogsQuery {
+                preset: Some(preset.to_string()),
+                ..LogsQuery::default()
+            })
+        };
+
+        let response = handle_get_all_logs(
+            State(state.clone()),
+            query("ios-warnings"),
+            HeaderMap::new(),
+        )
+        .await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let entries: Vec<LogEntry> = serde_json::from_slice(&body).unwrap();
+        let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["1"]);
+
+        let response = handle_get_all_logs(State(state), query("missing"), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_csv_upload_stored_as_entries() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+
+        let mut upload = upload_for(&request, Utc::now());
+        upload.logs.clear();
+        upload.format = UploadFormat::Csv;
+        upload.data = Some(
+            "id,timestamp,level,source,deviceId,userId,message,file,function,line,tags\n\
+             a,2024-03-01T12:00:00Z,error,ios,,,\"Sync failed, retrying\",Sync.swift,run(),42,net\n\
+             b,2024-03-01T12:00:01Z,info,ios,,,Recovered,,,,\n"
+                .to_string(),
+        );
+        upload.total_count = 2;
+        upload.from_timestamp = "2024-03-01T12:00:00Z".to_string();
+        upload.to_timestamp = "2024-03-01T12:00:01Z".to_string();
+
+        let status = handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(upload))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let stored = state
+            .storage
+            .read_upload(user_id, "device-1", request.id)
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].message, "Sync failed, retrying");
+        assert_eq!(stored[0].line, 42);
+        assert_eq!(stored[0].tags, vec!["net"]);
+        assert_eq!(stored[1].device_id, "device-1");
+    }
+
+    #[tokio::test]
+    async fn test_health_degraded_when_jwt_key_missing() {
+        let upload_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            upload_dir: upload_dir.path().to_path_buf(),
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config.clone());
+        let Json(summary) = handle_health_summary(State(state)).await;
+        assert_eq!(summary.status, HealthStatus::Ok);
+
+        // Configured, but the validator failed to load
+        let config = Config {
+            jwt_public_key_path: Some("/nonexistent/key.pem".to_string()),
+            ..config
+        };
+        let (state, _dir) = test_state(config);
+        let Json(summary) = handle_health_summary(State(state)).await;
+        assert_eq!(summary.checks.auth.status, HealthStatus::Degraded);
+        assert_eq!(summary.checks.storage.status, HealthStatus::Ok);
+        assert_eq!(summary.status, HealthStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn test_healthz_reports_uptime() {
+        let (state, _dir) = test_state(Config::default());
+
+        let response = handle_healthz(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["status"], "ok");
+        assert!(value["uptime_secs"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn test_upload_notifies_stream_subscribers() {
+        let (state, _dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+        let mut receiver = state.buffer.subscribe();
+
+        let upload = upload_for(&request, Utc::now());
+        handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(upload))
+            .await
+            .unwrap();
+
+        let BufferEvent::Upload(notice) = receiver.try_recv().unwrap() else {
+            panic!("expected an upload event");
+        };
+        assert_eq!(
+            notice,
+            UploadNotice {
+                request_id: request.id,
+                device_id: "device-1".to_string(),
+                count: 1,
+            }
+        );
+
+        let event = stream_event(BufferEvent::Upload(notice)).unwrap();
+        assert!(format!("{:?}", event).contains("upload"));
+    }
+
+    #[tokio::test]
+    async fn test_raw_upload_streams_file_contents() {
+        let (state, dir) = test_state(Config::default());
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        let logs: Vec<LogEntry> = (0..3)
+            .map(|i| create_entry(&i.to_string(), &Utc::now().to_rfc3339()))
+            .collect();
+        state
+            .storage
+            .save_upload(user_id, "device-1", request_id, &logs)
+            .unwrap();
+
+        let response = handle_get_upload_raw(
+            State(state.clone()),
+            AuthUser { user_id },
+            Path(request_id.to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let file = dir
+            .path()
+            .join(user_id.to_string())
+            .join("device-1")
+            .join(format!("{}.jsonl", request_id));
+        assert_eq!(body, std::fs::read(file).unwrap());
+
+        let err = handle_get_upload_raw(
+            State(state),
+            AuthUser { user_id },
+            Path(Uuid::new_v4().to_string()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_delete_request_on_download() {
+        let config = Config {
+            delete_request_on_download: true,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let user_id = Uuid::new_v4();
+        let request = state
+            .request_manager
+            .create_request(user_id, "device-1".to_string());
+        let upload = upload_for(&request, Utc::now());
+        handle_upload(State(state.clone()), AuthUser { user_id }, GzipJson(upload))
+            .await
+            .unwrap();
+        assert_eq!(state.request_manager.stats().fulfilled, 1);
+
+        let download = |state: Arc<AppState>| {
+            handle_get_upload(
+                State(state),
+                AuthUser { user_id },
+                Path(request.id.to_string()),
+                Query(UploadDownloadQuery { local_time: false }),
+            )
+        };
+        let response = download(state.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.request_manager.stats().total, 0);
+
+        // The stored upload is still there
+        let response = download(state.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    async fn get_logs_at(
+        state: &Arc<AppState>,
+        file: Option<&str>,
+        line: Option<u32>,
+    ) -> Vec<String> {
+        let query = LogsQuery {
+            file: file.map(str::to_string),
+            line,
+            ..LogsQuery::default()
+        };
+        let response =
+            handle_get_all_logs(State(state.clone()), Query(query), HeaderMap::new()).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let entries: Vec<LogEntry> = serde_json::from_slice(&body).unwrap();
+        entries.into_iter().map(|e| e.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_by_file_and_line() {
+        let (state, _dir) = test_state(Config::default());
+        for (id, file, line) in [
+            ("1", "MyApp/Network/Client.swift", 42),
+            ("2", "Client.swift", 7),
+            ("3", "MyApp/Network/ClientTests.swift", 42),
+            ("4", "C:\\src\\Client.swift", 42),
+        ] {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.file = file.to_string();
+            entry.line = line;
+            state.buffer.append(entry);
+        }
+
+        assert_eq!(
+            get_logs_at(&state, Some("Client.swift"), None).await,
+            vec!["1", "2", "4"]
+        );
+        assert_eq!(
+            get_logs_at(&state, Some("/build/MyApp/Network/Client.swift"), None).await,
+            vec!["1", "2", "4"]
+        );
+        assert_eq!(
+            get_logs_at(&state, None, Some(42)).await,
+            vec!["1", "3", "4"]
+        );
+        assert_eq!(
+            get_logs_at(&state, Some("Client.swift"), Some(7)).await,
+            vec!["2"]
+        );
+    }
+
+    async fn get_logs_at_level(state: &Arc<AppState>, level: &str) -> Response {
+        let query = LogsQuery {
+            level: Some(level.to_string()),
+            ..LogsQuery::default()
+        };
+        handle_get_all_logs(State(state.clone()), Query(query), HeaderMap::new()).await
+    }
+
+    async fn response_ids(response: Response) -> Vec<String> {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let entries: Vec<LogEntry> = serde_json::from_slice(&body).unwrap();
+        entries.into_iter().map(|e| e.id).collect()
+    }
+
+    fn leveled_state() -> (Arc<AppState>, TempDir) {
+        let (state, dir) = test_state(Config::default());
+        for (id, level) in [
+            ("1", "trace"),
+            ("2", "info"),
+            ("3", "warning"),
+            ("4", "critical"),
+        ] {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.level = level.to_string();
+            state.buffer.append(entry);
+        }
+        (state, dir)
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_level_trace_returns_everything() {
+        let (state, _dir) = leveled_state();
+        let response = get_logs_at_level(&state, "trace").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response_ids(response).await, ["1", "2", "3", "4"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_level_critical_returns_only_critical() {
+        let (state, _dir) = leveled_state();
+        let response = get_logs_at_level(&state, "CRITICAL").await;
+        assert_eq!(response_ids(response).await, ["4"]);
+
+        let response = get_logs_at_level(&state, "warning").await;
+        assert_eq!(response_ids(response).await, ["3", "4"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_unknown_level_rejected() {
+        let (state, _dir) = leveled_state();
+        let response = get_logs_at_level(&state, "loud").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    async fn get_logs_between(
+        state: &Arc<AppState>,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Response {
+        let query = LogsQuery {
+            since: since.map(str::to_string),
+            until: until.map(str::to_string),
+            ..LogsQuery::default()
+        };
+        handle_get_all_logs(State(state.clone()), Query(query), HeaderMap::new()).await
+    }
+
+    fn timed_state() -> (Arc<AppState>, TempDir) {
+        let (state, dir) = test_state(Config::default());
+        for (id, timestamp) in [
+            ("1", "2024-03-01T09:59:59Z"),
+            ("2", "2024-03-01T10:00:00Z"),
+            ("3", "2024-03-01T10:30:00Z"),
+            ("4", "2024-03-01T11:00:00Z"),
+            ("5", "2024-03-01T11:00:01Z"),
+        ] {
+            state.buffer.append(create_entry(id, timestamp));
+        }
+        (state, dir)
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_time_range_bounds_inclusive() {
+        let (state, _dir) = timed_state();
+
+        let response = get_logs_between(
+            &state,
+            Some("2024-03-01T10:00:00Z"),
+            Some("2024-03-01T11:00:00Z"),
+        )
+        .await;
+        assert_eq!(response_ids(response).await, ["2", "3", "4"]);
+
+        // Offsets are honoured: 12:00+01:00 is 11:00Z
+        let response = get_logs_between(&state, None, Some("2024-03-01T12:00:00+01:00")).await;
+        assert_eq!(response_ids(response).await, ["1", "2", "3", "4"]);
+
+        let response = get_logs_between(&state, Some("2024-03-01T11:00:00.001Z"), None).await;
+        assert_eq!(response_ids(response).await, ["5"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_time_range_composes_with_level() {
+        let (state, _dir) = timed_state();
+        let mut entry = create_entry("6", "2024-03-01T10:15:00Z");
+        entry.level = "error".to_string();
+        state.buffer.append(entry);
+
+        let query = LogsQuery {
+            since: Some("2024-03-01T10:00:00Z".to_string()),
+            until: Some("2024-03-01T10:30:00Z".to_string()),
+            level: Some("error".to_string()),
+            ..LogsQuery::default()
+        };
+        let response =
+            handle_get_all_logs(State(state.clone()), Query(query), HeaderMap::new()).await;
+        assert_eq!(response_ids(response).await, ["6"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_invalid_time_range_rejected() {
+        let (state, _dir) = timed_state();
+
+        let response = get_logs_between(&state, Some("yesterday"), None).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).starts_with("Invalid 'since' timestamp"));
+
+        let response = get_logs_between(&state, None, Some("2024-13-01T00:00:00Z")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    fn searchable_state() -> (Arc<AppState>, TempDir) {
+        let (state, dir) = test_state(Config::default());
+        for (id, message, source, region) in [
+            ("1", "Payment FAILED", "ios", "eu"),
+            ("2", "request ok", "payments-api", "us"),
+            ("3", "request ok", "ios", "payments-eu"),
+            ("4", "idle", "cli", "us"),
+        ] {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.message = message.to_string();
+            entry.source = source.to_string();
+            entry
+                .metadata
+                .insert("region".to_string(), region.to_string());
+            state.buffer.append(entry);
+        }
+        (state, dir)
+    }
+
+    async fn search_logs(state: &Arc<AppState>, q: &str, fields: Option<&str>) -> Response {
+        let query = LogsQuery {
+            q: Some(q.to_string()),
+            fields: fields.map(str::to_string),
+            ..LogsQuery::default()
+        };
+        handle_get_all_logs(State(state.clone()), Query(query), HeaderMap::new()).await
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_q_searches_message_source_and_metadata() {
+        let (state, _dir) = searchable_state();
+
+        let response = search_logs(&state, "PAYMENT", None).await;
+        assert_eq!(response_ids(response).await, ["1", "2", "3"]);
+
+        let response = search_logs(&state, "", None).await;
+        assert_eq!(response_ids(response).await, ["1", "2", "3", "4"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_q_restricted_to_fields() {
+        let (state, _dir) = searchable_state();
+
+        let response = search_logs(&state, "payment", Some("message")).await;
+        assert_eq!(response_ids(response).await, ["1"]);
+
+        let response = search_logs(&state, "payment", Some("message,source")).await;
+        assert_eq!(response_ids(response).await, ["1", "2"]);
+
+        let response = search_logs(&state, "payment", Some("metadata")).await;
+        assert_eq!(response_ids(response).await, ["3"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_unknown_search_field_rejected() {
+        let (state, _dir) = searchable_state();
+        let response = search_logs(&state, "payment", Some("message,tags")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    async fn get_logs_as(state: Arc<AppState>, accept: Option<&str>) -> (String, String) {
+        let mut headers = HeaderMap::new();
+        if let Some(accept) = accept {
+            headers.insert(header::ACCEPT, accept.parse().unwrap());
+        }
+
+        let response =
+            handle_get_all_logs(State(state), Query(LogsQuery::default()), headers).await;
+        let content_type = response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_content_negotiation() {
+        let (state, _dir) = test_state(Config::default());
+        state
+            .buffer
+            .append(create_entry("a", "2024-03-01T12:00:00Z"));
+
+        let (content_type, body) = get_logs_as(state.clone(), None).await;
+        assert_eq!(content_type, "application/json");
+        assert!(body.starts_with("[{\"id\":\"a\""));
+
+        let (content_type, body) = get_logs_as(state.clone(), Some("text/csv")).await;
+        assert_eq!(content_type, "text/csv; charset=utf-8");
+        assert!(body.starts_with("id,timestamp,level"));
+        assert!(body.contains("\na,2024-03-01T12:00:00.000Z,info,test,device-1"));
+
+        let (content_type, body) = get_logs_as(state, Some("text/plain")).await;
+        assert_eq!(content_type, "text/plain; charset=utf-8");
+        assert_eq!(body, "[2024-03-01T12:00:00.000Z] [INFO] [test] hello\n");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_pause_and_resume() {
+        let (state, _dir) = test_state(Config::default());
+        let admin = || AdminUser {
+            user_id: Uuid::new_v4(),
+        };
+        let submit = |state: Arc<AppState>, id: &str| {
+            let entry = create_entry(id, "2024-03-01T12:00:00Z");
+            handle_receive_log(State(state), None, EntryJson(entry))
+        };
+
+        let paused = IngestState { enabled: false };
+        let Json(updated) =
+            handle_set_ingest_state(State(state.clone()), admin(), Json(paused)).await;
+        assert!(!updated.enabled);
+        let Json(current) = handle_get_ingest_state(State(state.clone()), admin()).await;
+        assert!(!current.enabled);
+
+        let response = submit(state.clone(), "dropped").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let oslog = handle_receive_oslog(
+            State(state.clone()),
+            Query(OsLogQuery {
+                device_id: String::new(),
+            }),
+            EntryJson(Vec::new()),
+        )
+        .await;
+        assert_eq!(oslog.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(state.buffer.count(), 0);
+
+        let resumed = IngestState { enabled: true };
+        let Json(updated) =
+            handle_set_ingest_state(State(state.clone()), admin(), Json(resumed)).await;
+        assert!(updated.enabled);
+
+        let response = submit(state.clone(), "kept").await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(state.buffer.count(), 1);
+    }
+
+    /// Status of `method path` through a one-route router, authenticated with
+    /// a token carrying `role`
+    async fn admin_route_status(
+        state: Arc<AppState>,
+        route: axum::routing::MethodRouter<Arc<AppState>>,
+        method: &str,
+        path: &str,
+        role: Option<&str>,
+        body: &str,
+    ) -> StatusCode {
+        use crate::auth::tests::{ec_token, ec_validator};
+        use tower::ServiceExt;
+
+        let app = axum::Router::new()
+            .route(path, route)
+            .layer(Extension(ec_validator()))
+            .with_state(state);
+        let request = axum::http::Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::AUTHORIZATION, format!("Bearer {}", ec_token(role)))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_ingest_state_requires_admin() {
+        let (state, _dir) = test_state(Config::default());
+        let status = |route, method, role| {
+            admin_route_status(
+                state.clone(),
+                route,
+                method,
+                "/admin/ingest",
+                role,
+                r#"{"enabled": false}"#,
+            )
+        };
+
+        let set = || axum::routing::post(handle_set_ingest_state);
+        let get = || axum::routing::get(handle_get_ingest_state);
+        assert_eq!(status(set(), "POST", None).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(get(), "GET", None).await, StatusCode::FORBIDDEN);
+        assert!(state.ingest_enabled.load(Ordering::Relaxed));
+
+        let admin = Some(crate::auth::ADMIN_ROLE);
+        assert_eq!(status(set(), "POST", admin).await, StatusCode::OK);
+        assert!(!state.ingest_enabled.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_resize_buffer() {
+        let config = Config {
+            capacity: 3,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let user_id = Uuid::new_v4();
+        for id in ["1", "2", "3"] {
+            record_entry(&state, create_entry(id, "2024-03-01T12:00:00Z"));
+        }
+        let resize = |capacity: usize| {
+            handle_resize_buffer(
+                State(state.clone()),
+                AdminUser { user_id },
+                Json(BufferResize { capacity }),
+            )
+        };
+
+        let Json(stats) = resize(2).await.unwrap();
+        assert_eq!((stats.count, stats.capacity), (2, 2));
+        let ids: Vec<String> = state.buffer.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["2", "3"]);
+
+        let Json(limits) = handle_limits(State(state.clone())).await;
+        assert_eq!(limits.buffer_capacity, 2);
+
+        let (status, _) = resize(0).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_resize_buffer_requires_admin() {
+        let config = Config {
+            capacity: 3,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+        let status = admin_route_status(
+            state.clone(),
+            axum::routing::post(handle_resize_buffer),
+            "POST",
+            "/admin/buffer/resize",
+            None,
+            r#"{"capacity": 1}"#,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(state.buffer.stats().capacity, 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_sends_retry_hint() {
+        let config = Config {
+            sse_retry_ms: 2_500,
+            ..Config::default()
+        };
+        let (state, _dir) = test_state(config);
+
+        let response = handle_stream(State(state), Query(LogsQuery::default()))
+            .await
+            .into_response();
+        let mut body = response.into_body().into_data_stream();
+        let first = body.next().await.unwrap().unwrap();
+
+        assert_eq!(std::str::from_utf8(&first).unwrap(), "retry:2500\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_stream_filter_by_level_and_source() {
+        let filter = StreamFilter {
+            preset: None,
+            min_level: Some(LogLevel::Error),
+            source: Some("ios".to_string()),
+        };
+        let entry = |id: &str, level: &str, source: &str| {
+            let mut entry = create_entry(id, "2024-03-01T12:00:00Z");
+            entry.level = level.to_string();
+            entry.source = source.to_string();
+            BufferEvent::Log(Box::new(entry))
+        };
+        let events = futures::stream::iter([
+            entry("kept", "error", "ios"),
+            entry("too-low", "warning", "ios"),
+            entry("other-source", "critical", "android"),
+            entry("above", "critical", "ios"),
+            BufferEvent::Clear,
+        ]);
+
+        let passed: Vec<String> = events
+            .filter(|event| filter.allows(event))
+            .map(|event| match event {
+                BufferEvent::Log(entry) => entry.id,
+                _ => "clear".to_string(),
+            })
+            .collect()
+            .await;
+        assert_eq!(passed, vec!["kept", "above", "clear"]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_then_live_skips_entries_already_replayed() {
+        let log = |id: &str| BufferEvent::Log(Box::new(create_entry(id, "2024-03-01T12:00:00Z")));
+        let replayed = vec![
+            create_entry("a", "2024-03-01T12:00:00Z"),
+            create_entry("b", "2024-03-01T12:00:00Z"),
+        ];
+        // `b` was appended between subscribing and taking the snapshot
+        let live = futures::stream::iter([log("b"), log("c"), BufferEvent::Clear, log("b")]);
+
+        let sent: Vec<String> = replay_then_live(replayed, live)
+            .map(|event| match event {
+                BufferEvent::Log(entry) => entry.id,
+                _ => "clear".to_string(),
+            })
+            .collect()
+            .await;
+        assert_eq!(sent, vec!["a", "b", "c", "clear", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_replays_recent_entries() {
+        let (state, _dir) = test_state(Config::default());
+        for id in ["old", "recent"] {
+            let entry = create_entry(id, "2024-03-01T12:00:00Z");
+            state.buffer.append(entry);
+        }
+        let params = LogsQuery {
+            replay: Some(1),
+            ..LogsQuery::default()
+        };
+
+        let response = handle_stream(State(state), Query(params))
+            .await
+            .into_response();
+        let mut body = response.into_body().into_data_stream();
+        let _retry = body.next().await.unwrap().unwrap();
+        let replayed = body.next().await.unwrap().unwrap();
+
+        let replayed = std::str::from_utf8(&replayed).unwrap();
+        assert!(replayed.starts_with("event: log\n"));
+        assert!(replayed.contains("\"id\":\"recent\""));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_sends_heartbeat_when_idle() {
+        let (state, _dir) = test_state(Config {
+            sse_keepalive_secs: 1,
+            ..Config::default()
+        });
+
+        let response = handle_stream(State(state), Query(LogsQuery::default()))
+            .await
+            .into_response();
+        let mut body = response.into_body().into_data_stream();
+        let _retry = body.next().await.unwrap().unwrap();
+
+        // The `ping` comment may come first when both are due together
+        let mut heartbeat = None;
+        for _ in 0..2 {
+            let frame = body.next().await.unwrap().unwrap();
+            let frame = String::from_utf8(frame.to_vec()).unwrap();
+            if frame.starts_with("event: heartbeat\n") {
+                heartbeat = Some(frame);
+                break;
+            }
+        }
+        let heartbeat = heartbeat.expect("no heartbeat event while idle");
+        assert!(heartbeat.contains("\"timestamp\":"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_rejects_unknown_level() {
+        let (state, _dir) = test_state(Config::default());
+        let params = LogsQuery {
+            level: Some("loud".to_string()),
+            ..LogsQuery::default()
+        };
+
+        let Err((status, _)) = handle_stream(State(state), Query(params)).await else {
+            panic!("expected an unknown level to be rejected");
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_export_ndjson_lines_parse_back() {
+        let (state, _dir) = test_state(Config::default());
+        for (id, level, timestamp) in [
+            ("old-error", "error", "2024-03-01T11:00:00Z"),
+            ("debug", "debug", "2024-03-01T12:00:00Z"),
+            ("error", "error", "2024-03-01T12:00:01Z"),
+            ("critical", "critical", "2024-03-01T12:00:02Z"),
+        ] {
+            let mut entry = create_entry(id, timestamp);
+            entry.level = level.to_string();
+            state.buffer.append(entry);
+        }
+        let params = LogsQuery {
+            level: Some("error".to_string()),
+            since: Some("2024-03-01T12:00:00Z".to_string()),
+            ..LogsQuery::default()
+        };
+
+        let response = handle_export_ndjson(State(state), Query(params))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.ends_with('\n'));
+        let ids: Vec<String> = body
+            .lines()
+            .map(|line| serde_json::from_str::<LogEntry>(line).unwrap().id)
+            .collect();
+        assert_eq!(ids, vec!["error", "critical"]);
+    }
+
+    #[test]
+    fn test_parse_range_bound() {
+        assert_eq!(parse_range_bound("from", None).unwrap(), None);
+
+        let parsed = parse_range_bound("from", Some("2024-03-01T12:00:00+02:00")).unwrap();
+        assert_eq!(parsed.unwrap().to_rfc3339(), "2024-03-01T10:00:00+00:00");
+
+        let (status, message) = parse_range_bound("to", Some("yesterday")).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("'to'"));
     }
 }
@@ -0,0 +1,190 @@
+//! Composite health report: storage, auth and buffer checks rolled into one status.
+
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::buffer::BufferStats;
+use crate::AppState;
+
+/// Buffer occupancy at which the buffer check turns degraded
+const BUFFER_DEGRADED_FRACTION: f64 = 0.9;
+
+/// Probe file written and removed to test that the upload directory is writable
+const STORAGE_PROBE_FILE: &str = ".health-probe";
+
+/// Outcome of a check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+}
+
+/// Result of a single check
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub status: HealthStatus,
+
+    /// Why the check is in this state
+    pub reason: String,
+}
+
+impl Check {
+    fn ok(reason: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Ok,
+            reason: reason.into(),
+        }
+    }
+
+    fn degraded(reason: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Degraded,
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthChecks {
+    pub storage: Check,
+    pub auth: Check,
+    pub buffer: Check,
+}
+
+/// Report served by `GET /health`
+#[derive(Debug, Serialize)]
+pub struct HealthSummary {
+    /// The worst status among the checks
+    pub status: HealthStatus,
+    pub checks: HealthChecks,
+}
+
+impl HealthSummary {
+    pub fn collect(state: &AppState) -> Self {
+        let auth = match (&state.config.jwt_public_key_path, &state.jwt_validator) {
+            (None, _) => Check::ok("JWT authentication not configured"),
+            (Some(_), Some(_)) => Check::ok("JWT public key loaded"),
+            (Some(path), None) => Check::degraded(format!(
+                "JWT public key {} failed to load; protected endpoints are unavailable",
+                path
+            )),
+        };
+
+        Self::from_checks(HealthChecks {
+            storage: check_storage(&state.config.upload_dir),
+            auth,
+            buffer: check_buffer(&state.buffer.stats()),
+        })
+    }
+
+    fn from_checks(checks: HealthChecks) -> Self {
+        let status = [&checks.storage, &checks.auth, &checks.buffer]
+            .into_iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok);
+        Self { status, checks }
+    }
+}
+
+/// Liveness report served by `GET /healthz`
+#[derive(Debug, Serialize)]
+pub struct Liveness {
+    /// Always `ok`; a server that can answer is alive
+    pub status: HealthStatus,
+
+    /// Whole seconds since the server started
+    pub uptime_secs: u64,
+}
+
+impl Liveness {
+    /// Report for a server started at `started_at`
+    ///
+    /// Reads no shared state, so probes stay fast while ingestion holds the
+    /// buffer lock.
+    pub fn since(started_at: Instant) -> Self {
+        Self {
+            status: HealthStatus::Ok,
+            uptime_secs: started_at.elapsed().as_secs(),
+        }
+    }
+}
+
+/// Whether uploads can be written to `dir`
+fn check_storage(dir: &Path) -> Check {
+    let probe = dir.join(STORAGE_PROBE_FILE);
+    match std::fs::write(&probe, b"ok") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            Check::ok(format!("{} is writable", dir.display()))
+        }
+        Err(e) => Check::degraded(format!("{} is not writable: {}", dir.display(), e)),
+    }
+}
+
+/// How full the buffer is, by entries or by bytes when memory-bounded
+fn check_buffer(stats: &BufferStats) -> Check {
+    let mut occupancy = stats.count as f64 / stats.capacity.max(1) as f64;
+    if let Some(max_bytes) = stats.max_bytes {
+        occupancy = occupancy.max(stats.approx_bytes as f64 / max_bytes.max(1) as f64);
+    }
+
+    let reason = format!("{:.0}% full", occupancy * 100.0);
+    if occupancy >= BUFFER_DEGRADED_FRACTION {
+        Check::degraded(format!("{}; oldest entries are being evicted", reason))
+    } else {
+        Check::ok(reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(count: usize, capacity: usize) -> BufferStats {
+        BufferStats {
+            count,
+            capacity,
+            high_water_mark: count,
+            evictions: 0,
+            approx_bytes: 0,
+            max_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_buffer_check_degrades_when_nearly_full() {
+        assert_eq!(check_buffer(&stats(10, 100)).status, HealthStatus::Ok);
+        assert_eq!(check_buffer(&stats(95, 100)).status, HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn test_storage_check_fails_for_missing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_storage(dir.path()).status, HealthStatus::Ok);
+        assert!(!dir.path().join(STORAGE_PROBE_FILE).exists());
+
+        let missing = dir.path().join("missing");
+        assert_eq!(check_storage(&missing).status, HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn test_status_is_worst_check() {
+        let summary = HealthSummary::from_checks(HealthChecks {
+            storage: Check::degraded("read-only"),
+            auth: Check::ok("loaded"),
+            buffer: Check::ok("10% full"),
+        });
+        assert_eq!(summary.status, HealthStatus::Degraded);
+
+        let summary = HealthSummary::from_checks(HealthChecks {
+            storage: Check::ok("writable"),
+            auth: Check::ok("loaded"),
+            buffer: Check::ok("10% full"),
+        });
+        assert_eq!(summary.status, HealthStatus::Ok);
+    }
+}
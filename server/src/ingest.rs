@@ -0,0 +1,866 @@
+//! Ingestion checks for incoming log entries.
+//!
+//! Validates and normalizes entries before they reach the buffer.
+
+use axum::{
+    async_trait,
+    body::{self, Body},
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{LogEntry, LogLevel};
+
+/// JSON field names accepted on a submitted `LogEntry`
+const LOG_ENTRY_FIELDS: &[&str] = &[
+    "id",
+    "timestamp",
+    "level",
+    "message",
+    "userId",
+    "deviceId",
+    "source",
+    "metadata",
+    "tags",
+    "file",
+    "function",
+    "line",
+];
+
+/// Metadata key listing the fields the server filled in or changed
+pub const SERVER_FIELDS_KEY: &str = "_server";
+
+/// Largest accepted request body (axum's default limit, also used by the strict check)
+pub const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Appended to messages cut at MAX_MESSAGE_BYTES
+const TRUNCATION_MARKER: &str = "…";
+
+/// Run all configured ingestion checks on an entry
+pub fn prepare_entry(mut entry: LogEntry, config: &Config) -> Result<LogEntry, IngestError> {
+    validate_entry(&mut entry, config)?;
+    if entry.id.is_empty() {
+        entry.id = Uuid::new_v4().to_string();
+    }
+    check_device_id(&entry.device_id, config)?;
+    check_timestamp(&mut entry, config, Utc::now())?;
+    check_required_metadata(&entry, config)?;
+    if config.normalize_messages {
+        normalize_message(&mut entry);
+    }
+    truncate_message(&mut entry, config.max_message_bytes);
+    // Checked last, as normalizing or truncating can leave nothing behind
+    if entry.message.trim().is_empty() {
+        return Err(IngestError::EmptyMessage);
+    }
+    if config.infer_source_from_file {
+        infer_source(&mut entry);
+    }
+    if let Some(max_tags) = config.max_tags_per_entry {
+        truncate_tags(&mut entry, max_tags);
+    }
+    add_global_tags(&mut entry, &config.global_tags);
+    Ok(entry)
+}
+
+/// Record under `_server` which fields differ from what the client sent
+///
+/// Top-level fields are listed by JSON name and metadata keys as
+/// `metadata.<key>`, comma-separated. A `_server` value sent by the client is
+/// always replaced or removed, so the marker can't be forged.
+pub fn mark_server_fields(original: &LogEntry, entry: &mut LogEntry) {
+    let mut fields: Vec<String> = [
+        ("id", original.id != entry.id),
+        ("timestamp", original.timestamp != entry.timestamp),
+        ("level", original.level != entry.level),
+        ("message", original.message != entry.message),
+        ("source", original.source != entry.source),
+        ("tags", original.tags != entry.tags),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(name, _)| name.to_string())
+    .collect();
+
+    let mut metadata_keys: Vec<&String> = entry
+        .metadata
+        .iter()
+        .filter(|(key, value)| {
+            key.as_str() != SERVER_FIELDS_KEY && original.metadata.get(*key) != Some(value)
+        })
+        .map(|(key, _)| key)
+        .collect();
+    metadata_keys.sort();
+    fields.extend(
+        metadata_keys
+            .into_iter()
+            .map(|key| format!("metadata.{}", key)),
+    );
+
+    if fields.is_empty() {
+        entry.metadata.remove(SERVER_FIELDS_KEY);
+    } else {
+        entry
+            .metadata
+            .insert(SERVER_FIELDS_KEY.to_string(), fields.join(","));
+    }
+}
+
+/// Reject blank ids and canonicalize the level
+///
+/// An omitted id is generated later, but an id of only whitespace is
+/// rejected. Unknown levels become `info` unless STRICT_LEVELS is set.
+/// Empty messages are rejected by [`prepare_entry`] once the message has
+/// been normalized and truncated.
+fn validate_entry(entry: &mut LogEntry, config: &Config) -> Result<(), IngestError> {
+    if !entry.id.is_empty() && entry.id.trim().is_empty() {
+        return Err(IngestError::BlankId);
+    }
+
+    match LogLevel::parse(entry.level.trim()) {
+        Some(level) => entry.level = level.as_str().to_string(),
+        None if config.strict_levels => {
+            return Err(IngestError::UnknownLevel(entry.level.clone()));
+        }
+        None => entry.level = LogLevel::Info.as_str().to_string(),
+    }
+    Ok(())
+}
+
+/// Cut messages longer than `max_bytes` at a character boundary, ending in `…`
+///
+/// A limit too small for the marker cuts without it, so the result never
+/// exceeds `max_bytes`.
+fn truncate_message(entry: &mut LogEntry, max_bytes: usize) {
+    if entry.message.len() <= max_bytes {
+        return;
+    }
+
+    let marker = if max_bytes >= TRUNCATION_MARKER.len() {
+        TRUNCATION_MARKER
+    } else {
+        ""
+    };
+    let mut end = max_bytes - marker.len();
+    while !entry.message.is_char_boundary(end) {
+        end -= 1;
+    }
+    tracing::warn!(
+        id = %entry.id,
+        bytes = entry.message.len(),
+        max_bytes,
+        "Truncating entry message"
+    );
+    entry.message.truncate(end);
+    entry.message.push_str(marker);
+}
+
+/// Keep only the first `max_tags` client-supplied tags
+///
+/// Runs before global tags are added, so those are never the ones dropped.
+fn truncate_tags(entry: &mut LogEntry, max_tags: usize) {
+    if entry.tags.len() > max_tags {
+        tracing::warn!(
+            id = %entry.id,
+            tags = entry.tags.len(),
+            max_tags,
+            "Truncating entry tags"
+        );
+        entry.tags.truncate(max_tags);
+    }
+}
+
+/// Append server-wide tags the entry doesn't already carry
+fn add_global_tags(entry: &mut LogEntry, global_tags: &[String]) {
+    for tag in global_tags {
+        if !entry.tags.contains(tag) {
+            entry.tags.push(tag.clone());
+        }
+    }
+}
+
+/// Fill an empty source from the top-level component of the file path
+///
+/// `MyApp/Network/Client.swift` yields `MyApp`; a bare file name is left alone.
+fn infer_source(entry: &mut LogEntry) {
+    if !entry.source.is_empty() {
+        return;
+    }
+
+    let mut segments = entry.file.split(['/', '\\']).filter(|s| !s.is_empty());
+    if let (Some(first), Some(_)) = (segments.next(), segments.next()) {
+        entry.source = first.to_string();
+    }
+}
+
+/// Like [`Json`], but a body that doesn't parse gets a 400 saying why
+///
+/// Axum answers type mismatches with a plain-text 422; clients fixing their
+/// payloads need the offending field and position, so both syntax and type
+/// errors become `{"error": ..., "detail": ...}` with serde's message as the
+/// detail. Other rejections (wrong content type, unreadable body) are unchanged.
+pub struct EntryJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for EntryJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(request, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(malformed_body_response(rejection)),
+        }
+    }
+}
+
+/// 400 with a structured body for JSON that is invalid or has the wrong shape
+fn malformed_body_response(rejection: JsonRejection) -> Response {
+    let error = match rejection {
+        JsonRejection::JsonSyntaxError(_) => "Malformed JSON",
+        JsonRejection::JsonDataError(_) => "Invalid log entry",
+        rejection => return rejection.into_response(),
+    };
+
+    // The body text is "<axum's summary>: <serde's message>"
+    let body_text = rejection.body_text();
+    let detail = body_text
+        .split_once(": ")
+        .map_or(body_text.as_str(), |(_, detail)| detail);
+
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": error, "detail": detail })),
+    )
+        .into_response()
+}
+
+/// Middleware rejecting entries with fields `LogEntry` doesn't define
+///
+/// Serde silently drops unknown fields, which hides client typos such as
+/// `device_id`; in strict mode they get a 400 naming every offending field.
+/// Bodies that aren't a JSON object are left for the handler to reject.
+pub async fn reject_unknown_fields(request: Request, next: Next) -> Response {
+    let (parts, body) = request.into_parts();
+    let bytes = match body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    if let Ok(value) = serde_json::from_slice::<Value>(&bytes) {
+        let unknown = unknown_fields(&value);
+        if !unknown.is_empty() {
+            let error = IngestError::UnknownFields(unknown.join(", "));
+            return (StatusCode::BAD_REQUEST, error.to_string()).into_response();
+        }
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+/// Top-level keys of a submitted entry that `LogEntry` doesn't define
+fn unknown_fields(value: &Value) -> Vec<String> {
+    let Some(object) = value.as_object() else {
+        return Vec::new();
+    };
+
+    object
+        .keys()
+        .filter(|key| !LOG_ENTRY_FIELDS.contains(&key.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Strip control characters (other than tab) and trailing whitespace
+fn normalize_message(entry: &mut LogEntry) {
+    let mut message: String = entry
+        .message
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\t')
+        .collect();
+    message.truncate(message.trim_end().len());
+    entry.message = message;
+}
+
+/// Reject device ids that aren't UUIDs when STRICT_DEVICE_IDS is set
+pub fn check_device_id(device_id: &str, config: &Config) -> Result<(), IngestError> {
+    if config.strict_device_ids && Uuid::parse_str(device_id).is_err() {
+        return Err(IngestError::InvalidDeviceId(device_id.to_string()));
+    }
+    Ok(())
+}
+
+/// Reject or clamp timestamps that are too far ahead of `now`
+fn check_timestamp(
+    entry: &mut LogEntry,
+    config: &Config,
+    now: DateTime<Utc>,
+) -> Result<(), IngestError> {
+    if !config.reject_future_timestamps && !config.clamp_future_timestamps {
+        return Ok(());
+    }
+
+    let limit = now + Duration::seconds(config.future_timestamp_tolerance_secs);
+    if entry.timestamp <= limit {
+        return Ok(());
+    }
+
+    if config.reject_future_timestamps {
+        return Err(IngestError::FutureTimestamp(entry.timestamp.to_rfc3339()));
+    }
+
+    entry.timestamp = now;
+    Ok(())
+}
+
+/// Reject entries missing any of the configured metadata keys
+fn check_required_metadata(entry: &LogEntry, config: &Config) -> Result<(), IngestError> {
+    let missing: Vec<String> = config
+        .required_metadata_keys
+        .iter()
+        .filter(|key| !entry.metadata.contains_key(*key))
+        .cloned()
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(IngestError::MissingMetadata(missing.join(", ")))
+    }
+}
+
+/// Ingestion errors
+#[derive(Debug, thiserror::Error)]
+pub enum IngestError {
+    #[error("Timestamp {0} is too far in the future")]
+    FutureTimestamp(String),
+
+    #[error("Missing required metadata keys: {0}")]
+    MissingMetadata(String),
+
+    #[error("Unknown fields: {0}")]
+    UnknownFields(String),
+
+    #[error("Device id {0:?} is not a UUID")]
+    InvalidDeviceId(String),
+
+    #[error("Entry id must not be blank")]
+    BlankId,
+
+    #[error("Entry message must not be empty")]
+    EmptyMessage,
+
+    #[error("Unknown level {0:?}; expected trace to critical")]
+    UnknownLevel(String),
+}
+
+impl IngestError {
+    /// 422 for entries that parsed but failed validation, 400 otherwise
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::BlankId | Self::EmptyMessage | Self::UnknownLevel(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::post, Json, Router};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    fn create_entry(timestamp: DateTime<Utc>) -> LogEntry {
+        LogEntry {
+            id: "test".to_string(),
+            timestamp,
+            level: "info".to_string(),
+            message: "Test message".to_string(),
+            user_id: None,
+            device_id: "test-device".to_string(),
+            source: "test".to_string(),
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            file: String::new(),
+            function: String::new(),
+            line: 0,
+        }
+    }
+
+    fn reject_config() -> Config {
+        Config {
+            reject_future_timestamps: true,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_timestamp_within_tolerance_accepted() {
+        let now = Utc::now();
+        let mut entry = create_entry(now + Duration::seconds(59));
+
+        assert!(check_timestamp(&mut entry, &reject_config(), now).is_ok());
+        assert_eq!(entry.timestamp, now + Duration::seconds(59));
+    }
+
+    #[test]
+    fn test_far_future_timestamp_rejected() {
+        let now = Utc::now();
+        let mut entry = create_entry(now + Duration::days(365));
+
+        let result = check_timestamp(&mut entry, &reject_config(), now);
+        assert!(matches!(result, Err(IngestError::FutureTimestamp(_))));
+    }
+
+    #[test]
+    fn test_device_id_checked_only_when_strict() {
+        let uuid = Uuid::new_v4().to_string();
+        let lenient = Config::default();
+        assert!(check_device_id(&uuid, &lenient).is_ok());
+        assert!(check_device_id("my-iphone", &lenient).is_ok());
+
+        let strict = Config {
+            strict_device_ids: true,
+            ..Config::default()
+        };
+        assert!(check_device_id(&uuid, &strict).is_ok());
+        assert!(matches!(
+            check_device_id("my-iphone", &strict),
+            Err(IngestError::InvalidDeviceId(_))
+        ));
+    }
+
+    #[test]
+    fn test_far_future_timestamp_clamped() {
+        let now = Utc::now();
+        let mut entry = create_entry(now + Duration::days(365));
+        let config = Config {
+            clamp_future_timestamps: true,
+            ..Config::default()
+        };
+
+        assert!(check_timestamp(&mut entry, &config, now).is_ok());
+        assert_eq!(entry.timestamp, now);
+    }
+
+    #[test]
+    fn test_future_timestamp_allowed_by_default() {
+        let now = Utc::now();
+        let mut entry = create_entry(now + Duration::days(365));
+
+        assert!(check_timestamp(&mut entry, &Config::default(), now).is_ok());
+    }
+
+    fn required_keys_config() -> Config {
+        Config {
+            required_metadata_keys: vec!["build".to_string(), "env".to_string()],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_required_metadata_present() {
+        let mut entry = create_entry(Utc::now());
+        entry.metadata = HashMap::from([
+            ("build".to_string(), "1234".to_string()),
+            ("env".to_string(), "staging".to_string()),
+        ]);
+
+        assert!(check_required_metadata(&entry, &required_keys_config()).is_ok());
+    }
+
+    #[test]
+    fn test_required_metadata_missing() {
+        let mut entry = create_entry(Utc::now());
+        entry.metadata = HashMap::from([("env".to_string(), "staging".to_string())]);
+
+        let err = check_required_metadata(&entry, &required_keys_config()).unwrap_err();
+        assert!(matches!(&err, IngestError::MissingMetadata(keys) if keys == "build"));
+        assert_eq!(err.to_string(), "Missing required metadata keys: build");
+    }
+
+    #[test]
+    fn test_required_metadata_unconfigured() {
+        let entry = create_entry(Utc::now());
+
+        assert!(check_required_metadata(&entry, &Config::default()).is_ok());
+    }
+
+    #[test]
+    fn test_message_normalization() {
+        let mut entry = create_entry(Utc::now());
+        entry.message = "\tSaved\0 user\u{1b}[0m\tprofile \n\r\n".to_string();
+        let config = Config {
+            normalize_messages: true,
+            ..Config::default()
+        };
+
+        let entry = prepare_entry(entry, &config).unwrap();
+        assert_eq!(entry.message, "\tSaved user[0m\tprofile");
+    }
+
+    fn infer_config() -> Config {
+        Config {
+            infer_source_from_file: true,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_source_inferred_from_file() {
+        let mut entry = create_entry(Utc::now());
+        entry.source = String::new();
+        entry.file = "MyApp/Network/Client.swift".to_string();
+
+        let entry = prepare_entry(entry, &infer_config()).unwrap();
+        assert_eq!(entry.source, "MyApp");
+    }
+
+    #[test]
+    fn test_existing_source_kept() {
+        let mut entry = create_entry(Utc::now());
+        entry.file = "MyApp/Network/Client.swift".to_string();
+
+        let entry = prepare_entry(entry, &infer_config()).unwrap();
+        assert_eq!(entry.source, "test");
+    }
+
+    #[test]
+    fn test_known_fields_cover_log_entry() {
+        let mut entry = create_entry(Utc::now());
+        entry.user_id = Some("user".to_string());
+        entry.metadata.insert("k".to_string(), "v".to_string());
+
+        let value = serde_json::to_value(entry).unwrap();
+        assert!(unknown_fields(&value).is_empty());
+    }
+
+    async fn submit(strict: bool, payload: Value) -> (StatusCode, String) {
+        let mut route = post(|Json(_): Json<LogEntry>| async { StatusCode::CREATED });
+        if strict {
+            route = route.route_layer(middleware::from_fn(reject_unknown_fields));
+        }
+        let app = Router::new().route("/logs", route);
+
+        let request = Request::post("/logs")
+            .header("content-type", "application/json")
+            .body(Body::from(payload.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn payload_with_extra_fields() -> Value {
+        serde_json::json!({
+            "id": "1",
+            "timestamp": "2024-03-01T12:00:00Z",
+            "level": "info",
+            "message": "hello",
+            "deviceId": "device-1",
+            "device_id": "device-1",
+            "source": "test",
+            "severity": "high",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_strict_json_rejects_unknown_fields() {
+        let (status, body) = submit(true, payload_with_extra_fields()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.starts_with("Unknown fields: "));
+        assert!(body.contains("device_id"));
+        assert!(body.contains("severity"));
+
+        let mut valid = payload_with_extra_fields();
+        let object = valid.as_object_mut().unwrap();
+        object.remove("device_id");
+        object.remove("severity");
+        let (status, _) = submit(true, valid).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_lenient_json_ignores_unknown_fields() {
+        let (status, _) = submit(false, payload_with_extra_fields()).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    async fn extract_entry(body: &str) -> Result<LogEntry, (StatusCode, Value)> {
+        let request = Request::post("/logs")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        match EntryJson::<LogEntry>::from_request(request, &()).await {
+            Ok(EntryJson(entry)) => Ok(entry),
+            Err(response) => {
+                let status = response.status();
+                let body = body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                Err((status, serde_json::from_slice(&body).unwrap()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_entry_explained() {
+        let mut payload = payload_with_extra_fields();
+        payload["line"] = serde_json::json!("42");
+
+        let (status, body) = extract_entry(&payload.to_string()).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid log entry");
+        let detail = body["detail"].as_str().unwrap();
+        assert!(
+            detail.starts_with("line: invalid type: string \"42\""),
+            "{detail}"
+        );
+        assert!(detail.contains("column"), "{detail}");
+
+        let (status, body) = extract_entry("{\"id\": ").await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Malformed JSON");
+
+        payload["line"] = serde_json::json!(42);
+        assert_eq!(extract_entry(&payload.to_string()).await.unwrap().line, 42);
+    }
+
+    #[test]
+    fn test_global_tags_added_once() {
+        let config = Config {
+            global_tags: vec!["staging".to_string(), "eu".to_string()],
+            ..Config::default()
+        };
+        let mut entry = create_entry(Utc::now());
+        entry.tags = vec!["auth".to_string(), "staging".to_string()];
+
+        let entry = prepare_entry(entry, &config).unwrap();
+        assert_eq!(entry.tags, vec!["auth", "staging", "eu"]);
+    }
+
+    #[test]
+    fn test_message_untouched_by_default() {
+        let mut entry = create_entry(Utc::now());
+        entry.message = "line\0\n".to_string();
+
+        let entry = prepare_entry(entry, &Config::default()).unwrap();
+        assert_eq!(entry.message, "line\0\n");
+    }
+
+    #[test]
+    fn test_server_generated_id_flagged() {
+        let mut entry = create_entry(Utc::now());
+        entry.id = String::new();
+        entry.metadata = HashMap::from([("build".to_string(), "1234".to_string())]);
+        let original = entry.clone();
+        let config = Config {
+            global_tags: vec!["staging".to_string()],
+            ..Config::default()
+        };
+
+        let mut entry = prepare_entry(entry, &config).unwrap();
+        mark_server_fields(&original, &mut entry);
+
+        assert_eq!(entry.metadata[SERVER_FIELDS_KEY], "id,tags");
+        assert_eq!(entry.metadata["build"], "1234");
+    }
+
+    #[test]
+    fn test_rewritten_level_flagged() {
+        let mut entry = create_entry(Utc::now());
+        entry.level = "WARNING".to_string();
+        let original = entry.clone();
+
+        let mut entry = prepare_entry(entry, &Config::default()).unwrap();
+        mark_server_fields(&original, &mut entry);
+
+        assert_eq!(entry.level, "warning");
+        assert_eq!(entry.metadata[SERVER_FIELDS_KEY], "level");
+    }
+
+    #[test]
+    fn test_untouched_entry_not_flagged() {
+        let mut entry = create_entry(Utc::now());
+        // A client can't claim server provenance for its own fields
+        entry
+            .metadata
+            .insert(SERVER_FIELDS_KEY.to_string(), "message".to_string());
+        let original = entry.clone();
+
+        let mut entry = prepare_entry(entry, &Config::default()).unwrap();
+        mark_server_fields(&original, &mut entry);
+
+        assert!(!entry.metadata.contains_key(SERVER_FIELDS_KEY));
+    }
+
+    #[test]
+    fn test_enriched_metadata_flagged_by_key() {
+        let entry = create_entry(Utc::now());
+        let original = entry.clone();
+
+        let mut entry = entry;
+        entry
+            .metadata
+            .insert("geo_country".to_string(), "DE".to_string());
+        mark_server_fields(&original, &mut entry);
+
+        assert_eq!(entry.metadata[SERVER_FIELDS_KEY], "metadata.geo_country");
+    }
+
+    #[test]
+    fn test_blank_id_rejected() {
+        let mut entry = create_entry(Utc::now());
+        entry.id = "   ".to_string();
+
+        let err = prepare_entry(entry, &Config::default()).unwrap_err();
+        assert!(matches!(err, IngestError::BlankId));
+        assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // An omitted id is still generated
+        let mut entry = create_entry(Utc::now());
+        entry.id = String::new();
+        let entry = prepare_entry(entry, &Config::default()).unwrap();
+        assert!(!entry.id.is_empty());
+    }
+
+    #[test]
+    fn test_empty_message_rejected() {
+        for message in ["", " \n\t"] {
+            let mut entry = create_entry(Utc::now());
+            entry.message = message.to_string();
+
+            let err = prepare_entry(entry, &Config::default()).unwrap_err();
+            assert!(matches!(err, IngestError::EmptyMessage));
+            assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    #[test]
+    fn test_message_empty_after_normalization_rejected() {
+        let config = Config {
+            normalize_messages: true,
+            ..Config::default()
+        };
+
+        let mut entry = create_entry(Utc::now());
+        entry.message = "\u{0}\u{1b}\r\n".to_string();
+        let err = prepare_entry(entry, &config).unwrap_err();
+        assert!(matches!(err, IngestError::EmptyMessage));
+    }
+
+    #[test]
+    fn test_levels_canonicalized() {
+        for (level, expected) in [
+            ("ERROR", "error"),
+            (" Warning ", "warning"),
+            ("bogus", "info"),
+            ("", "info"),
+        ] {
+            let mut entry = create_entry(Utc::now());
+            entry.level = level.to_string();
+
+            let entry = prepare_entry(entry, &Config::default()).unwrap();
+            assert_eq!(entry.level, expected, "level {:?}", level);
+        }
+    }
+
+    #[test]
+    fn test_unknown_level_rejected_when_strict() {
+        let config = Config {
+            strict_levels: true,
+            ..Config::default()
+        };
+
+        let mut entry = create_entry(Utc::now());
+        entry.level = "ERROR".to_string();
+        assert_eq!(prepare_entry(entry, &config).unwrap().level, "error");
+
+        let mut entry = create_entry(Utc::now());
+        entry.level = "bogus".to_string();
+        let err = prepare_entry(entry, &config).unwrap_err();
+        assert!(matches!(err, IngestError::UnknownLevel(ref level) if level == "bogus"));
+        assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_long_message_truncated_with_ellipsis() {
+        let config = Config {
+            max_message_bytes: 10,
+            ..Config::default()
+        };
+
+        let mut entry = create_entry(Utc::now());
+        entry.message = "a".repeat(50);
+        let entry = prepare_entry(entry, &config).unwrap();
+        assert_eq!(entry.message, "aaaaaaa…");
+        assert_eq!(entry.message.len(), 10);
+
+        // Multi-byte characters are never split
+        let mut entry = create_entry(Utc::now());
+        entry.message = "ééééééé".to_string();
+        let entry = prepare_entry(entry, &config).unwrap();
+        assert_eq!(entry.message, "ééé…");
+
+        let mut entry = create_entry(Utc::now());
+        entry.message = "short".to_string();
+        assert_eq!(prepare_entry(entry, &config).unwrap().message, "short");
+    }
+
+    #[test]
+    fn test_limit_below_marker_size_truncates_without_marker() {
+        for (max_bytes, expected) in [(1, "a"), (2, "ab"), (3, "…")] {
+            let mut entry = create_entry(Utc::now());
+            entry.message = "abcdef".to_string();
+            truncate_message(&mut entry, max_bytes);
+            assert_eq!(entry.message, expected, "max_bytes {}", max_bytes);
+        }
+
+        // Nothing fits before the first character, which leaves nothing to store
+        let config = Config {
+            max_message_bytes: 1,
+            ..Config::default()
+        };
+        let mut entry = create_entry(Utc::now());
+        entry.message = "é".to_string();
+        let err = prepare_entry(entry, &config).unwrap_err();
+        assert!(matches!(err, IngestError::EmptyMessage));
+    }
+
+    #[test]
+    fn test_tags_truncated_to_limit() {
+        let mut entry = create_entry(Utc::now());
+        entry.tags = (0..300).map(|i| format!("tag-{}", i)).collect();
+        let config = Config {
+            max_tags_per_entry: Some(10),
+            global_tags: vec!["staging".to_string()],
+            ..Config::default()
+        };
+
+        let entry = prepare_entry(entry, &config).unwrap();
+
+        assert_eq!(entry.tags.len(), 11);
+        assert_eq!(entry.tags[0], "tag-0");
+        assert_eq!(entry.tags[9], "tag-9");
+        assert_eq!(entry.tags[10], "staging");
+    }
+}
@@ -0,0 +1,105 @@
+//! Output format of the server's own diagnostics.
+//!
+//! `LOG_FORMAT=json` switches them to newline-delimited JSON for log
+//! aggregators; the default stays human-readable.
+
+use tracing::Subscriber;
+use tracing_subscriber::{fmt, fmt::MakeWriter, registry::LookupSpan, Layer};
+
+/// How the server's own log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl LogFormat {
+    /// Parse a LOG_FORMAT value (`text` or `json`, case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Read LOG_FORMAT, refusing to start on unknown values
+    pub fn from_env() -> Self {
+        std::env::var("LOG_FORMAT")
+            .ok()
+            .map(|s| {
+                Self::parse(&s).unwrap_or_else(|| panic!("LOG_FORMAT: unknown format '{}'", s))
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Formatting layer for the server's own logs, written to `writer`
+pub fn layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_target(false).with_writer(writer);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::prelude::*;
+
+    /// Writer collecting everything logged into a shared buffer
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn log_line(format: LogFormat) -> String {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::registry().with(layer(format, move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || tracing::info!(port = 9006, "listening"));
+
+        let bytes = captured.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(LogFormat::parse("json"), Some(LogFormat::Json));
+        assert_eq!(LogFormat::parse(" JSON "), Some(LogFormat::Json));
+        assert_eq!(LogFormat::parse("text"), Some(LogFormat::Text));
+        assert_eq!(LogFormat::parse("xml"), None);
+    }
+
+    #[test]
+    fn test_layer_follows_format() {
+        let line = log_line(LogFormat::Json);
+        let json: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["fields"]["message"], "listening");
+        assert_eq!(json["fields"]["port"], 9006);
+
+        let line = log_line(LogFormat::Text);
+        assert!(line.contains("listening") && line.contains("9006"));
+        assert!(serde_json::from_str::<serde_json::Value>(line.trim()).is_err());
+    }
+}
@@ -3,61 +3,237 @@
 //! HTTP server for receiving, storing, and displaying log entries from Swift clients.
 
 use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::Instant;
 
 use axum::{
+    extract::{connect_info::IntoMakeServiceWithConnectInfo, DefaultBodyLimit},
+    middleware,
     routing::{delete, get, post},
     Router,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use colored::Colorize;
 use tokio::signal;
 use tower_http::services::ServeDir;
 use tracing::info;
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+use tracing_subscriber::{prelude::*, EnvFilter};
 
+mod allowlist;
+mod archive;
 mod auth;
+mod auto_save;
 mod buffer;
+mod cleanup;
 mod config;
+mod deadman;
 mod display;
+mod export;
+mod file_sink;
+mod geoip;
+mod gzip;
 mod handlers;
+mod health;
+mod ingest;
+mod logging;
+mod metrics;
 mod models;
+mod openapi;
+mod oslog;
+mod presets;
+mod rate_limit;
 mod request_manager;
+mod snapshot;
+#[cfg(feature = "sqlite")]
+mod sqlite_storage;
 mod storage;
 mod tags;
+mod upload_cache;
+mod webhook;
 
+use allowlist::IpAllowlist;
+use archive::ArchiveSink;
 use auth::JwtValidator;
 use buffer::LogBuffer;
 use config::Config;
+use deadman::DeadManSwitch;
+use file_sink::FileSink;
+use geoip::GeoIp;
+use logging::LogFormat;
+use models::LogLevel;
+use rate_limit::RateLimiter;
 use request_manager::RequestManager;
-use storage::LogStorage;
+use storage::{LogStorage, PathTemplate, UploadStore};
+use webhook::WebhookClient;
 
 /// Application state shared across handlers
 pub struct AppState {
+    /// Recent entries for the live view (`/logs`, `/stream`)
     pub buffer: LogBuffer,
+    /// Larger history fed by the same ingest, used by `/logs/search`
+    pub cold_buffer: Option<LogBuffer>,
     pub verbose: bool,
     pub request_manager: RequestManager,
-    pub storage: LogStorage,
+    /// Turns away devices polling faster than POLL_MIN_INTERVAL_MS
+    pub poll_limiter: RateLimiter,
+    /// Drops entries from sources sending faster than INGEST_RATE_PER_SEC
+    pub ingest_limiter: RateLimiter,
+    pub storage: Arc<dyn UploadStore>,
+    pub archive: Option<ArchiveSink>,
+    /// Rotating LOG_FILE copy of received entries
+    pub log_file: Option<FileSink>,
+    /// Country lookups for `geo_country` enrichment
+    pub geoip: Option<GeoIp>,
+    /// Last-seen times of the sources in DEADMAN_SOURCES
+    pub deadman: Option<Arc<DeadManSwitch>>,
+    /// Cleared while an operator has paused ingestion
+    pub ingest_enabled: AtomicBool,
     pub jwt_validator: Option<JwtValidator>,
+    pub config: Config,
+    /// When the state was built, for the uptime on `/healthz`
+    pub started_at: Instant,
+}
+
+impl AppState {
+    /// Build application state from the loaded configuration
+    pub fn new(
+        config: Config,
+        storage: Arc<dyn UploadStore>,
+        jwt_validator: Option<JwtValidator>,
+    ) -> Self {
+        let mut buffer = LogBuffer::new(config.capacity);
+        if let Some(max_bytes) = config.max_buffer_bytes {
+            buffer = buffer.with_max_bytes(max_bytes);
+        }
+        if config.dedup {
+            buffer = buffer.with_dedup();
+        }
+
+        // Pick up where the last run left off
+        if let Some(path) = snapshot::restore_path(&config, std::time::SystemTime::now()) {
+            match buffer::read_jsonl(&path) {
+                Ok(entries) => {
+                    info!("Restored {} entries from {}", entries.len(), path.display());
+                    buffer = buffer.load_from(entries);
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to restore buffer"),
+            }
+        }
+
+        let cold_buffer = config.cold_capacity.map(LogBuffer::new);
+
+        let archive = config.archive_dir.clone().map(|dir| {
+            ArchiveSink::new(dir)
+                .with_source_partitioning(config.archive_by_source)
+                .with_min_level(config.archive_min_level)
+                .with_compression(config.archive_compress)
+        });
+
+        let log_file = config.log_file.clone().and_then(|path| {
+            match FileSink::spawn(path, config.log_file_max_bytes, config.log_file_max_files) {
+                Ok(sink) => Some(sink),
+                Err(e) => {
+                    tracing::warn!(error = %e, "LOG_FILE disabled");
+                    None
+                }
+            }
+        });
+
+        // A broken database only disables enrichment
+        let geoip = config
+            .geoip_database
+            .as_deref()
+            .and_then(|path| match GeoIp::open(path) {
+                Ok(geoip) => Some(geoip),
+                Err(e) => {
+                    tracing::warn!(error = %e, "GeoIP enrichment disabled");
+                    None
+                }
+            });
+
+        let deadman = (!config.deadman_sources.is_empty()).then(|| {
+            Arc::new(DeadManSwitch::new(
+                &config.deadman_sources,
+                std::time::Duration::from_secs(config.deadman_interval_secs),
+                chrono::Utc::now(),
+            ))
+        });
+
+        let mut request_manager = RequestManager::new()
+            .with_ttl(chrono::Duration::hours(config.request_ttl_hours))
+            .with_dedup_window(chrono::Duration::seconds(config.request_dedup_window_secs))
+            .with_upload_grace(chrono::Duration::seconds(config.upload_grace_secs));
+        if let Some(path) = &config.requests_persist_path {
+            // Don't persist over a file we couldn't read
+            match request_manager.clone().with_persistence(path) {
+                Ok(persisted) => request_manager = persisted,
+                Err(e) => tracing::warn!(error = %e, "Failed to load persisted log requests"),
+            }
+        }
+
+        Self {
+            buffer,
+            cold_buffer,
+            verbose: config.verbose,
+            request_manager,
+            poll_limiter: RateLimiter::new(std::time::Duration::from_millis(
+                config.poll_min_interval_ms,
+            ))
+            .with_burst(config.poll_burst),
+            ingest_limiter: RateLimiter::per_second(config.ingest_rate_per_sec)
+                .with_burst(config.ingest_burst),
+            storage,
+            archive,
+            log_file,
+            geoip,
+            deadman,
+            ingest_enabled: AtomicBool::new(true),
+            jwt_validator,
+            config,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Buffer searched for history: the cold buffer when configured
+    pub fn search_buffer(&self) -> &LogBuffer {
+        self.cold_buffer.as_ref().unwrap_or(&self.buffer)
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging (for server's own logs)
+    // Initialize logging (for server's own logs); LOG_FORMAT is read ahead of
+    // the rest of the config so its warnings already use the chosen format
+    let log_format = LogFormat::from_env();
     tracing_subscriber::registry()
-        .with(fmt::layer().with_target(false))
+        .with(logging::layer(log_format, std::io::stdout))
         .with(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
         .init();
 
-    // Load configuration
-    let config = Config::from_env();
+    // Load configuration; environment variables override a CONFIG_FILE.
+    // Misconfiguration stops the server rather than falling back to defaults
+    let loaded = match std::env::var("CONFIG_FILE") {
+        Ok(path) => Config::from_file(Path::new(&path))
+            .map_err(|e| format!("Failed to load CONFIG_FILE {}: {}", path, e)),
+        Err(_) => Config::from_env().map_err(|e| format!("Invalid configuration: {}", e)),
+    };
+    let config = match loaded {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Initialize JWT validator if public key path is provided
     let jwt_validator = config
         .jwt_public_key_path
         .as_ref()
-        .and_then(|path| match JwtValidator::from_pem_file(path) {
+        .and_then(|path| match JwtValidator::from_pem_file(path, config.jwt_algorithm) {
             Ok(validator) => {
-                info!("JWT authentication enabled");
+                info!("JWT authentication enabled ({:?})", config.jwt_algorithm);
                 Some(validator)
             }
             Err(e) => {
@@ -67,36 +243,117 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         });
 
     // Initialize log storage
-    let storage = LogStorage::new(config.upload_dir.clone()).map_err(|e| {
-        format!("Failed to initialize log storage: {}", e)
-    })?;
+    let storage =
+        open_storage(&config).map_err(|e| format!("Failed to initialize log storage: {}", e))?;
+
+    // Networks allowed to submit logs
+    let allowlist = IpAllowlist::parse(&config.ingest_ip_allowlist, config.trust_forwarded_for)
+        .map_err(|e| format!("Invalid INGEST_IP_ALLOWLIST: {}", e))?;
 
     // Create shared state
-    let state = Arc::new(AppState {
-        buffer: LogBuffer::new(config.capacity),
-        verbose: config.verbose,
-        request_manager: RequestManager::new(),
-        storage,
-        jwt_validator,
-    });
+    let state = Arc::new(AppState::new(config.clone(), storage, jwt_validator));
+
+    // Keep saving snapshots (the last one was restored by AppState::new)
+    if let Some(interval_secs) = config.snapshot_interval_secs {
+        let period = std::time::Duration::from_secs(interval_secs);
+        tokio::spawn(snapshot::run(
+            state.clone(),
+            snapshot::snapshot_path(&config),
+            period,
+        ));
+    }
+
+    // Keep the log request file in step with the request map
+    if let Some(path) = &config.requests_persist_path {
+        tokio::spawn(state.request_manager.clone().persist(path.clone()));
+    }
+
+    // Keep entries on disk before the buffer evicts them
+    if config.auto_save {
+        let saver = auto_save::AutoSaver::new(
+            config.upload_dir.join(auto_save::AUTO_SAVE_DIR),
+            config.capacity,
+            config.auto_save_threshold,
+        );
+        tokio::spawn(auto_save::run(state.clone(), saver));
+    }
+
+    // Expire requests and delete old uploads; stopped once the server is done
+    let cleanup_task = tokio::spawn(cleanup::run(
+        state.clone(),
+        std::time::Duration::from_secs(config.cleanup_interval_secs),
+        config.retention_days,
+    ));
+
+    // Alert when watched sources go quiet
+    if let Some(deadman) = &state.deadman {
+        let webhook = config
+            .alert_webhook_url
+            .clone()
+            .map(|url| WebhookClient::new(url, config.webhook_signing_secret.clone()));
+        tokio::spawn(deadman::run(deadman.clone(), webhook));
+    }
+
+    // Dump a stats table to the console on `kill -USR1 <pid>`
+    #[cfg(unix)]
+    tokio::spawn(print_summary_on_sigusr1(
+        state.clone(),
+        config.summary_top_sources,
+    ));
 
     // Build router
     let app = Router::new()
         // Public endpoints
         .route("/", get(handlers::handle_root))
         .route("/info", get(handlers::handle_info))
-        .route("/logs", post(handlers::handle_receive_log))
+        .merge(ingest_routes(&config, allowlist))
         .route("/logs", get(handlers::handle_get_all_logs))
         .route("/logs", delete(handlers::handle_clear_logs))
+        .route("/logs/delta", post(handlers::handle_logs_delta))
+        .route("/logs/chunk", get(handlers::handle_get_log_chunk))
+        .route("/logs/search", get(handlers::handle_search_logs))
+        .route("/logs/export", get(handlers::handle_export_ndjson))
+        .route("/logs/export.logfmt", get(handlers::handle_export_logfmt))
+        .route("/logs/export.csv", get(handlers::handle_export_csv))
+        .route("/logs/stats", get(handlers::handle_log_stats))
         .route("/stream", get(handlers::handle_stream))
+        .route("/stats", get(handlers::handle_stats))
+        .route("/stats/error-rates", get(handlers::handle_error_rates))
+        .route("/limits", get(handlers::handle_limits))
+        .route("/health", get(handlers::handle_health_summary))
+        .route("/healthz", get(handlers::handle_healthz))
+        .route("/metrics", get(handlers::handle_metrics))
+        .route(
+            "/metrics/prometheus",
+            get(handlers::handle_metrics_prometheus),
+        )
+        .route("/openapi.json", get(handlers::handle_openapi))
         // Protected endpoints (require JWT)
         .route("/logs/request", post(handlers::handle_create_request))
+        .route("/logs/request", delete(handlers::handle_cancel_request))
+        .route("/logs/requests", get(handlers::handle_list_requests))
         .route("/logs/poll", get(handlers::handle_poll))
-        .route("/logs/upload", post(handlers::handle_upload))
         .route("/logs/uploads", get(handlers::handle_list_uploads))
+        .route("/logs/uploads/usage", get(handlers::handle_upload_usage))
         .route("/logs/uploads/:request_id", get(handlers::handle_get_upload))
+        .route("/logs/uploads/:request_id", delete(handlers::handle_delete_upload))
+        .route(
+            "/logs/uploads/:request_id/raw",
+            get(handlers::handle_get_upload_raw),
+        )
+        .route(
+            "/logs/uploads/device/:device_id/archive",
+            get(handlers::handle_download_device_archive),
+        )
+        .route(
+            "/admin/requests/export",
+            get(handlers::handle_export_requests),
+        )
+        .route("/admin/ingest", get(handlers::handle_get_ingest_state))
+        .route("/admin/ingest", post(handlers::handle_set_ingest_state))
+        .route("/admin/buffer/resize", post(handlers::handle_resize_buffer))
         .nest_service("/static", ServeDir::new("static"))
-        .with_state(state);
+        .with_state(state.clone());
 
     // Server address
     let addr: SocketAddr = format!("0.0.0.0:{}", config.port).parse()?;
@@ -105,9 +362,80 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!();
     println!("{}", "Log Server (Rust) starting...".green());
     println!("Listening on {}", addr.to_string().cyan());
-    println!("Buffer capacity: {} entries", config.capacity);
+    match config.max_buffer_bytes {
+        Some(max_bytes) => println!("Buffer capacity: {} bytes (memory-bounded)", max_bytes),
+        None => println!("Buffer capacity: {} entries", config.capacity),
+    }
+    if let Some(cold_capacity) = config.cold_capacity {
+        println!("Cold buffer capacity: {} entries", cold_capacity);
+    }
+    if let Some(interval_secs) = config.snapshot_interval_secs {
+        println!("Buffer snapshots: every {}s", interval_secs);
+    }
+    println!(
+        "Cleanup: every {}s, uploads kept {} days",
+        config.cleanup_interval_secs, config.retention_days
+    );
+    if config.persist_buffer {
+        println!(
+            "Buffer persistence: {}",
+            snapshot::persist_path(&config).display()
+        );
+    }
+    if config.auto_save {
+        println!(
+            "Auto-save: {}/ at {:.0}% of capacity",
+            config.upload_dir.join(auto_save::AUTO_SAVE_DIR).display(),
+            config.auto_save_threshold * 100.0
+        );
+    }
     println!("Upload directory: {}", config.upload_dir.display());
-    if config.verbose {
+    if config.storage_backend == "sqlite" {
+        println!("Upload storage: SQLite");
+    } else {
+        println!("Upload layout: {}", config.storage_path_template);
+    }
+    if let Some(archive_dir) = &config.archive_dir {
+        let layout = if config.archive_by_source {
+            "{source}/{date}.jsonl"
+        } else {
+            "{date}.jsonl"
+        };
+        println!(
+            "Archive: {}/{} ({}+)",
+            archive_dir.display(),
+            layout,
+            config.archive_min_level.as_str().to_uppercase()
+        );
+    }
+    if let Some(path) = &config.log_file {
+        println!(
+            "Log file: {} (rotated at {} bytes, {} kept)",
+            path.display(),
+            config.log_file_max_bytes,
+            config.log_file_max_files
+        );
+    }
+    if let Some(path) = &config.geoip_database {
+        println!("GeoIP database: {}", path.display());
+    }
+    if !config.ingest_ip_allowlist.is_empty() {
+        let ranges = config.ingest_ip_allowlist.join(", ");
+        println!("Ingest allowlist: {}", ranges);
+    }
+    if config.strict_json {
+        println!("Strict JSON: {}", "ON".green());
+    }
+    if config.min_ingest_level > LogLevel::Trace {
+        println!(
+            "Minimum ingest level: {}+",
+            config.min_ingest_level.as_str().to_uppercase()
+        );
+    }
+    if !config.verbose_levels.is_empty() {
+        let levels: Vec<&str> = config.verbose_levels.iter().map(|l| l.as_str()).collect();
+        println!("Verbose mode: {} only", levels.join(", ").green());
+    } else if config.verbose {
         println!("Verbose mode: {}", "ON".green());
     } else {
         println!("Verbose mode: OFF (set VERBOSE=1 for metadata)");
@@ -117,21 +445,160 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     } else {
         println!("Authentication: {} (protected endpoints disabled)", "DISABLED".yellow());
     }
+    if !config.deadman_sources.is_empty() {
+        println!(
+            "Dead-man's switch: {} (alert after {}s of silence)",
+            config.deadman_sources.join(", "),
+            config.deadman_interval_secs
+        );
+    }
+    if config.webhook_signing_secret.is_some() {
+        println!("Webhook signing: {}", "ENABLED".green());
+    }
+    if config.tls().is_some() {
+        println!("TLS: {}", "ENABLED".green());
+    }
+    #[cfg(unix)]
+    println!("Stats summary: kill -USR1 {}", std::process::id());
     println!();
 
     // Start server with graceful shutdown
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    if let Some((cert, key)) = config.tls() {
+        serve_tls(addr, service, cert, key).await?;
+    } else {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, service)
+            .with_graceful_shutdown(shutdown_signal())
+            .await?;
+    }
 
     println!();
     println!("Shutting down server...");
+    cleanup_task.abort();
+
+    if config.persist_buffer {
+        let path = snapshot::persist_path(&config);
+        match state.buffer.save_to_file(&path) {
+            Ok(count) => info!("Saved {} buffered entries to {}", count, path.display()),
+            Err(e) => tracing::warn!(error = %e, "Failed to save buffer"),
+        }
+    }
+    // Catch changes still inside the persistence debounce
+    if let Some(path) = &config.requests_persist_path {
+        if let Err(e) = state.request_manager.flush(path) {
+            tracing::warn!(error = %e, "Failed to save log requests");
+        }
+    }
     info!("Goodbye!");
 
     Ok(())
 }
 
+/// Serve HTTPS with the PEM certificate and key, until a shutdown signal
+async fn serve_tls(
+    addr: SocketAddr,
+    service: IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+    cert: &Path,
+    key: &Path,
+) -> Result<(), String> {
+    // Built without a default provider, so pick one before loading the certificate
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls = RustlsConfig::from_pem_file(cert, key)
+        .await
+        .map_err(|e| format!("Failed to load TLS certificate or key: {}", e))?;
+
+    // Stop accepting on shutdown and let in-flight requests finish
+    let handle = axum_server::Handle::new();
+    let shutdown = handle.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        shutdown.graceful_shutdown(None);
+    });
+
+    axum_server::bind_rustls(addr, tls)
+        .handle(handle)
+        .serve(service)
+        .await
+        .map_err(|e| format!("HTTPS server failed: {}", e))
+}
+
+/// Routes that accept logs, all behind the INGEST_IP_ALLOWLIST guard
+fn ingest_routes(config: &Config, allowlist: IpAllowlist) -> Router<Arc<AppState>> {
+    // Strict mode rejects entries carrying fields we don't recognize
+    let mut receive_log = post(handlers::handle_receive_log);
+    if config.strict_json {
+        receive_log = receive_log.route_layer(middleware::from_fn(ingest::reject_unknown_fields));
+    }
+
+    Router::new()
+        .route("/logs", receive_log)
+        .route("/logs/oslog", post(handlers::handle_receive_oslog))
+        // Device uploads also need a JWT
+        .route(
+            "/logs/upload",
+            post(handlers::handle_upload).layer(DefaultBodyLimit::max(config.max_upload_bytes)),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Arc::new(allowlist),
+            allowlist::enforce_ingest_allowlist,
+        ))
+}
+
+/// Open the upload storage backend selected by `STORAGE_BACKEND`
+fn open_storage(config: &Config) -> Result<Arc<dyn UploadStore>, String> {
+    match config.storage_backend.as_str() {
+        "files" => {
+            let path_template =
+                PathTemplate::parse(&config.storage_path_template).map_err(|e| e.to_string())?;
+            let mut storage = LogStorage::new(config.upload_dir.clone())
+                .map_err(|e| e.to_string())?
+                .with_path_template(path_template)
+                .with_compression(config.compress_uploads)
+                .with_split_threshold(config.upload_split_entries);
+            if config.upload_cache {
+                storage = storage.with_read_cache(config.upload_cache_max_entries);
+            }
+            if let Ok(usage) = storage.disk_usage() {
+                info!(
+                    bytes = usage.total.total_bytes,
+                    files = usage.total.file_count,
+                    users = usage.per_user.len(),
+                    "Stored uploads"
+                );
+            }
+            Ok(Arc::new(storage))
+        }
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            let path = config.upload_dir.join(sqlite_storage::DATABASE_FILE);
+            let storage = sqlite_storage::SqliteStorage::open(&path).map_err(|e| e.to_string())?;
+            Ok(Arc::new(storage))
+        }
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => Err("built without SQLite support (enable the `sqlite` feature)".to_string()),
+        other => Err(format!("unknown STORAGE_BACKEND '{}'", other)),
+    }
+}
+
+/// Print the console stats summary each time SIGUSR1 arrives
+#[cfg(unix)]
+async fn print_summary_on_sigusr1(state: Arc<AppState>, top_sources: usize) {
+    let mut signals = match signal::unix::signal(signal::unix::SignalKind::user_defined1()) {
+        Ok(signals) => signals,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to install SIGUSR1 handler");
+            return;
+        }
+    };
+
+    while signals.recv().await.is_some() {
+        let summary = metrics::Metrics::collect(&state);
+        let sources = metrics::top_sources(&state.buffer.get_all(), top_sources);
+        println!("{}", display::render_summary(&summary, &sources));
+    }
+}
+
 /// Wait for shutdown signal (Ctrl+C or SIGTERM)
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -156,3 +623,43 @@ async fn shutdown_signal() {
         _ = terminate => {},
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        extract::ConnectInfo,
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_allowlist_guards_every_ingest_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let storage = Arc::new(LogStorage::new(dir.path().to_path_buf()).unwrap());
+        let state = Arc::new(AppState::new(config.clone(), storage, None));
+        let allowlist = IpAllowlist::parse(&["10.0.0.0/8".to_string()], false).unwrap();
+        let app = ingest_routes(&config, allowlist).with_state(state);
+
+        let send = |path: &str, peer: [u8; 4]| {
+            let mut request = Request::post(path)
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap();
+            let addr = SocketAddr::from((peer, 5000));
+            request.extensions_mut().insert(ConnectInfo(addr));
+            app.clone().oneshot(request)
+        };
+
+        for path in ["/logs", "/logs/oslog", "/logs/upload"] {
+            let response = send(path, [203, 0, 113, 5]).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", path);
+        }
+
+        // Allowed addresses reach the handler, which still wants a token
+        let response = send("/logs/upload", [10, 1, 2, 3]).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
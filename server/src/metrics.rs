@@ -0,0 +1,357 @@
+//! Scrape-friendly summary of buffer contents and log request state.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::{LogEntry, LogLevel};
+use crate::request_manager::RequestStats;
+use crate::AppState;
+
+/// Snapshot served by `GET /metrics`
+#[derive(Debug, Serialize)]
+pub struct Metrics {
+    /// Entries currently buffered
+    pub count: usize,
+
+    /// Configured buffer capacity
+    pub capacity: usize,
+
+    /// Buffered entries per level; every level is present, even at zero
+    pub levels: BTreeMap<&'static str, usize>,
+
+    /// Log request counts by status
+    pub requests: RequestStats,
+}
+
+impl Metrics {
+    pub fn collect(state: &AppState) -> Self {
+        Self {
+            count: state.buffer.count(),
+            capacity: state.buffer.capacity(),
+            levels: count_levels(&state.buffer.get_all()),
+            requests: state.request_manager.stats(),
+        }
+    }
+
+    /// Render in the Prometheus text exposition format (version 0.0.4)
+    ///
+    /// Level label values come from the closed `LogLevel` set, so they never
+    /// need escaping.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        write_family(
+            &mut out,
+            "applog_entries_total",
+            "Buffered log entries by level",
+            self.levels
+                .iter()
+                .map(|(level, count)| (format!("{{level=\"{}\"}}", level), *count)),
+        );
+        write_family(
+            &mut out,
+            "applog_buffer_entries",
+            "Entries currently in the buffer",
+            [(String::new(), self.count)],
+        );
+        write_family(
+            &mut out,
+            "applog_buffer_capacity",
+            "Configured buffer capacity",
+            [(String::new(), self.capacity)],
+        );
+        for (status, count) in [
+            ("pending", self.requests.pending),
+            ("fulfilled", self.requests.fulfilled),
+            ("expired", self.requests.expired),
+            ("cancelled", self.requests.cancelled),
+        ] {
+            write_family(
+                &mut out,
+                &format!("applog_requests_{}", status),
+                &format!("Log requests currently {}", status),
+                [(String::new(), count)],
+            );
+        }
+
+        out
+    }
+}
+
+/// Aggregate counts served by `GET /logs/stats`
+#[derive(Debug, Serialize)]
+pub struct LogSummary {
+    /// Entries summarized
+    pub total: usize,
+
+    /// Entries per level; every level is present, even at zero
+    #[serde(rename = "byLevel")]
+    pub by_level: BTreeMap<&'static str, usize>,
+
+    /// Entries per source
+    #[serde(rename = "bySource")]
+    pub by_source: BTreeMap<String, usize>,
+
+    /// Earliest entry timestamp, if any entries are buffered
+    #[serde(rename = "oldestTimestamp")]
+    pub oldest_timestamp: Option<DateTime<Utc>>,
+
+    /// Latest entry timestamp, if any entries are buffered
+    #[serde(rename = "newestTimestamp")]
+    pub newest_timestamp: Option<DateTime<Utc>>,
+}
+
+/// Count entries by level and source and find the time span they cover
+///
+/// The span comes from entry timestamps, not arrival order, so clients with
+/// skewed clocks still widen it correctly.
+pub fn summarize(entries: &[LogEntry]) -> LogSummary {
+    let mut by_source: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in entries {
+        *by_source.entry(entry.source.clone()).or_default() += 1;
+    }
+
+    LogSummary {
+        total: entries.len(),
+        by_level: count_levels(entries),
+        by_source,
+        oldest_timestamp: entries.iter().map(|entry| entry.timestamp).min(),
+        newest_timestamp: entries.iter().map(|entry| entry.timestamp).max(),
+    }
+}
+
+/// Share of a source's buffered entries at `error` or above
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourceErrorRate {
+    pub source: String,
+
+    /// Entries at `error` or `critical`
+    pub errors: usize,
+
+    pub total: usize,
+
+    /// `errors / total`
+    pub rate: f64,
+}
+
+/// Per-source error rates, highest first (ties ordered by source)
+pub fn error_rates(entries: &[LogEntry]) -> Vec<SourceErrorRate> {
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for entry in entries {
+        let (errors, total) = counts.entry(entry.source.as_str()).or_default();
+        if LogLevel::from_str(&entry.level) >= LogLevel::Error {
+            *errors += 1;
+        }
+        *total += 1;
+    }
+
+    let mut rates: Vec<SourceErrorRate> = counts
+        .into_iter()
+        .map(|(source, (errors, total))| SourceErrorRate {
+            source: source.to_string(),
+            errors,
+            total,
+            rate: errors as f64 / total as f64,
+        })
+        .collect();
+    rates.sort_by(|a, b| {
+        b.rate
+            .total_cmp(&a.rate)
+            .then_with(|| a.source.cmp(&b.source))
+    });
+    rates
+}
+
+/// The `limit` sources with the most buffered entries, busiest first (ties ordered by source)
+pub fn top_sources(entries: &[LogEntry], limit: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in entries {
+        *counts.entry(entry.source.as_str()).or_default() += 1;
+    }
+
+    let mut sources: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(source, count)| (source.to_string(), count))
+        .collect();
+    sources.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sources.truncate(limit);
+    sources
+}
+
+/// Append one gauge with its HELP/TYPE header and `(labels, value)` samples
+///
+/// The buffer forgets entries as it evicts, so even the `_total` family can
+/// go down and is exposed as a gauge rather than a counter.
+fn write_family(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, usize)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+/// Tally entries by level in a single pass
+///
+/// Unrecognized level strings count as `info`, as they do everywhere else.
+pub fn count_levels(entries: &[LogEntry]) -> BTreeMap<&'static str, usize> {
+    let mut counts: BTreeMap<&'static str, usize> = LogLevel::ALL
+        .iter()
+        .map(|level| (level.as_str(), 0))
+        .collect();
+
+    for entry in entries {
+        *counts
+            .entry(LogLevel::from_str(&entry.level).as_str())
+            .or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_manager::RequestStats;
+
+    #[test]
+    fn test_prometheus_output() {
+        let metrics = Metrics {
+            count: 3,
+            capacity: 100,
+            levels: BTreeMap::from([("error", 2), ("info", 1)]),
+            requests: RequestStats {
+                total: 1,
+                pending: 1,
+                ..RequestStats::default()
+            },
+        };
+
+        let text = metrics.to_prometheus();
+
+        assert!(text.contains("# HELP applog_entries_total Buffered log entries by level\n"));
+        assert!(text.contains("# TYPE applog_entries_total gauge\n"));
+        assert!(text.contains("applog_entries_total{level=\"error\"} 2\n"));
+        assert!(text.contains("applog_entries_total{level=\"info\"} 1\n"));
+        assert!(text.contains("applog_buffer_capacity 100\n"));
+        assert!(text.contains("applog_requests_pending 1\n"));
+        assert!(text.contains("applog_requests_cancelled 0\n"));
+    }
+
+    fn create_entry(source: &str, level: &str) -> LogEntry {
+        LogEntry {
+            id: String::new(),
+            timestamp: chrono::Utc::now(),
+            level: level.to_string(),
+            message: "hello".to_string(),
+            user_id: None,
+            device_id: "device-1".to_string(),
+            source: source.to_string(),
+            metadata: std::collections::HashMap::new(),
+            tags: vec![],
+            file: String::new(),
+            function: String::new(),
+            line: 0,
+        }
+    }
+
+    #[test]
+    fn test_top_sources_busiest_first() {
+        let entries = [
+            create_entry("web", "info"),
+            create_entry("ios", "info"),
+            create_entry("ios", "error"),
+            create_entry("cli", "info"),
+            create_entry("api", "info"),
+        ];
+
+        let top = top_sources(&entries, 3);
+        assert_eq!(
+            top,
+            [
+                ("ios".to_string(), 2),
+                ("api".to_string(), 1),
+                ("cli".to_string(), 1),
+            ]
+        );
+        assert!(top_sources(&entries, 0).is_empty());
+    }
+
+    #[test]
+    fn test_summarize_counts_and_span() {
+        let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+        let mut entries = vec![
+            create_entry("ios", "error"),
+            create_entry("ios", "info"),
+            create_entry("cli", "bogus"),
+            create_entry("web", "warning"),
+        ];
+        for (entry, time) in entries.iter_mut().zip([
+            "2024-03-01T12:00:05Z",
+            "2024-03-01T12:00:00Z",
+            "2024-03-01T12:00:09Z",
+            "2024-03-01T12:00:02Z",
+        ]) {
+            entry.timestamp = at(time);
+        }
+
+        let summary = summarize(&entries);
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.by_level["info"], 2);
+        assert_eq!(summary.by_level["error"], 1);
+        assert_eq!(summary.by_level["warning"], 1);
+        assert_eq!(summary.by_level["critical"], 0);
+        assert_eq!(
+            summary.by_source,
+            BTreeMap::from([
+                ("cli".to_string(), 1),
+                ("ios".to_string(), 2),
+                ("web".to_string(), 1),
+            ])
+        );
+        assert_eq!(summary.oldest_timestamp, Some(at("2024-03-01T12:00:00Z")));
+        assert_eq!(summary.newest_timestamp, Some(at("2024-03-01T12:00:09Z")));
+
+        let empty = serde_json::to_value(summarize(&[])).unwrap();
+        assert_eq!(empty["total"], 0);
+        assert_eq!(empty["bySource"], serde_json::json!({}));
+        assert!(empty["oldestTimestamp"].is_null());
+    }
+
+    #[test]
+    fn test_error_rates_per_source() {
+        let entries = [
+            create_entry("ios", "error"),
+            create_entry("ios", "info"),
+            create_entry("ios", "critical"),
+            create_entry("ios", "warning"),
+            create_entry("cli", "info"),
+            create_entry("cli", "debug"),
+            create_entry("api", "error"),
+            create_entry("web", "critical"),
+            create_entry("web", "info"),
+        ];
+
+        let rates = error_rates(&entries);
+
+        let summary: Vec<(&str, usize, usize, f64)> = rates
+            .iter()
+            .map(|r| (r.source.as_str(), r.errors, r.total, r.rate))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("api", 1, 1, 1.0),
+                ("ios", 2, 4, 0.5),
+                ("web", 1, 2, 0.5),
+                ("cli", 0, 2, 0.0),
+            ]
+        );
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! Matches the LogEntryDTO structure from Swift's NetworkLogHandler.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -10,7 +11,8 @@ use uuid::Uuid;
 /// Log entry received from Swift clients
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
-    /// Unique identifier
+    /// Unique identifier (generated by the server if omitted)
+    #[serde(default)]
     pub id: String,
 
     /// Timestamp when the log was created
@@ -31,6 +33,7 @@ pub struct LogEntry {
     pub device_id: String,
 
     /// Source identifier (e.g., "cli", "ios", "ios-simulator", "ios-device")
+    #[serde(default)]
     pub source: String,
 
     /// Optional metadata key-value pairs
@@ -54,6 +57,32 @@ pub struct LogEntry {
     pub line: u32,
 }
 
+/// Log entry with its timestamp also rendered in a display timezone
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalizedLogEntry {
+    #[serde(flatten)]
+    pub entry: LogEntry,
+
+    /// The UTC `timestamp` converted to the display timezone
+    #[serde(rename = "localTimestamp")]
+    pub local_timestamp: String,
+}
+
+impl LocalizedLogEntry {
+    /// Attach the entry's time in `timezone`, keeping the original UTC timestamp
+    pub fn new(entry: LogEntry, timezone: Tz) -> Self {
+        let local_timestamp = entry
+            .timestamp
+            .with_timezone(&timezone)
+            .to_rfc3339_opts(SecondsFormat::Millis, false);
+
+        Self {
+            entry,
+            local_timestamp,
+        }
+    }
+}
+
 /// Log severity levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
@@ -67,6 +96,17 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
+    /// Every level, from least to most severe
+    pub const ALL: [LogLevel; 7] = [
+        Self::Trace,
+        Self::Debug,
+        Self::Info,
+        Self::Notice,
+        Self::Warning,
+        Self::Error,
+        Self::Critical,
+    ];
+
     /// Parse log level from string
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
@@ -81,6 +121,12 @@ impl LogLevel {
         }
     }
 
+    /// Parse a level name, rejecting unknown ones instead of defaulting to info
+    pub fn parse(s: &str) -> Option<Self> {
+        let level = Self::from_str(s);
+        (level.as_str() == s.to_lowercase()).then_some(level)
+    }
+
     /// Convert to string representation
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -123,6 +169,13 @@ mod tests {
         assert_eq!(LogLevel::from_str("unknown"), LogLevel::Info); // default
     }
 
+    #[test]
+    fn test_log_level_strict_parsing() {
+        assert_eq!(LogLevel::parse("Warning"), Some(LogLevel::Warning));
+        assert_eq!(LogLevel::parse("info"), Some(LogLevel::Info));
+        assert_eq!(LogLevel::parse("unknown"), None);
+    }
+
     #[test]
     fn test_log_entry_deserialization() {
         let json = r#"{
@@ -143,6 +196,26 @@ mod tests {
         assert_eq!(entry.device_id, "device-uuid-123");
         assert_eq!(entry.line, 42);
     }
+
+    #[test]
+    fn test_localized_entry_serialization() {
+        let json = r#"{
+            "id": "test-123",
+            "timestamp": "2024-01-15T10:30:00Z",
+            "level": "info",
+            "message": "Test message",
+            "deviceId": "device-uuid-123",
+            "source": "cli"
+        }"#;
+        let entry: LogEntry = serde_json::from_str(json).unwrap();
+
+        let localized = LocalizedLogEntry::new(entry, chrono_tz::Asia::Tokyo);
+        let value = serde_json::to_value(&localized).unwrap();
+
+        assert_eq!(value["timestamp"], "2024-01-15T10:30:00Z");
+        assert_eq!(value["localTimestamp"], "2024-01-15T19:30:00.000+09:00");
+        assert_eq!(value["id"], "test-123");
+    }
 }
 
 // MARK: - Log Request Models
@@ -219,8 +292,17 @@ pub struct LogUploadRequest {
     pub device_id: String,
 
     /// Array of log entries
+    #[serde(default)]
     pub logs: Vec<LogEntry>,
 
+    /// Encoding of the upload; CSV uploads carry their rows in `data`
+    #[serde(default)]
+    pub format: UploadFormat,
+
+    /// Raw upload content for non-JSON formats
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+
     /// Timestamp of earliest log in the upload
     #[serde(rename = "fromTimestamp")]
     pub from_timestamp: String,
@@ -234,6 +316,17 @@ pub struct LogUploadRequest {
     pub total_count: usize,
 }
 
+/// Encoding of an upload's log entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadFormat {
+    /// Entries in the `logs` array
+    #[default]
+    Json,
+    /// CSV rows in `data`, columns as in the CSV export
+    Csv,
+}
+
 /// Metadata about an uploaded log file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogUploadMetadata {
@@ -257,3 +350,15 @@ pub struct LogUploadMetadata {
     #[serde(rename = "fileSizeBytes")]
     pub file_size_bytes: u64,
 }
+
+/// Disk space taken up by stored uploads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UploadUsage {
+    /// Bytes on disk (compressed size for `.jsonl.gz` uploads)
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+
+    /// Files holding uploads; a split upload counts each part
+    #[serde(rename = "fileCount")]
+    pub file_count: usize,
+}
@@ -0,0 +1,886 @@
+//! OpenAPI description of the HTTP API.
+//!
+//! Hand-written OpenAPI 3 document so clients can generate bindings.
+
+use serde_json::{json, Value};
+
+/// Build the OpenAPI 3 document for the server
+pub fn document() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Log Server",
+            "description": "Development logging and diagnostics service",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths(),
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "bearerAuth": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "bearerFormat": "JWT",
+                },
+            },
+        },
+    })
+}
+
+/// Endpoint descriptions
+fn paths() -> Value {
+    let mut paths = public_paths();
+    if let (Some(paths), Value::Object(protected)) = (paths.as_object_mut(), protected_paths()) {
+        paths.extend(protected);
+    }
+    paths
+}
+
+/// Endpoints open to everyone
+fn public_paths() -> Value {
+    json!({
+        "/": {
+            "get": {
+                "summary": "Web dashboard",
+                "parameters": [query_param("theme", "`light` or `dark`; defaults to the browser's preference", false)],
+                "responses": {
+                    "200": { "description": "Dashboard HTML", "content": { "text/html": {} } },
+                    "400": { "description": "Unknown theme" },
+                },
+            },
+        },
+        "/info": {
+            "get": {
+                "summary": "Endpoint documentation, port, buffer usage and auth state in plain text",
+                "responses": {
+                    "200": { "description": "Server info", "content": { "text/plain": {} } },
+                },
+            },
+        },
+        "/logs": {
+            "get": {
+                "summary": "Retrieve all buffered logs in chronological order",
+                "parameters": [
+                    query_param("preset", "Filter preset name from FILTER_PRESETS", false),
+                    query_param("file", "Only entries from this source file (matched by basename)", false),
+                    query_param("line", "Only entries logged at this line number", false),
+                    query_param("level", "Only entries at or above this level (trace ... critical)", false),
+                    query_param("since", "Only entries timestamped at or after this RFC 3339 time", false),
+                    query_param("until", "Only entries timestamped at or before this RFC 3339 time", false),
+                    query_param("q", "Case-insensitive text to find in the searched fields", false),
+                    query_param("fields", "Comma-separated fields searched by q: message, source, metadata (default all)", false),
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Buffered log entries; format chosen by the Accept header",
+                        "content": {
+                            "application/json": { "schema": array_of("LogEntry") },
+                            "text/csv": {},
+                            "text/plain": {},
+                        },
+                    },
+                    "400": { "description": "Unknown filter preset, log level or search field, or invalid time bound" },
+                },
+            },
+            "post": {
+                "summary": "Submit a log entry",
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": schema_ref("LogEntry") } },
+                },
+                "responses": {
+                    "201": {
+                        "description": "Entry stored; the body is the entry as stored, including a generated id",
+                        "headers": {
+                            "X-Buffer-Evicting": {
+                                "description": "`true` when the buffer was full and older entries were dropped",
+                                "schema": { "type": "string" },
+                            },
+                        },
+                        "content": { "application/json": { "schema": schema_ref("LogEntry") } },
+                    },
+                    "202": { "description": "Entry below MIN_INGEST_LEVEL; accepted but not stored" },
+                    "400": { "description": "Malformed JSON or a field of the wrong type (`{error, detail}` naming the field and position), entry rejected by ingestion checks, or unknown fields under STRICT_JSON" },
+                    "403": { "description": "Source address not in INGEST_IP_ALLOWLIST" },
+                    "422": { "description": "Blank id, empty message, or unknown level under STRICT_LEVELS" },
+                    "429": { "description": "Source exceeded INGEST_RATE_PER_SEC; the entry was dropped, see Retry-After" },
+                },
+            },
+            "delete": {
+                "summary": "Clear all buffered logs, or only those before a cutoff",
+                "parameters": [
+                    query_param("before", "Only remove entries timestamped before this RFC 3339 time", false),
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Entries before the cutoff removed",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": { "removed": { "type": "integer" } },
+                                },
+                            },
+                        },
+                    },
+                    "204": { "description": "Buffer cleared" },
+                    "400": { "description": "Invalid before timestamp" },
+                },
+            },
+        },
+        "/logs/delta": {
+            "post": {
+                "summary": "Buffered entries missing from the client's known set",
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": { "type": "array", "items": { "type": "string" } } } },
+                },
+                "responses": {
+                    "200": {
+                        "description": "Entries whose ids were not posted, in chronological order",
+                        "content": { "application/json": { "schema": array_of("LogEntry") } },
+                    },
+                },
+            },
+        },
+        "/logs/chunk": {
+            "get": {
+                "summary": "Page through buffered entries with a cursor that stays stable as new entries arrive",
+                "parameters": [
+                    query_param("cursor", "`nextCursor` from the previous page; omit to start at the oldest entry", false),
+                    query_param("size", "Entries per page (default 500, max 5000)", false),
+                ],
+                "responses": {
+                    "200": {
+                        "description": "A page of entries in chronological order",
+                        "content": { "application/json": { "schema": schema_ref("LogChunk") } },
+                    },
+                    "400": { "description": "Invalid cursor" },
+                },
+            },
+        },
+        "/logs/search": {
+            "get": {
+                "summary": "Search buffered history (the cold buffer when COLD_CAPACITY is set)",
+                "parameters": [
+                    query_param("q", "Case-insensitive text to find in messages", false),
+                    query_param("highlight", "Regex the message must match; each result carries its match spans", false),
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Matching entries in chronological order",
+                        "content": { "application/json": { "schema": array_of("SearchResult") } },
+                    },
+                    "400": { "description": "Invalid highlight regex" },
+                },
+            },
+        },
+        "/logs/export": {
+            "get": {
+                "summary": "Buffered logs as newline-delimited JSON",
+                "parameters": [
+                    query_param("level", "Only entries at or above this level (trace ... critical)", false),
+                    query_param("since", "Only entries timestamped at or after this RFC 3339 time", false),
+                ],
+                "responses": {
+                    "200": { "description": "One LogEntry JSON object per line", "content": { "application/x-ndjson": {} } },
+                    "400": { "description": "Unknown level or invalid since time" },
+                },
+            },
+        },
+        "/logs/export.logfmt": {
+            "get": {
+                "summary": "Buffered logs as logfmt (`ts=... level=... source=... device=... msg=...`)",
+                "responses": {
+                    "200": { "description": "One logfmt line per entry", "content": { "text/plain": {} } },
+                },
+            },
+        },
+        "/logs/export.csv": {
+            "get": {
+                "summary": "Buffered logs as a CSV download for spreadsheets",
+                "parameters": [query_param("level", "Only entries at or above this level (trace ... critical)", false)],
+                "responses": {
+                    "200": {
+                        "description": "Header row then one row per entry: timestamp, level, source, message, file, function, line, metadata (`key=value;...`)",
+                        "content": { "text/csv": {} },
+                    },
+                    "400": { "description": "Unknown log level" },
+                },
+            },
+        },
+        "/logs/oslog": {
+            "post": {
+                "summary": "Submit entries exported with `log show --style json`",
+                "parameters": [query_param("deviceId", "Device the entries came from", false)],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": { "type": "array", "items": { "type": "object" } } } },
+                },
+                "responses": {
+                    "201": { "description": "Entries stored" },
+                    "400": { "description": "Malformed JSON (`{error, detail}`), or an entry could not be converted" },
+                    "403": { "description": "Source address not in INGEST_IP_ALLOWLIST" },
+                    "422": { "description": "An entry failed validation (empty message, unknown level under STRICT_LEVELS)" },
+                    "429": { "description": "A source exceeded INGEST_RATE_PER_SEC; the whole batch was dropped and charged nothing, see Retry-After" },
+                },
+            },
+        },
+        "/stream": {
+            "get": {
+                "summary": "Server-Sent Events stream of new log entries",
+                "parameters": [
+                    query_param("preset", "Filter preset name from FILTER_PRESETS", false),
+                    query_param("level", "Only entries at or above this level (trace ... critical)", false),
+                    query_param("source", "Only entries from exactly this source", false),
+                    query_param("replay", "Number of recent buffered entries to send first", false),
+                ],
+                "responses": {
+                    "400": { "description": "Unknown filter preset or log level" },
+                    "200": {
+                        "description": "`log` events carrying LogEntry JSON; `clear` when the buffer is cleared; `upload` with `{requestId, deviceId, count}` when a device upload is stored; `heartbeat` with `{timestamp}` every SSE_KEEPALIVE_SECS",
+                        "content": { "text/event-stream": {} },
+                    },
+                },
+            },
+        },
+        "/stats": {
+            "get": {
+                "summary": "Buffer occupancy counters",
+                "responses": {
+                    "200": {
+                        "description": "Buffer stats",
+                        "content": { "application/json": { "schema": schema_ref("BufferStats") } },
+                    },
+                },
+            },
+        },
+        "/logs/stats": {
+            "get": {
+                "summary": "Buffered entries counted by level and source, with their time span",
+                "responses": {
+                    "200": {
+                        "description": "Log summary",
+                        "content": { "application/json": { "schema": schema_ref("LogSummary") } },
+                    },
+                },
+            },
+        },
+        "/stats/error-rates": {
+            "get": {
+                "summary": "Per-source share of error and critical entries, highest rate first",
+                "responses": {
+                    "200": {
+                        "description": "Error rates",
+                        "content": { "application/json": { "schema": array_of("SourceErrorRate") } },
+                    },
+                },
+            },
+        },
+        "/limits": {
+            "get": {
+                "summary": "Configured ingest limits for client-side pre-validation",
+                "responses": {
+                    "200": {
+                        "description": "Server limits",
+                        "content": { "application/json": { "schema": schema_ref("ServerLimits") } },
+                    },
+                },
+            },
+        },
+        "/health": {
+            "get": {
+                "summary": "Storage writability, JWT key and buffer occupancy checks with an overall status",
+                "responses": {
+                    "200": {
+                        "description": "Health report; `status` is the worst check",
+                        "content": { "application/json": { "schema": schema_ref("HealthSummary") } },
+                    },
+                },
+            },
+        },
+        "/healthz": {
+            "get": {
+                "summary": "Liveness probe that answers without touching the buffer",
+                "responses": {
+                    "200": {
+                        "description": "Server is up",
+                        "content": { "application/json": { "schema": schema_ref("Liveness") } },
+                    },
+                },
+            },
+        },
+        "/metrics": {
+            "get": {
+                "summary": "Buffer occupancy, per-level entry counts and log request stats",
+                "responses": {
+                    "200": {
+                        "description": "Metrics snapshot",
+                        "content": { "application/json": { "schema": schema_ref("Metrics") } },
+                    },
+                },
+            },
+        },
+        "/metrics/prometheus": {
+            "get": {
+                "summary": "Metrics in the Prometheus text exposition format",
+                "responses": {
+                    "200": {
+                        "description": "`applog_*` gauges",
+                        "content": { "text/plain; version=0.0.4": {} },
+                    },
+                },
+            },
+        },
+        "/openapi.json": {
+            "get": {
+                "summary": "This document",
+                "responses": {
+                    "200": { "description": "OpenAPI document", "content": { "application/json": {} } },
+                },
+            },
+        },
+    })
+}
+
+/// Endpoints that require a bearer token
+fn protected_paths() -> Value {
+    json!({
+        "/logs/request": {
+            "post": {
+                "summary": "Request logs from a device",
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "type": "object",
+                                "required": ["device_id"],
+                                "properties": {
+                                    "device_id": { "type": "string" },
+                                    "expiresInHours": {
+                                        "type": "integer",
+                                        "minimum": 1,
+                                        "maximum": 168,
+                                        "description": "Lifetime instead of REQUEST_TTL_HOURS; larger values are capped",
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+                "responses": {
+                    "200": {
+                        "description": "Created request",
+                        "content": { "application/json": { "schema": schema_ref("LogRequest") } },
+                    },
+                },
+            },
+            "delete": {
+                "summary": "Cancel a device's pending log request",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [query_param("deviceId", "Device identifier", true)],
+                "responses": {
+                    "204": { "description": "Request cancelled" },
+                    "403": { "description": "The pending request belongs to a different user" },
+                    "404": { "description": "No pending request for this device" },
+                },
+            },
+        },
+        "/logs/requests": {
+            "get": {
+                "summary": "List the authenticated user's log requests across devices, whatever their status",
+                "security": [{ "bearerAuth": [] }],
+                "responses": {
+                    "200": {
+                        "description": "Requests, oldest first",
+                        "content": { "application/json": { "schema": array_of("LogRequest") } },
+                    },
+                },
+            },
+        },
+        "/logs/poll": {
+            "get": {
+                "summary": "Poll for a pending log request",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [query_param("deviceId", "Device identifier", true)],
+                "responses": {
+                    "200": {
+                        "description": "Pending request, or null",
+                        "content": { "application/json": { "schema": schema_ref("LogPollResponse") } },
+                    },
+                    "429": { "description": "Device polled sooner than POLL_MIN_INTERVAL_MS; see Retry-After" },
+                },
+            },
+        },
+        "/logs/upload": {
+            "post": {
+                "summary": "Upload logs in response to a request",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [{
+                    "name": "Content-Encoding",
+                    "in": "header",
+                    "required": false,
+                    "description": "`gzip` when the body is gzip-compressed",
+                    "schema": { "type": "string", "enum": ["gzip"] },
+                }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": schema_ref("LogUploadRequest") } },
+                },
+                "responses": {
+                    "201": { "description": "Upload stored" },
+                    "400": { "description": "Invalid request id, corrupt gzip body, totalCount not matching the entries sent, or fromTimestamp older than MAX_UPLOAD_AGE_DAYS" },
+                    "403": { "description": "Source address not in INGEST_IP_ALLOWLIST" },
+                    "413": { "description": "Body larger than MAX_UPLOAD_BYTES (64 MiB once inflated), or more entries than MAX_UPLOAD_LOGS" },
+                    "404": { "description": "No pending request for this device" },
+                    "422": { "description": "fromTimestamp after toTimestamp, or an entry outside that range" },
+                },
+            },
+        },
+        "/logs/uploads": {
+            "get": {
+                "summary": "List uploads for the authenticated user",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [
+                    query_param("from", "Only uploads at or after this RFC 3339 time", false),
+                    query_param("to", "Only uploads at or before this RFC 3339 time", false),
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Upload metadata",
+                        "content": { "application/json": { "schema": array_of("LogUploadMetadata") } },
+                    },
+                    "400": { "description": "Invalid from/to timestamp" },
+                },
+            },
+        },
+        "/logs/uploads/usage": {
+            "get": {
+                "summary": "Disk space taken up by the authenticated user's uploads",
+                "security": [{ "bearerAuth": [] }],
+                "responses": {
+                    "200": {
+                        "description": "Total bytes and number of upload files",
+                        "content": { "application/json": { "schema": schema_ref("UploadUsage") } },
+                    },
+                },
+            },
+        },
+        "/admin/requests/export": {
+            "get": {
+                "summary": "Export every log request made since startup, all users and statuses, for audits; only the latest 10,000 superseded or removed requests are kept (token needs `role: admin`)",
+                "security": [{ "bearerAuth": [] }],
+                "responses": {
+                    "200": {
+                        "description": "Requests, oldest first; fulfilled ones include `log_file_path`",
+                        "content": { "application/json": { "schema": array_of("LogRequest") } },
+                    },
+                    "403": { "description": "Token lacks the admin role" },
+                },
+            },
+        },
+        "/admin/ingest": {
+            "get": {
+                "summary": "Report whether ingestion is enabled (token needs `role: admin`)",
+                "security": [{ "bearerAuth": [] }],
+                "responses": {
+                    "200": {
+                        "description": "Current state",
+                        "content": { "application/json": { "schema": schema_ref("IngestState") } },
+                    },
+                    "403": { "description": "Token lacks the admin role" },
+                },
+            },
+            "post": {
+                "summary": "Pause or resume ingestion (submissions get 503 while paused; token needs `role: admin`)",
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": schema_ref("IngestState") } },
+                },
+                "responses": {
+                    "200": {
+                        "description": "New state",
+                        "content": { "application/json": { "schema": schema_ref("IngestState") } },
+                    },
+                    "403": { "description": "Token lacks the admin role" },
+                },
+            },
+        },
+        "/admin/buffer/resize": {
+            "post": {
+                "summary": "Change the live buffer's capacity; shrinking drops the oldest entries (token needs `role: admin`)",
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": schema_ref("BufferResize") } },
+                },
+                "responses": {
+                    "200": {
+                        "description": "Buffer occupancy after resizing",
+                        "content": { "application/json": { "schema": schema_ref("BufferStats") } },
+                    },
+                    "400": { "description": "Capacity is zero" },
+                    "403": { "description": "Token lacks the admin role" },
+                    "409": { "description": "Buffer is bounded by MAX_BUFFER_BYTES" },
+                },
+            },
+        },
+        "/logs/uploads/{request_id}": {
+            "get": {
+                "summary": "Download an uploaded log file",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [
+                    path_param("request_id", "Request identifier"),
+                    query_param("localTime", "Add `localTimestamp` in DISPLAY_TIMEZONE (true/false)", false),
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Uploaded log entries",
+                        "content": { "application/json": { "schema": array_of("LogEntry") } },
+                    },
+                    "404": { "description": "Upload not found" },
+                },
+            },
+            "delete": {
+                "summary": "Delete an uploaded log file",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [path_param("request_id", "Request identifier")],
+                "responses": {
+                    "204": { "description": "Upload deleted" },
+                    "404": { "description": "Upload not found" },
+                },
+            },
+        },
+        "/logs/uploads/{request_id}/raw": {
+            "get": {
+                "summary": "Stream an uploaded log file as stored, without parsing",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [path_param("request_id", "Request identifier")],
+                "responses": {
+                    "200": {
+                        "description": "The stored entries, one per line",
+                        "content": { "application/x-ndjson": { "schema": { "type": "string" } } },
+                    },
+                    "404": { "description": "Upload not found" },
+                },
+            },
+        },
+        "/logs/uploads/device/{device_id}/archive": {
+            "get": {
+                "summary": "Download every upload from a device as one JSONL stream",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [path_param("device_id", "Device identifier")],
+                "responses": {
+                    "200": {
+                        "description": "Entries from all of the device's uploads, one per line",
+                        "content": { "application/x-ndjson": { "schema": { "type": "string" } } },
+                    },
+                    "404": { "description": "No uploads for this device" },
+                },
+            },
+        },
+    })
+}
+
+/// Schemas for the models in `models.rs`
+fn schemas() -> Value {
+    json!({
+        "LogEntry": {
+            "type": "object",
+            "required": ["timestamp", "level", "message", "deviceId"],
+            "properties": {
+                "id": { "type": "string" },
+                "timestamp": { "type": "string", "format": "date-time" },
+                "level": {
+                    "type": "string",
+                    "enum": ["trace", "debug", "info", "notice", "warning", "error", "critical"],
+                },
+                "message": { "type": "string" },
+                "userId": { "type": "string" },
+                "deviceId": { "type": "string" },
+                "source": { "type": "string" },
+                "metadata": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Free-form key/value pairs; `ttlSeconds` expires the entry from the buffer; the server may add `geo_country`",
+                },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "file": { "type": "string" },
+                "function": { "type": "string" },
+                "line": { "type": "integer", "minimum": 0 },
+            },
+        },
+        "LogChunk": {
+            "type": "object",
+            "required": ["entries", "nextCursor", "hasMore"],
+            "properties": {
+                "entries": array_of("LogEntry"),
+                "nextCursor": { "type": "string", "description": "Opaque cursor for the following page" },
+                "hasMore": { "type": "boolean" },
+            },
+        },
+        "SearchResult": {
+            "allOf": [
+                schema_ref("LogEntry"),
+                {
+                    "type": "object",
+                    "properties": {
+                        "highlights": {
+                            "type": "array",
+                            "description": "Byte offsets of each `highlight` match in `message` (only with `?highlight=`)",
+                            "items": {
+                                "type": "object",
+                                "required": ["start", "end"],
+                                "properties": {
+                                    "start": { "type": "integer", "minimum": 0 },
+                                    "end": { "type": "integer", "minimum": 0 },
+                                },
+                            },
+                        },
+                    },
+                },
+            ],
+        },
+        "LogUploadRequest": {
+            "type": "object",
+            "required": ["requestId", "deviceId", "fromTimestamp", "toTimestamp", "totalCount"],
+            "properties": {
+                "requestId": { "type": "string", "format": "uuid" },
+                "deviceId": { "type": "string" },
+                "logs": array_of("LogEntry"),
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "csv"],
+                    "description": "`csv` uploads carry rows in `data`, in the CSV export's column order",
+                },
+                "data": { "type": "string", "description": "CSV content when `format` is `csv`" },
+                "fromTimestamp": { "type": "string", "format": "date-time" },
+                "toTimestamp": { "type": "string", "format": "date-time" },
+                "totalCount": { "type": "integer", "minimum": 0 },
+            },
+        },
+        "LogUploadMetadata": {
+            "type": "object",
+            "properties": {
+                "requestId": { "type": "string" },
+                "deviceId": { "type": "string" },
+                "uploadedAt": { "type": "string", "format": "date-time" },
+                "logCount": { "type": "integer" },
+                "fileSizeBytes": { "type": "integer" },
+            },
+        },
+        "UploadUsage": {
+            "type": "object",
+            "properties": {
+                "totalBytes": { "type": "integer" },
+                "fileCount": { "type": "integer" },
+            },
+        },
+        "LogPollResponse": {
+            "type": "object",
+            "properties": {
+                "requestId": { "type": "string" },
+                "requestedAt": { "type": "string", "format": "date-time" },
+                "expiresAt": { "type": "string", "format": "date-time" },
+            },
+        },
+        "LogRequest": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "user_id": { "type": "string", "format": "uuid" },
+                "device_id": { "type": "string" },
+                "requested_at": { "type": "string", "format": "date-time" },
+                "expires_at": { "type": "string", "format": "date-time" },
+                "status": {
+                    "type": "string",
+                    "enum": ["pending", "fulfilled", "expired", "cancelled"],
+                },
+                "fulfilled_at": { "type": "string", "format": "date-time" },
+                "log_file_path": { "type": "string" },
+            },
+        },
+        "BufferStats": {
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer" },
+                "capacity": { "type": "integer" },
+                "highWaterMark": { "type": "integer" },
+                "evictions": { "type": "integer" },
+                "approxBytes": { "type": "integer" },
+                "maxBytes": { "type": "integer" },
+            },
+        },
+        "LogSummary": {
+            "type": "object",
+            "properties": {
+                "total": { "type": "integer" },
+                "byLevel": { "type": "object", "additionalProperties": { "type": "integer" } },
+                "bySource": { "type": "object", "additionalProperties": { "type": "integer" } },
+                "oldestTimestamp": { "type": "string", "format": "date-time", "nullable": true },
+                "newestTimestamp": { "type": "string", "format": "date-time", "nullable": true },
+            },
+        },
+        "SourceErrorRate": {
+            "type": "object",
+            "properties": {
+                "source": { "type": "string" },
+                "errors": { "type": "integer" },
+                "total": { "type": "integer" },
+                "rate": { "type": "number", "description": "errors / total" },
+            },
+        },
+        "HealthCheck": {
+            "type": "object",
+            "required": ["status", "reason"],
+            "properties": {
+                "status": { "type": "string", "enum": ["ok", "degraded"] },
+                "reason": { "type": "string" },
+            },
+        },
+        "Liveness": {
+            "type": "object",
+            "required": ["status", "uptime_secs"],
+            "properties": {
+                "status": { "type": "string", "enum": ["ok"] },
+                "uptime_secs": { "type": "integer", "minimum": 0 },
+            },
+        },
+        "HealthSummary": {
+            "type": "object",
+            "required": ["status", "checks"],
+            "properties": {
+                "status": { "type": "string", "enum": ["ok", "degraded"] },
+                "checks": {
+                    "type": "object",
+                    "properties": {
+                        "storage": schema_ref("HealthCheck"),
+                        "auth": schema_ref("HealthCheck"),
+                        "buffer": schema_ref("HealthCheck"),
+                    },
+                },
+            },
+        },
+        "Metrics": {
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer" },
+                "capacity": { "type": "integer" },
+                "levels": {
+                    "type": "object",
+                    "description": "Entry count per level (trace ... critical)",
+                    "additionalProperties": { "type": "integer" },
+                },
+                "requests": {
+                    "type": "object",
+                    "properties": {
+                        "total": { "type": "integer" },
+                        "pending": { "type": "integer" },
+                        "fulfilled": { "type": "integer" },
+                        "expired": { "type": "integer" },
+                        "cancelled": { "type": "integer" },
+                    },
+                },
+            },
+        },
+        "ServerLimits": {
+            "type": "object",
+            "properties": {
+                "maxBodyBytes": { "type": "integer" },
+                "bufferCapacity": { "type": "integer" },
+                "maxBufferBytes": { "type": "integer", "nullable": true },
+                "maxMessageBytes": { "type": "integer" },
+                "minIngestLevel": { "type": "string" },
+                "requiredMetadataKeys": { "type": "array", "items": { "type": "string" } },
+                "maxTagsPerEntry": { "type": "integer", "nullable": true },
+                "maxUploadAgeDays": { "type": "integer", "nullable": true },
+                "maxUploadLogs": { "type": "integer", "nullable": true },
+                "maxUploadBytes": { "type": "integer" },
+                "futureTimestampToleranceSecs": { "type": "integer", "nullable": true },
+                "pollMinIntervalMs": { "type": "integer" },
+                "pollBurst": { "type": "integer" },
+                "ingestRatePerSec": { "type": "integer" },
+                "ingestBurst": { "type": "integer" },
+            },
+        },
+        "BufferResize": {
+            "type": "object",
+            "required": ["capacity"],
+            "properties": { "capacity": { "type": "integer", "minimum": 1 } },
+        },
+        "IngestState": {
+            "type": "object",
+            "required": ["enabled"],
+            "properties": { "enabled": { "type": "boolean" } },
+        },
+    })
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn array_of(name: &str) -> Value {
+    json!({ "type": "array", "items": schema_ref(name) })
+}
+
+fn query_param(name: &str, description: &str, required: bool) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": required,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn path_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_is_valid_json() {
+        let text = serde_json::to_string(&document()).unwrap();
+        let parsed: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed["openapi"], "3.0.3");
+    }
+
+    #[test]
+    fn test_document_lists_logs_endpoint() {
+        let doc = document();
+        assert!(doc["paths"]["/logs"]["get"].is_object());
+        assert!(doc["paths"]["/logs"]["post"].is_object());
+        assert!(doc["components"]["schemas"]["LogEntry"].is_object());
+        assert!(doc["components"]["schemas"]["LogUploadRequest"].is_object());
+    }
+
+    #[test]
+    fn test_schema_refs_resolve() {
+        let doc = document();
+        let text = doc.to_string();
+        let prefix = "#/components/schemas/";
+
+        for (idx, _) in text.match_indices(prefix) {
+            let rest = &text[idx + prefix.len()..];
+            let name = &rest[..rest.find('"').unwrap()];
+            assert!(
+                doc["components"]["schemas"][name].is_object(),
+                "unresolved ref {}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Apple OSLog JSON ingestion.
+//!
+//! Maps entries exported with `log show --style json` onto `LogEntry`.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::{LogEntry, LogLevel};
+
+/// Fallback source when an OSLog entry has no subsystem
+const DEFAULT_SOURCE: &str = "oslog";
+
+/// A single entry from Apple's OSLog JSON export
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsLogEntry {
+    /// Local timestamp, e.g. `2024-01-15 10:30:00.123456-0800`
+    pub timestamp: String,
+
+    /// Rendered log message
+    #[serde(rename = "eventMessage", default)]
+    pub event_message: String,
+
+    /// Default, Info, Debug, Error or Fault
+    #[serde(rename = "messageType", default)]
+    pub message_type: Option<String>,
+
+    /// Reverse-DNS subsystem (e.g. `com.example.app`)
+    #[serde(default)]
+    pub subsystem: String,
+
+    /// Category within the subsystem
+    #[serde(default)]
+    pub category: String,
+
+    /// Path of the process that emitted the entry
+    #[serde(rename = "processImagePath", default)]
+    pub process_image_path: String,
+
+    /// Path of the library or binary that emitted the entry
+    #[serde(rename = "senderImagePath", default)]
+    pub sender_image_path: String,
+
+    /// Emitting process ID
+    #[serde(rename = "processID", default)]
+    pub process_id: Option<u64>,
+
+    /// Emitting thread ID
+    #[serde(rename = "threadID", default)]
+    pub thread_id: Option<u64>,
+}
+
+impl OsLogEntry {
+    /// Convert into a `LogEntry` attributed to the given device
+    pub fn into_log_entry(self, device_id: &str) -> Result<LogEntry, OsLogError> {
+        let timestamp = parse_timestamp(&self.timestamp)
+            .ok_or_else(|| OsLogError::InvalidTimestamp(self.timestamp.clone()))?;
+
+        let level = map_message_type(self.message_type.as_deref());
+
+        let source = if self.subsystem.is_empty() {
+            DEFAULT_SOURCE.to_string()
+        } else {
+            self.subsystem.clone()
+        };
+
+        let mut metadata = HashMap::new();
+        if !self.subsystem.is_empty() {
+            metadata.insert("subsystem".to_string(), self.subsystem);
+        }
+        if !self.category.is_empty() {
+            metadata.insert("category".to_string(), self.category.clone());
+        }
+        if !self.process_image_path.is_empty() {
+            metadata.insert("process".to_string(), self.process_image_path);
+        }
+        if let Some(pid) = self.process_id {
+            metadata.insert("pid".to_string(), pid.to_string());
+        }
+        if let Some(tid) = self.thread_id {
+            metadata.insert("tid".to_string(), tid.to_string());
+        }
+
+        let tags = if self.category.is_empty() {
+            Vec::new()
+        } else {
+            vec![self.category]
+        };
+
+        Ok(LogEntry {
+            id: Uuid::new_v4().to_string(),
+            timestamp,
+            level: level.as_str().to_string(),
+            message: self.event_message,
+            user_id: None,
+            device_id: device_id.to_string(),
+            source,
+            metadata,
+            tags,
+            file: self.sender_image_path,
+            function: String::new(),
+            line: 0,
+        })
+    }
+}
+
+/// Map an OSLog message type to the nearest `LogLevel`
+fn map_message_type(message_type: Option<&str>) -> LogLevel {
+    match message_type.map(str::to_lowercase).as_deref() {
+        Some("debug") => LogLevel::Debug,
+        Some("info") => LogLevel::Info,
+        Some("default") => LogLevel::Notice,
+        Some("error") => LogLevel::Error,
+        Some("fault") => LogLevel::Critical,
+        _ => LogLevel::Info,
+    }
+}
+
+/// Parse OSLog's `YYYY-MM-DD HH:MM:SS.ffffff±zzzz` timestamps (or RFC 3339)
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// OSLog conversion errors
+#[derive(Debug, thiserror::Error)]
+pub enum OsLogError {
+    #[error("Invalid OSLog timestamp: {0}")]
+    InvalidTimestamp(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"[
+        {
+            "traceID": 1234,
+            "eventMessage": "Request finished",
+            "eventType": "logEvent",
+            "source": null,
+            "formatString": "Request finished",
+            "activityIdentifier": 0,
+            "subsystem": "com.example.app",
+            "category": "network",
+            "threadID": 5678,
+            "senderImageUUID": "A1B2C3",
+            "backtrace": { "frames": [] },
+            "bootUUID": "",
+            "processImagePath": "/Applications/Example.app/Example",
+            "senderImagePath": "/Applications/Example.app/Example",
+            "timestamp": "2024-01-15 10:30:00.123456-0800",
+            "machTimestamp": 99,
+            "messageType": "Default",
+            "processImageUUID": "D4E5F6",
+            "processID": 42,
+            "senderProgramCounter": 100,
+            "parentActivityIdentifier": 0,
+            "timezoneName": ""
+        },
+        {
+            "eventMessage": "Disk full",
+            "subsystem": "",
+            "category": "",
+            "timestamp": "2024-01-15 10:31:00.000000+0000",
+            "messageType": "Fault"
+        }
+    ]"#;
+
+    #[test]
+    fn test_parse_oslog_array() {
+        let entries: Vec<OsLogEntry> = serde_json::from_str(SAMPLE).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let logs: Vec<LogEntry> = entries
+            .into_iter()
+            .map(|e| e.into_log_entry("device-1").unwrap())
+            .collect();
+
+        assert_eq!(logs[0].message, "Request finished");
+        assert_eq!(logs[0].level, "notice");
+        assert_eq!(logs[0].source, "com.example.app");
+        assert_eq!(logs[0].tags, vec!["network"]);
+        assert_eq!(logs[0].metadata["pid"], "42");
+        assert_eq!(logs[0].device_id, "device-1");
+        assert_eq!(
+            logs[0].timestamp.to_rfc3339(),
+            "2024-01-15T18:30:00.123456+00:00"
+        );
+
+        assert_eq!(logs[1].level, "critical");
+        assert_eq!(logs[1].source, "oslog");
+        assert!(logs[1].tags.is_empty());
+    }
+
+    #[test]
+    fn test_message_type_mapping() {
+        assert_eq!(map_message_type(Some("Debug")), LogLevel::Debug);
+        assert_eq!(map_message_type(Some("Info")), LogLevel::Info);
+        assert_eq!(map_message_type(Some("Error")), LogLevel::Error);
+        assert_eq!(map_message_type(None), LogLevel::Info);
+    }
+
+    #[test]
+    fn test_invalid_timestamp_rejected() {
+        let entry: OsLogEntry =
+            serde_json::from_str(r#"{"timestamp": "yesterday", "eventMessage": "x"}"#).unwrap();
+        assert!(entry.into_log_entry("device-1").is_err());
+    }
+}
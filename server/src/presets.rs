@@ -0,0 +1,133 @@
+//! Named filter presets for log listings and the live stream.
+//!
+//! Presets are loaded from `FILTER_PRESETS` at startup so a long filter
+//! combination can be applied with `?preset=name`.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::models::{LogEntry, LogLevel};
+
+/// Preset as written in `FILTER_PRESETS`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PresetDefinition {
+    min_level: Option<String>,
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Level, source and tag criteria applied together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterPreset {
+    /// Only entries at or above this level
+    pub min_level: Option<LogLevel>,
+    /// Only entries from one of these sources (any source if empty)
+    pub sources: Vec<String>,
+    /// Only entries carrying at least one of these tags (any entry if empty)
+    pub tags: Vec<String>,
+}
+
+impl FilterPreset {
+    /// Whether an entry passes every criterion of the preset
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let level_ok = self
+            .min_level
+            .map_or(true, |min| LogLevel::from_str(&entry.level) >= min);
+        let source_ok = self.sources.is_empty() || self.sources.contains(&entry.source);
+        let tags_ok = self.tags.is_empty() || entry.tags.iter().any(|t| self.tags.contains(t));
+
+        level_ok && source_ok && tags_ok
+    }
+}
+
+impl TryFrom<PresetDefinition> for FilterPreset {
+    type Error = String;
+
+    fn try_from(definition: PresetDefinition) -> Result<Self, Self::Error> {
+        let min_level = definition
+            .min_level
+            .map(|name| LogLevel::parse(&name).ok_or_else(|| format!("unknown level '{}'", name)))
+            .transpose()?;
+
+        Ok(Self {
+            min_level,
+            sources: definition.sources,
+            tags: definition.tags,
+        })
+    }
+}
+
+/// Parse presets from a JSON object of name → definition
+///
+/// e.g. `{"auth-errors": {"minLevel": "warning", "sources": ["ios-device"], "tags": ["auth"]}}`
+pub fn parse_presets(json: &str) -> Result<HashMap<String, FilterPreset>, PresetError> {
+    let definitions: HashMap<String, PresetDefinition> =
+        serde_json::from_str(json).map_err(|e| PresetError::InvalidJson(e.to_string()))?;
+
+    definitions
+        .into_iter()
+        .map(|(name, definition)| {
+            FilterPreset::try_from(definition)
+                .map(|preset| (name.clone(), preset))
+                .map_err(|reason| PresetError::InvalidPreset(name, reason))
+        })
+        .collect()
+}
+
+/// Preset parsing errors
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error("Invalid filter presets JSON: {0}")]
+    InvalidJson(String),
+
+    #[error("Invalid filter preset '{0}': {1}")]
+    InvalidPreset(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_entry(level: &str, source: &str, tags: &[&str]) -> LogEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "timestamp": "2024-03-01T12:00:00Z",
+            "level": level,
+            "message": "hello",
+            "deviceId": "device-1",
+            "source": source,
+            "tags": tags,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_preset_matching() {
+        let presets = parse_presets(
+            r#"{"auth": {"minLevel": "warning", "sources": ["ios-device"], "tags": ["auth"]}}"#,
+        )
+        .unwrap();
+        let preset = &presets["auth"];
+
+        assert!(preset.matches(&create_entry("error", "ios-device", &["auth", "net"])));
+        assert!(!preset.matches(&create_entry("info", "ios-device", &["auth"])));
+        assert!(!preset.matches(&create_entry("error", "cli", &["auth"])));
+        assert!(!preset.matches(&create_entry("error", "ios-device", &["net"])));
+    }
+
+    #[test]
+    fn test_invalid_presets_rejected() {
+        assert!(matches!(
+            parse_presets(r#"{"bad": {"minLevel": "loud"}}"#),
+            Err(PresetError::InvalidPreset(name, _)) if name == "bad"
+        ));
+        assert!(matches!(
+            parse_presets(r#"{"bad": {"level": "error"}}"#),
+            Err(PresetError::InvalidJson(_))
+        ));
+    }
+}
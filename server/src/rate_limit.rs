@@ -0,0 +1,347 @@
+//! Keyed rate limiting: polls per device and ingested entries per source.
+//!
+//! Each key gets a token bucket; requests that find it empty are turned away
+//! with the exact time until the next token.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Default number of keys tracked before old entries are dropped
+pub const DEFAULT_MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Token bucket refilled one token per interval
+///
+/// Holds up to `capacity` tokens; each request takes one. Refill is tracked
+/// with whole tokens so the wait until the next token is exact.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: u32,
+    refill_interval: Duration,
+    tokens: u32,
+    /// When the partially elapsed refill interval started
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket
+    pub fn new(capacity: u32, refill_interval: Duration, now: Instant) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            refill_interval,
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    /// Take `n` tokens at once, or take none and return how long until the
+    /// missing ones are earned
+    pub fn try_take_n(&mut self, n: u32, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+
+        if self.tokens < n {
+            return Err(self.time_to_tokens(n, now));
+        }
+
+        self.tokens -= n;
+        Ok(())
+    }
+
+    /// Time until `n` tokens would have been earned (zero if they're there now)
+    pub fn time_to_tokens(&self, n: u32, now: Instant) -> Duration {
+        let missing = n.saturating_sub(self.tokens);
+        if missing == 0 {
+            return Duration::ZERO;
+        }
+        (self.last_refill + self.refill_interval * missing).saturating_duration_since(now)
+    }
+
+    /// Whether the bucket has refilled completely (and so carries no state)
+    pub fn is_full(&self, now: Instant) -> bool {
+        let mut bucket = self.clone();
+        bucket.refill(now);
+        bucket.tokens == bucket.capacity
+    }
+
+    /// Add the tokens earned since the last refill
+    fn refill(&mut self, now: Instant) {
+        if self.tokens >= self.capacity {
+            self.last_refill = now;
+            return;
+        }
+
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let intervals = elapsed.as_nanos() / self.refill_interval.as_nanos().max(1);
+        let missing = self.capacity - self.tokens;
+        let earned = intervals.min(u128::from(missing)) as u32;
+
+        self.tokens += earned;
+        self.last_refill = if self.tokens == self.capacity {
+            now
+        } else {
+            self.last_refill + self.refill_interval * earned
+        };
+    }
+}
+
+/// Token buckets keyed by device (polling) or source (ingest)
+pub struct RateLimiter {
+    refill_interval: Duration,
+    burst: u32,
+    max_keys: usize,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Allow one request per `refill_interval`; a zero interval disables limiting
+    pub fn new(refill_interval: Duration) -> Self {
+        Self {
+            refill_interval,
+            burst: 1,
+            max_keys: DEFAULT_MAX_TRACKED_KEYS,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Allow `rate` requests per second; zero disables limiting
+    pub fn per_second(rate: u32) -> Self {
+        if rate == 0 {
+            return Self::new(Duration::ZERO);
+        }
+        Self::new(Duration::from_secs(1) / rate)
+    }
+
+    /// Let a key make up to `burst` requests back to back
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Bound how many keys are remembered at once
+    pub fn with_max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = max_keys.max(1);
+        self
+    }
+
+    /// Record a request, or return how long the key must wait
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        self.check_batch_at([key], now)
+    }
+
+    /// Record a batch of requests, one per key occurrence, or return how long
+    /// until the whole batch fits
+    ///
+    /// All or nothing: a refused batch takes no tokens from any key.
+    pub fn check_batch<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> Result<(), Duration> {
+        self.check_batch_at(keys, Instant::now())
+    }
+
+    fn check_batch_at<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a str>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        if self.refill_interval.is_zero() {
+            return Ok(());
+        }
+
+        let mut costs: HashMap<&str, u32> = HashMap::new();
+        for key in keys {
+            *costs.entry(key).or_default() += 1;
+        }
+
+        let mut buckets = self.buckets.lock();
+
+        let mut wait = None;
+        for (key, cost) in &costs {
+            if let Err(key_wait) = self
+                .bucket(&mut buckets, key, now)
+                .clone()
+                .try_take_n(*cost, now)
+            {
+                wait = wait.max(Some(key_wait));
+            }
+        }
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+
+        for (key, cost) in &costs {
+            self.bucket(&mut buckets, key, now).try_take_n(*cost, now)?;
+        }
+        Ok(())
+    }
+
+    /// The bucket for `key`, making room for it if too many keys are tracked
+    fn bucket<'b>(
+        &self,
+        buckets: &'b mut HashMap<String, TokenBucket>,
+        key: &str,
+        now: Instant,
+    ) -> &'b mut TokenBucket {
+        if !buckets.contains_key(key) && buckets.len() >= self.max_keys {
+            // Full buckets are indistinguishable from new ones
+            buckets.retain(|_, bucket| !bucket.is_full(now));
+
+            if buckets.len() >= self.max_keys {
+                let oldest = buckets
+                    .iter()
+                    .min_by_key(|(_, bucket)| bucket.last_refill)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    buckets.remove(&oldest);
+                }
+            }
+        }
+
+        buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new(self.burst, self.refill_interval, now))
+    }
+
+    /// Number of keys currently tracked
+    #[cfg(test)]
+    fn tracked(&self) -> usize {
+        self.buckets.lock().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_single_device() {
+        let limiter = RateLimiter::new(Duration::from_secs(2));
+        let start = Instant::now();
+
+        assert!(limiter.check_at("device-1", start).is_ok());
+
+        let wait = limiter
+            .check_at("device-1", start + Duration::from_millis(500))
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_millis(1500));
+
+        // Rejected polls don't push the window forward
+        assert!(limiter
+            .check_at("device-1", start + Duration::from_secs(2))
+            .is_ok());
+        assert!(limiter.check_at("device-2", start).is_ok());
+    }
+
+    #[test]
+    fn test_retry_time_follows_refill_schedule() {
+        let start = Instant::now();
+        let secs = |n: u64| start + Duration::from_secs(n);
+        let mut bucket = TokenBucket::new(3, Duration::from_secs(10), start);
+
+        for _ in 0..3 {
+            assert!(bucket.try_take_n(1, start).is_ok());
+        }
+        assert_eq!(bucket.try_take_n(1, start), Err(Duration::from_secs(10)));
+        assert_eq!(bucket.try_take_n(1, secs(4)), Err(Duration::from_secs(6)));
+
+        // One token per interval, counted from when the bucket was first drawn down
+        assert!(bucket.try_take_n(1, secs(10)).is_ok());
+        assert_eq!(bucket.try_take_n(1, secs(12)), Err(Duration::from_secs(8)));
+        assert_eq!(bucket.time_to_tokens(1, secs(12)), Duration::from_secs(8));
+
+        // Two intervals later both tokens are back, but no more than capacity
+        assert!(bucket.try_take_n(1, secs(30)).is_ok());
+        assert!(bucket.try_take_n(1, secs(30)).is_ok());
+        assert_eq!(bucket.try_take_n(1, secs(30)), Err(Duration::from_secs(10)));
+        assert!(bucket.is_full(secs(60)));
+    }
+
+    #[test]
+    fn test_burst_allows_back_to_back_polls() {
+        let limiter = RateLimiter::new(Duration::from_secs(5)).with_burst(2);
+        let start = Instant::now();
+
+        assert!(limiter.check_at("device-1", start).is_ok());
+        assert!(limiter.check_at("device-1", start).is_ok());
+        assert_eq!(
+            limiter.check_at("device-1", start + Duration::from_secs(1)),
+            Err(Duration::from_secs(4))
+        );
+    }
+
+    #[test]
+    fn test_batch_takes_all_or_nothing() {
+        let limiter = RateLimiter::new(Duration::from_secs(5)).with_burst(2);
+        let start = Instant::now();
+
+        // "ios" can't cover three, so "cli" isn't charged either
+        assert_eq!(
+            limiter.check_batch_at(["cli", "ios", "ios", "ios"], start),
+            Err(Duration::from_secs(5))
+        );
+        assert!(limiter.check_batch_at(["ios", "ios", "cli"], start).is_ok());
+        assert!(limiter.check_at("cli", start).is_ok());
+        assert_eq!(
+            limiter.check_batch_at(["ios"], start + Duration::from_secs(1)),
+            Err(Duration::from_secs(4))
+        );
+    }
+
+    #[test]
+    fn test_zero_interval_disables_limit() {
+        let limiter = RateLimiter::new(Duration::ZERO);
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            assert!(limiter.check_at("device-1", now).is_ok());
+        }
+        assert_eq!(limiter.tracked(), 0);
+    }
+
+    #[test]
+    fn test_tracked_devices_bounded() {
+        let limiter = RateLimiter::new(Duration::from_secs(60)).with_max_keys(2);
+        let start = Instant::now();
+
+        for (i, device) in ["a", "b", "c"].iter().enumerate() {
+            let now = start + Duration::from_secs(i as u64);
+            assert!(limiter.check_at(device, now).is_ok());
+        }
+
+        assert_eq!(limiter.tracked(), 2);
+        // The oldest device was forgotten
+        assert!(limiter
+            .check_at("a", start + Duration::from_secs(3))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_per_second_rate_refills_over_time() {
+        let limiter = RateLimiter::per_second(4).with_burst(2);
+        let start = Instant::now();
+
+        assert!(limiter.check_at("ios", start).is_ok());
+        assert!(limiter.check_at("ios", start).is_ok());
+        assert_eq!(
+            limiter.check_at("ios", start),
+            Err(Duration::from_millis(250))
+        );
+        assert!(limiter.check_at("cli", start).is_ok());
+
+        assert!(limiter
+            .check_at("ios", start + Duration::from_millis(250))
+            .is_ok());
+        assert!(limiter
+            .check_at("ios", start + Duration::from_millis(250))
+            .is_err());
+
+        // A second later the whole burst is back
+        let later = start + Duration::from_millis(1_250);
+        assert!(limiter.check_at("ios", later).is_ok());
+        assert!(limiter.check_at("ios", later).is_ok());
+        assert!(RateLimiter::per_second(0).check_at("ios", later).is_ok());
+    }
+}
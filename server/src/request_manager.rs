@@ -1,19 +1,47 @@
 //! Log request lifecycle management.
 //!
 //! Manages pending log requests from server to clients, with automatic expiration.
+//! The request map can optionally be persisted to a JSON file so it survives restarts.
 
 use crate::models::{LogRequest, LogRequestStatus};
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::sync::Notify;
 use uuid::Uuid;
 
+/// Longest lifetime a single request may ask for
+pub const MAX_REQUEST_TTL_HOURS: i64 = 7 * 24;
+
+/// Quiet period after a change before the request map is written out
+const PERSIST_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Most retired requests kept for [`RequestManager::history`]; the oldest
+/// are forgotten first
+const MAX_RETIRED_REQUESTS: usize = 10_000;
+
 /// Manages log requests with in-memory storage
 #[derive(Clone)]
 pub struct RequestManager {
     /// Active requests keyed by device_id
     requests: Arc<RwLock<HashMap<String, LogRequest>>>,
+    /// Requests that left `requests` (superseded, removed or cleaned up), in
+    /// the order they left, so [`history`](Self::history) can still list them;
+    /// holds at most [`MAX_RETIRED_REQUESTS`]
+    retired: Arc<RwLock<VecDeque<LogRequest>>>,
+    /// How long a new request stays pending
+    ttl: Duration,
+    /// Repeat requests for a device within this window reuse the pending one
+    dedup_window: Duration,
+    /// How long after expiry an upload is still accepted
+    upload_grace: Duration,
+    /// Signalled on every change when the map is persisted
+    changed: Option<Arc<Notify>>,
 }
 
 impl RequestManager {
@@ -21,16 +49,144 @@ impl RequestManager {
     pub fn new() -> Self {
         Self {
             requests: Arc::new(RwLock::new(HashMap::new())),
+            retired: Arc::new(RwLock::new(VecDeque::new())),
+            ttl: Duration::hours(24),
+            dedup_window: Duration::zero(),
+            upload_grace: Duration::zero(),
+            changed: None,
+        }
+    }
+
+    /// Load the requests saved at `path` and track changes so
+    /// [`persist`](Self::persist) can keep the file up to date
+    ///
+    /// A missing file starts empty.
+    pub fn with_persistence(mut self, path: &Path) -> io::Result<Self> {
+        let saved: Vec<LogRequest> = match File::open(path) {
+            Ok(file) => serde_json::from_reader(io::BufReader::new(file))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        *self.requests.write() = saved
+            .into_iter()
+            .map(|req| (req.device_id.clone(), req))
+            .collect();
+        self.changed = Some(Arc::new(Notify::new()));
+        Ok(self)
+    }
+
+    /// Write the request map to `path` after each burst of changes
+    ///
+    /// Runs until the process exits; the only writer of the file, so saves
+    /// never interleave. Does nothing without
+    /// [`with_persistence`](Self::with_persistence).
+    pub async fn persist(self, path: PathBuf) {
+        let Some(changed) = self.changed.clone() else {
+            return;
+        };
+        loop {
+            changed.notified().await;
+            tokio::time::sleep(PERSIST_DEBOUNCE).await;
+            if let Err(e) = self.save_to_file(&path) {
+                tracing::warn!(error = %e, path = %path.display(), "Failed to persist log requests");
+            }
+        }
+    }
+
+    /// Save now, e.g. on shutdown before a debounced save has run
+    ///
+    /// Does nothing without [`with_persistence`](Self::with_persistence), so
+    /// a file that failed to load is never overwritten.
+    pub fn flush(&self, path: &Path) -> io::Result<()> {
+        if self.changed.is_none() {
+            return Ok(());
         }
+        self.save_to_file(path)
+    }
+
+    /// Atomically write every request to `path` as a JSON array
+    fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        let requests: Vec<LogRequest> = self.requests.read().values().cloned().collect();
+
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = Path::new(&tmp_name);
+
+        let mut writer = BufWriter::new(File::create(tmp_path)?);
+        serde_json::to_writer(&mut writer, &requests)?;
+        writer.flush()?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+
+        fs::rename(tmp_path, path)
+    }
+
+    /// Note a change for the persistence task
+    fn mark_changed(&self) {
+        if let Some(changed) = &self.changed {
+            changed.notify_one();
+        }
+    }
+
+    /// Let new requests stay pending for `ttl` instead of 24 hours
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Accept uploads for requests that expired less than `grace` ago
+    pub fn with_upload_grace(mut self, grace: Duration) -> Self {
+        self.upload_grace = grace;
+        self
+    }
+
+    /// Return the existing pending request instead of replacing it when the
+    /// same device is requested again within `window`
+    pub fn with_dedup_window(mut self, window: Duration) -> Self {
+        self.dedup_window = window;
+        self
     }
 
     /// Create a new log request for a specific device
     ///
     /// If a pending request already exists for this device, it will be cancelled
-    /// and replaced with the new request.
+    /// and replaced with the new request, unless it was created within the
+    /// dedup window, in which case it is returned as-is.
     pub fn create_request(&self, user_id: Uuid, device_id: String) -> LogRequest {
+        self.create_request_expiring(user_id, device_id, self.ttl)
+    }
+
+    /// Like [`create_request`](Self::create_request), with a lifetime other
+    /// than the configured one
+    pub fn create_request_expiring(
+        &self,
+        user_id: Uuid,
+        device_id: String,
+        ttl: Duration,
+    ) -> LogRequest {
         let now = Utc::now();
-        let expires_at = now + Duration::hours(24);
+        let expires_at = now + ttl;
+
+        let mut requests = self.requests.write();
+
+        // Treat a quick repeat (e.g. a double-submitted form) as the same request
+        if let Some(existing) = requests.get(&device_id) {
+            if existing.status == LogRequestStatus::Pending
+                && existing.user_id == user_id
+                && now < existing.expires_at
+                && now - existing.requested_at < self.dedup_window
+            {
+                tracing::info!(
+                    device_id = %device_id,
+                    request_id = %existing.id,
+                    "Reusing recent pending request"
+                );
+                return existing.clone();
+            }
+        }
 
         let request = LogRequest {
             id: Uuid::new_v4(),
@@ -43,10 +199,8 @@ impl RequestManager {
             log_file_path: None,
         };
 
-        let mut requests = self.requests.write();
-
         // Cancel any existing pending request for this device
-        if let Some(existing) = requests.get(&device_id) {
+        if let Some(mut existing) = requests.insert(device_id.clone(), request.clone()) {
             if existing.status == LogRequestStatus::Pending {
                 tracing::info!(
                     device_id = %device_id,
@@ -54,10 +208,12 @@ impl RequestManager {
                     new_request_id = %request.id,
                     "Replacing existing pending request"
                 );
+                existing.status = LogRequestStatus::Cancelled;
             }
+            Self::retire(&mut self.retired.write(), existing);
         }
 
-        requests.insert(device_id, request.clone());
+        self.mark_changed();
         request
     }
 
@@ -76,6 +232,7 @@ impl RequestManager {
             let mut expired_request = request.clone();
             expired_request.status = LogRequestStatus::Expired;
             requests.insert(device_id.to_string(), expired_request);
+            self.mark_changed();
 
             tracing::info!(
                 device_id = %device_id,
@@ -94,7 +251,31 @@ impl RequestManager {
         }
     }
 
+    /// Get the request a device may upload for
+    ///
+    /// Like [`get_pending`](Self::get_pending), but a request that expired
+    /// within the upload grace period still counts, so an upload that started
+    /// just before expiry isn't lost.
+    pub fn get_uploadable(&self, device_id: &str) -> Option<LogRequest> {
+        let requests = self.requests.read();
+        let request = requests.get(device_id)?;
+
+        self.accepts_upload(request, Utc::now())
+            .then(|| request.clone())
+    }
+
+    /// Whether an upload for `request` arriving at `now` should be taken
+    fn accepts_upload(&self, request: &LogRequest, now: DateTime<Utc>) -> bool {
+        let open = matches!(
+            request.status,
+            LogRequestStatus::Pending | LogRequestStatus::Expired
+        );
+        open && now <= request.expires_at + self.upload_grace
+    }
+
     /// Mark a request as fulfilled with the uploaded log file path
+    ///
+    /// Requests that expired within the upload grace period can still be fulfilled.
     pub fn fulfill(&self, request_id: Uuid, file_path: String) -> Result<(), RequestError> {
         let mut requests = self.requests.write();
 
@@ -109,8 +290,8 @@ impl RequestManager {
             .get_mut(&device_id)
             .ok_or(RequestError::NotFound)?;
 
-        // Verify it's still pending
-        if request.status != LogRequestStatus::Pending {
+        // Verify it's still pending (or only just expired)
+        if !self.accepts_upload(request, Utc::now()) {
             return Err(RequestError::AlreadyProcessed);
         }
 
@@ -125,6 +306,7 @@ impl RequestManager {
             file_path = %file_path,
             "Request fulfilled"
         );
+        self.mark_changed();
 
         Ok(())
     }
@@ -148,10 +330,85 @@ impl RequestManager {
             request_id = %request.id,
             "Request cancelled"
         );
+        self.mark_changed();
 
         Ok(())
     }
 
+    /// All requests owned by `user_id`, oldest first, whatever their status
+    ///
+    /// Pending requests past their expiry are reported as expired even if no
+    /// poll or cleanup has marked them yet.
+    pub fn list_for_user(&self, user_id: Uuid) -> Vec<LogRequest> {
+        self.collect(|req| req.user_id == user_id)
+    }
+
+    /// Every request made since startup, for every user, oldest first
+    ///
+    /// Includes requests superseded by a newer one for the same device (shown
+    /// as cancelled if they were still pending), removed, or dropped by
+    /// [`cleanup_expired`](Self::cleanup_expired). Only the latest
+    /// [`MAX_RETIRED_REQUESTS`] of those are kept. The history lives in memory
+    /// only: after a restart it starts from the persisted active requests.
+    pub fn history(&self) -> Vec<LogRequest> {
+        let requests = self.requests.read();
+        let retired = self.retired.read();
+        Self::sorted(retired.iter().chain(requests.values()).cloned())
+    }
+
+    /// Add `request` to the retired list, forgetting the oldest once it is full
+    fn retire(retired: &mut VecDeque<LogRequest>, request: LogRequest) {
+        if retired.len() == MAX_RETIRED_REQUESTS {
+            retired.pop_front();
+        }
+        retired.push_back(request);
+    }
+
+    /// Requests matching `filter`, oldest first, with lapsed pending ones shown as expired
+    fn collect(&self, filter: impl Fn(&LogRequest) -> bool) -> Vec<LogRequest> {
+        let requests = self.requests.read();
+        Self::sorted(requests.values().filter(|req| filter(req)).cloned())
+    }
+
+    /// `requests` oldest first, with lapsed pending ones shown as expired
+    fn sorted(requests: impl Iterator<Item = LogRequest>) -> Vec<LogRequest> {
+        let now = Utc::now();
+
+        let mut owned: Vec<LogRequest> = requests
+            .map(|mut req| {
+                if req.status == LogRequestStatus::Pending && now > req.expires_at {
+                    req.status = LogRequestStatus::Expired;
+                }
+                req
+            })
+            .collect();
+        owned.sort_by_key(|req| req.requested_at);
+        owned
+    }
+
+    /// Forget a request entirely, e.g. once its upload has been downloaded
+    ///
+    /// Only the in-memory record goes; a stored upload is left in place.
+    pub fn remove(&self, request_id: Uuid) -> Option<LogRequest> {
+        let mut requests = self.requests.write();
+
+        let device_id = requests
+            .iter()
+            .find(|(_, req)| req.id == request_id)
+            .map(|(did, _)| did.clone())?;
+
+        tracing::info!(
+            device_id = %device_id,
+            request_id = %request_id,
+            "Request removed"
+        );
+
+        self.mark_changed();
+        let removed = requests.remove(&device_id)?;
+        Self::retire(&mut self.retired.write(), removed.clone());
+        Some(removed)
+    }
+
     /// Clean up expired requests
     ///
     /// Should be called periodically (e.g., every hour) to remove old entries.
@@ -159,6 +416,7 @@ impl RequestManager {
         let mut requests = self.requests.write();
         let now = Utc::now();
         let initial_count = requests.len();
+        let mut marked = false;
 
         // Mark expired pending requests
         for (device_id, request) in requests.iter_mut() {
@@ -169,11 +427,13 @@ impl RequestManager {
                     "Marking request as expired during cleanup"
                 );
                 request.status = LogRequestStatus::Expired;
+                marked = true;
             }
         }
 
         // Remove non-pending requests older than 7 days
         let cutoff = now - Duration::days(7);
+        let mut retired = self.retired.write();
         requests.retain(|device_id, request| {
             let should_keep = request.status == LogRequestStatus::Pending
                 || request.requested_at > cutoff;
@@ -185,6 +445,7 @@ impl RequestManager {
                     status = ?request.status,
                     "Removing old request"
                 );
+                Self::retire(&mut retired, request.clone());
             }
 
             should_keep
@@ -194,6 +455,9 @@ impl RequestManager {
         if removed > 0 {
             tracing::info!(removed = removed, "Cleaned up old requests");
         }
+        if marked || removed > 0 {
+            self.mark_changed();
+        }
 
         removed
     }
@@ -243,7 +507,7 @@ pub enum RequestError {
 }
 
 /// Statistics about active requests
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct RequestStats {
     pub total: usize,
     pub pending: usize,
@@ -270,6 +534,22 @@ mod tests {
         assert_eq!(retrieved.unwrap().id, request.id);
     }
 
+    #[test]
+    fn test_custom_ttl() {
+        let manager = RequestManager::new().with_ttl(Duration::hours(2));
+        let request = manager.create_request(Uuid::new_v4(), "test-device".to_string());
+        let lifetime = request.expires_at - request.requested_at;
+        assert_eq!(lifetime, Duration::hours(2));
+
+        let request = manager.create_request_expiring(
+            Uuid::new_v4(),
+            "other-device".to_string(),
+            Duration::hours(1),
+        );
+        let lifetime = request.expires_at - request.requested_at;
+        assert_eq!(lifetime, Duration::hours(1));
+    }
+
     #[test]
     fn test_fulfill_request() {
         let manager = RequestManager::new();
@@ -298,4 +578,199 @@ mod tests {
         assert!(retrieved.is_some());
         assert_eq!(retrieved.unwrap().id, request2.id);
     }
+
+    #[test]
+    fn test_dedup_within_window() {
+        let manager = RequestManager::new().with_dedup_window(Duration::seconds(30));
+        let user_id = Uuid::new_v4();
+        let device_id = "test-device".to_string();
+
+        let request1 = manager.create_request(user_id, device_id.clone());
+        let request2 = manager.create_request(user_id, device_id.clone());
+
+        assert_eq!(request1.id, request2.id);
+        assert_eq!(manager.get_pending(&device_id).unwrap().id, request1.id);
+    }
+
+    #[test]
+    fn test_dedup_outside_window() {
+        let manager = RequestManager::new().with_dedup_window(Duration::seconds(30));
+        let user_id = Uuid::new_v4();
+        let device_id = "test-device".to_string();
+
+        let request1 = manager.create_request(user_id, device_id.clone());
+
+        // Age the pending request past the window
+        manager
+            .requests
+            .write()
+            .get_mut(&device_id)
+            .unwrap()
+            .requested_at -= Duration::seconds(31);
+
+        let request2 = manager.create_request(user_id, device_id.clone());
+        assert_ne!(request1.id, request2.id);
+        assert_eq!(manager.get_pending(&device_id).unwrap().id, request2.id);
+    }
+
+    #[test]
+    fn test_list_for_user() {
+        let manager = RequestManager::new();
+        let user_id = Uuid::new_v4();
+        let other_user = Uuid::new_v4();
+
+        let fulfilled = manager.create_request(user_id, "device-1".to_string());
+        manager
+            .fulfill(fulfilled.id, "path/to/logs.jsonl".to_string())
+            .unwrap();
+        let stale = manager.create_request(user_id, "device-2".to_string());
+        manager
+            .requests
+            .write()
+            .get_mut("device-2")
+            .unwrap()
+            .expires_at = Utc::now() - Duration::minutes(1);
+        let pending = manager.create_request(user_id, "device-3".to_string());
+        manager.create_request(other_user, "device-4".to_string());
+
+        let listed = manager.list_for_user(user_id);
+        let summary: Vec<(Uuid, LogRequestStatus)> =
+            listed.iter().map(|req| (req.id, req.status)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (fulfilled.id, LogRequestStatus::Fulfilled),
+                (stale.id, LogRequestStatus::Expired),
+                (pending.id, LogRequestStatus::Pending),
+            ]
+        );
+        assert_eq!(manager.list_for_user(other_user).len(), 1);
+    }
+
+    #[test]
+    fn test_history_keeps_superseded_and_removed_requests() {
+        let manager = RequestManager::new();
+        let user_id = Uuid::new_v4();
+
+        let superseded = manager.create_request(user_id, "device-1".to_string());
+        let current = manager.create_request(user_id, "device-1".to_string());
+        let removed = manager.create_request(user_id, "device-2".to_string());
+        manager.remove(removed.id).unwrap();
+        let old = manager.create_request(user_id, "device-3".to_string());
+        manager.cancel("device-3").unwrap();
+        manager
+            .requests
+            .write()
+            .get_mut("device-3")
+            .unwrap()
+            .requested_at -= Duration::days(8);
+        assert_eq!(manager.cleanup_expired(), 1);
+
+        let history: Vec<(Uuid, LogRequestStatus)> = manager
+            .history()
+            .iter()
+            .map(|req| (req.id, req.status))
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                (old.id, LogRequestStatus::Cancelled),
+                (superseded.id, LogRequestStatus::Cancelled),
+                (current.id, LogRequestStatus::Pending),
+                (removed.id, LogRequestStatus::Pending),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_history_forgets_oldest_retired_requests() {
+        let manager = RequestManager::new();
+        let user_id = Uuid::new_v4();
+
+        let first = manager.create_request(user_id, "device-1".to_string());
+        for _ in 0..=MAX_RETIRED_REQUESTS {
+            manager.create_request(user_id, "device-1".to_string());
+        }
+
+        let history = manager.history();
+        assert_eq!(history.len(), MAX_RETIRED_REQUESTS + 1);
+        assert!(history.iter().all(|req| req.id != first.id));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_survive_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("requests.json");
+
+        let manager = RequestManager::new().with_persistence(&path).unwrap();
+        tokio::spawn(manager.clone().persist(path.clone()));
+        let user_id = Uuid::new_v4();
+        let fulfilled = manager.create_request(user_id, "device-1".to_string());
+        manager
+            .fulfill(fulfilled.id, "path/to/logs.jsonl".to_string())
+            .unwrap();
+        let pending = manager.create_request(user_id, "device-2".to_string());
+
+        // Saved once the debounce has passed, not before
+        tokio::task::yield_now().await;
+        assert!(!path.exists());
+        tokio::time::advance(PERSIST_DEBOUNCE).await;
+        tokio::task::yield_now().await;
+
+        // A fresh manager, as after a restart
+        let reloaded = RequestManager::new().with_persistence(&path).unwrap();
+        assert_eq!(reloaded.get_pending("device-2").unwrap().id, pending.id);
+        let history = reloaded.list_for_user(user_id);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].id, fulfilled.id);
+        assert_eq!(history[0].status, LogRequestStatus::Fulfilled);
+    }
+
+    #[test]
+    fn test_missing_persist_file_starts_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RequestManager::new()
+            .with_persistence(&dir.path().join("requests.json"))
+            .unwrap();
+        assert_eq!(manager.stats().total, 0);
+    }
+
+    /// Create a request for `device_id` that expired `ago` before now
+    fn expired_request(manager: &RequestManager, device_id: &str, ago: Duration) -> LogRequest {
+        let request = manager.create_request(Uuid::new_v4(), device_id.to_string());
+        manager
+            .requests
+            .write()
+            .get_mut(device_id)
+            .unwrap()
+            .expires_at = Utc::now() - ago;
+
+        // Polling marks it expired
+        assert!(manager.get_pending(device_id).is_none());
+        request
+    }
+
+    #[test]
+    fn test_upload_within_grace_period() {
+        let manager = RequestManager::new().with_upload_grace(Duration::minutes(5));
+        let request = expired_request(&manager, "test-device", Duration::minutes(2));
+
+        let uploadable = manager.get_uploadable("test-device").unwrap();
+        assert_eq!(uploadable.id, request.id);
+        assert!(manager
+            .fulfill(request.id, "path/to/logs.jsonl".to_string())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_upload_beyond_grace_period() {
+        let manager = RequestManager::new().with_upload_grace(Duration::minutes(5));
+        let request = expired_request(&manager, "test-device", Duration::minutes(10));
+
+        assert!(manager.get_uploadable("test-device").is_none());
+        assert!(matches!(
+            manager.fulfill(request.id, "path/to/logs.jsonl".to_string()),
+            Err(RequestError::AlreadyProcessed)
+        ));
+    }
 }
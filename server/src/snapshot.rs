@@ -0,0 +1,199 @@
+//! Buffer persistence across restarts: periodic snapshots so a crash loses at
+//! most one interval of logs, and a flush on graceful shutdown.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::config::Config;
+use crate::AppState;
+
+/// Snapshot file name inside the upload directory when SNAPSHOT_PATH is unset
+pub const DEFAULT_SNAPSHOT_FILE: &str = "buffer-snapshot.jsonl";
+
+/// File inside the upload directory the buffer is flushed to on shutdown
+pub const PERSIST_FILE: &str = "buffer.jsonl";
+
+/// Periodic snapshot file
+pub fn snapshot_path(config: &Config) -> PathBuf {
+    config
+        .snapshot_path
+        .clone()
+        .unwrap_or_else(|| config.upload_dir.join(DEFAULT_SNAPSHOT_FILE))
+}
+
+/// Shutdown flush file used by PERSIST_BUFFER
+pub fn persist_path(config: &Config) -> PathBuf {
+    config.upload_dir.join(PERSIST_FILE)
+}
+
+/// The saved copy to reload the buffer from on startup, if any
+///
+/// Considers the shutdown flush (when younger than PERSIST_BUFFER_MAX_AGE_SECS)
+/// and the periodic snapshot, and picks the most recently written; after a
+/// crash the snapshot is newer than the previous run's flush.
+pub fn restore_path(config: &Config, now: SystemTime) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    if config.persist_buffer {
+        let max_age = Duration::from_secs(config.persist_buffer_max_age_secs);
+        candidates.push((persist_path(config), Some(max_age)));
+    }
+    if config.snapshot_interval_secs.is_some() {
+        candidates.push((snapshot_path(config), None));
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|(path, max_age)| {
+            let modified = modified_at(&path)?;
+            let age = now.duration_since(modified).unwrap_or_default();
+            max_age
+                .map_or(true, |max_age| age <= max_age)
+                .then_some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+/// Save the live buffer to `path` every `period` until the process exits
+pub async fn run(state: Arc<AppState>, path: PathBuf, period: Duration) {
+    let mut ticker = tokio::time::interval(period);
+    // The first tick completes immediately; there's nothing new to save yet
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+
+        match state.buffer.save_to_file(&path) {
+            Ok(count) => {
+                tracing::debug!(entries = count, path = %path.display(), "Saved buffer snapshot")
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, path = %path.display(), "Failed to save buffer snapshot")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::LogBuffer;
+    use crate::config::Config;
+    use crate::models::LogEntry;
+    use crate::storage::LogStorage;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn create_entry(id: &str) -> LogEntry {
+        LogEntry {
+            id: id.to_string(),
+            timestamp: Utc::now(),
+            level: "info".to_string(),
+            message: format!("message {}", id),
+            user_id: None,
+            device_id: "device-1".to_string(),
+            source: "test".to_string(),
+            metadata: HashMap::new(),
+            tags: vec![],
+            file: String::new(),
+            function: String::new(),
+            line: 0,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_snapshot_written_after_interval_and_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().join("uploads")).unwrap();
+        let state = Arc::new(AppState::new(Config::default(), Arc::new(storage), None));
+        for id in ["1", "2", "3"] {
+            state.buffer.append(create_entry(id));
+        }
+
+        let path = dir.path().join(DEFAULT_SNAPSHOT_FILE);
+        let task = tokio::spawn(run(state.clone(), path.clone(), Duration::from_millis(50)));
+
+        // The task takes its immediate first tick without saving
+        tokio::task::yield_now().await;
+        assert!(!path.exists());
+
+        tokio::time::advance(Duration::from_millis(50)).await;
+        tokio::task::yield_now().await;
+        task.abort();
+        assert!(path.exists());
+
+        let restored = LogBuffer::new(10);
+        assert_eq!(restored.load_from_file(&path).unwrap(), 3);
+        let ids: Vec<String> = restored.get_all().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+        assert_eq!(restored.get_all()[1].message, "message 2");
+    }
+
+    fn set_age(path: &Path, age: Duration) {
+        std::fs::write(path, "").unwrap();
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    #[test]
+    fn test_restore_path_respects_persist_max_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            upload_dir: dir.path().to_path_buf(),
+            persist_buffer: true,
+            persist_buffer_max_age_secs: 3600,
+            ..Config::default()
+        };
+        assert_eq!(restore_path(&config, SystemTime::now()), None);
+
+        set_age(&persist_path(&config), Duration::from_secs(60));
+        assert_eq!(
+            restore_path(&config, SystemTime::now()),
+            Some(persist_path(&config))
+        );
+
+        set_age(&persist_path(&config), Duration::from_secs(7200));
+        assert_eq!(restore_path(&config, SystemTime::now()), None);
+    }
+
+    #[test]
+    fn test_restore_path_prefers_newest_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            upload_dir: dir.path().to_path_buf(),
+            persist_buffer: true,
+            snapshot_interval_secs: Some(30),
+            ..Config::default()
+        };
+
+        // A crash after the last graceful shutdown: the snapshot is newer
+        set_age(&persist_path(&config), Duration::from_secs(600));
+        set_age(&snapshot_path(&config), Duration::from_secs(10));
+        assert_eq!(
+            restore_path(&config, SystemTime::now()),
+            Some(snapshot_path(&config))
+        );
+
+        set_age(&persist_path(&config), Duration::from_secs(1));
+        assert_eq!(
+            restore_path(&config, SystemTime::now()),
+            Some(persist_path(&config))
+        );
+    }
+
+    #[test]
+    fn test_missing_snapshot_loads_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let buffer = LogBuffer::new(10);
+
+        let loaded = buffer
+            .load_from_file(&dir.path().join(DEFAULT_SNAPSHOT_FILE))
+            .unwrap();
+        assert_eq!(loaded, 0);
+    }
+}
@@ -0,0 +1,562 @@
+//! SQLite storage backend for uploaded client logs.
+//!
+//! Alternative to the JSONL file tree: entries live in an indexed `logs`
+//! table so uploads can be queried without walking the filesystem.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+use crate::models::{LogEntry, LogUploadMetadata};
+use crate::storage::{StorageError, UploadStore};
+
+/// Database file name inside the upload directory
+pub const DATABASE_FILE: &str = "logs.sqlite3";
+
+/// Tables and indexes, created on open if missing
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS uploads (
+    request_id  TEXT PRIMARY KEY,
+    user_id     TEXT NOT NULL,
+    device_id   TEXT NOT NULL,
+    uploaded_at TEXT NOT NULL,
+    log_count   INTEGER NOT NULL,
+    size_bytes  INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_uploads_user ON uploads (user_id, uploaded_at);
+
+CREATE TABLE IF NOT EXISTS logs (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id     TEXT NOT NULL,
+    device_id   TEXT NOT NULL,
+    request_id  TEXT NOT NULL,
+    timestamp   TEXT NOT NULL,
+    entry       TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_logs_user ON logs (user_id);
+CREATE INDEX IF NOT EXISTS idx_logs_device ON logs (device_id);
+CREATE INDEX IF NOT EXISTS idx_logs_request ON logs (request_id);
+CREATE INDEX IF NOT EXISTS idx_logs_timestamp ON logs (timestamp);
+";
+
+/// Stores uploads in a single SQLite database
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open (or create) the database at `path`
+    pub fn open(path: &Path) -> Result<Self, StorageError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                StorageError::IoError(format!("Failed to create storage directory: {}", e))
+            })?;
+        }
+
+        let conn = Connection::open(path).map_err(db_error)?;
+        let storage = Self::with_connection(conn)?;
+
+        tracing::info!(path = %path.display(), "SQLite log storage initialized");
+
+        Ok(storage)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, StorageError> {
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Query stored entries across uploads
+    ///
+    /// Narrows by device and entry timestamp (inclusive) when given; results
+    /// are ordered by timestamp.
+    #[allow(dead_code)]
+    pub fn query_logs(
+        &self,
+        user_id: Uuid,
+        device_id: Option<&str>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogEntry>, StorageError> {
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare(
+                "SELECT entry FROM logs
+                 WHERE user_id = ?1
+                   AND (?2 IS NULL OR device_id = ?2)
+                   AND (?3 IS NULL OR timestamp >= ?3)
+                   AND (?4 IS NULL OR timestamp <= ?4)
+                 ORDER BY timestamp, id",
+            )
+            .map_err(db_error)?;
+
+        let rows = stmt
+            .query_map(
+                params![
+                    user_id.to_string(),
+                    device_id,
+                    from.map(sortable_timestamp),
+                    to.map(sortable_timestamp),
+                ],
+                |row| row.get::<_, String>(0),
+            )
+            .map_err(db_error)?;
+
+        rows.map(|row| parse_entry(&row.map_err(db_error)?))
+            .collect()
+    }
+}
+
+impl UploadStore for SqliteStorage {
+    fn save_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+        logs: &[LogEntry],
+    ) -> Result<LogUploadMetadata, StorageError> {
+        let now = Utc::now();
+        let user = user_id.to_string();
+        let request = request_id.to_string();
+
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().map_err(db_error)?;
+
+        // Replace any earlier upload for the same request
+        tx.execute("DELETE FROM logs WHERE request_id = ?1", params![request])
+            .map_err(db_error)?;
+
+        let mut size_bytes = 0u64;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO logs (user_id, device_id, request_id, timestamp, entry)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .map_err(db_error)?;
+
+            for log in logs {
+                let json = serde_json::to_string(log).map_err(|e| {
+                    StorageError::SerializationError(format!(
+                        "Failed to serialize log entry: {}",
+                        e
+                    ))
+                })?;
+                size_bytes += json.len() as u64 + 1;
+
+                insert
+                    .execute(params![
+                        user,
+                        device_id,
+                        request,
+                        sortable_timestamp(log.timestamp),
+                        json
+                    ])
+                    .map_err(db_error)?;
+            }
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO uploads
+             (request_id, user_id, device_id, uploaded_at, log_count, size_bytes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                request,
+                user,
+                device_id,
+                sortable_timestamp(now),
+                logs.len() as i64,
+                size_bytes as i64
+            ],
+        )
+        .map_err(db_error)?;
+
+        tx.commit().map_err(db_error)?;
+
+        tracing::info!(
+            user_id = %user_id,
+            device_id = %device_id,
+            request_id = %request_id,
+            log_count = logs.len(),
+            "Logs saved to SQLite"
+        );
+
+        Ok(LogUploadMetadata {
+            request_id: request,
+            device_id: device_id.to_string(),
+            uploaded_at: now.to_rfc3339(),
+            log_count: logs.len(),
+            file_size_bytes: size_bytes,
+        })
+    }
+
+    fn upload_location(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<String, StorageError> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            "SELECT 1 FROM uploads WHERE request_id = ?1 AND user_id = ?2 AND device_id = ?3",
+            params![request_id.to_string(), user_id.to_string(), device_id],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(db_error)?
+        .ok_or(StorageError::NotFound)?;
+
+        Ok(format!("{}#{}", DATABASE_FILE, request_id))
+    }
+
+    fn read_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<Vec<LogEntry>, StorageError> {
+        // Distinguish "no such upload" from an upload with zero entries
+        self.upload_location(user_id, device_id, request_id)?;
+
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare(
+                "SELECT entry FROM logs
+                 WHERE request_id = ?1 AND user_id = ?2 AND device_id = ?3
+                 ORDER BY id",
+            )
+            .map_err(db_error)?;
+
+        let rows = stmt
+            .query_map(
+                params![request_id.to_string(), user_id.to_string(), device_id],
+                |row| row.get::<_, String>(0),
+            )
+            .map_err(db_error)?;
+
+        rows.map(|row| parse_entry(&row.map_err(db_error)?))
+            .collect()
+    }
+
+    fn delete_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<(), StorageError> {
+        let request = request_id.to_string();
+        let user = user_id.to_string();
+
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().map_err(db_error)?;
+
+        let deleted = tx
+            .execute(
+                "DELETE FROM uploads WHERE request_id = ?1 AND user_id = ?2 AND device_id = ?3",
+                params![request, user, device_id],
+            )
+            .map_err(db_error)?;
+        if deleted == 0 {
+            return Err(StorageError::NotFound);
+        }
+        tx.execute(
+            "DELETE FROM logs WHERE request_id = ?1 AND user_id = ?2 AND device_id = ?3",
+            params![request, user, device_id],
+        )
+        .map_err(db_error)?;
+
+        tx.commit().map_err(db_error)
+    }
+
+    fn list_uploads(&self, user_id: Uuid) -> Result<Vec<LogUploadMetadata>, StorageError> {
+        self.list_uploads_between(user_id, None, None)
+    }
+
+    fn list_uploads_between(
+        &self,
+        user_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogUploadMetadata>, StorageError> {
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare(
+                "SELECT request_id, device_id, uploaded_at, log_count, size_bytes FROM uploads
+                 WHERE user_id = ?1
+                   AND (?2 IS NULL OR uploaded_at >= ?2)
+                   AND (?3 IS NULL OR uploaded_at <= ?3)
+                 ORDER BY uploaded_at",
+            )
+            .map_err(db_error)?;
+
+        let rows = stmt
+            .query_map(
+                params![
+                    user_id.to_string(),
+                    from.map(sortable_timestamp),
+                    to.map(sortable_timestamp),
+                ],
+                |row| {
+                    Ok(LogUploadMetadata {
+                        request_id: row.get(0)?,
+                        device_id: row.get(1)?,
+                        uploaded_at: row.get(2)?,
+                        log_count: row.get::<_, i64>(3)? as usize,
+                        file_size_bytes: row.get::<_, i64>(4)? as u64,
+                    })
+                },
+            )
+            .map_err(db_error)?;
+
+        rows.map(|row| row.map_err(db_error)).collect()
+    }
+
+    /// Delete uploads stored more than `days` ago along with their entries
+    fn cleanup_old_uploads(&self, days: i64) -> Result<usize, StorageError> {
+        let cutoff = sortable_timestamp(Utc::now() - chrono::Duration::days(days));
+
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().map_err(db_error)?;
+
+        tx.execute(
+            "DELETE FROM logs WHERE request_id IN
+             (SELECT request_id FROM uploads WHERE uploaded_at < ?1)",
+            params![cutoff],
+        )
+        .map_err(db_error)?;
+        let removed = tx
+            .execute(
+                "DELETE FROM uploads WHERE uploaded_at < ?1",
+                params![cutoff],
+            )
+            .map_err(db_error)?;
+
+        tx.commit().map_err(db_error)?;
+
+        if removed > 0 {
+            tracing::info!(
+                removed = removed,
+                days = days,
+                "Cleaned up old SQLite uploads"
+            );
+        }
+        Ok(removed)
+    }
+}
+
+/// RFC 3339 in UTC with fixed precision, so string order matches time order
+fn sortable_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_entry(json: &str) -> Result<LogEntry, StorageError> {
+    serde_json::from_str(json).map_err(|e| {
+        StorageError::SerializationError(format!("Failed to parse stored log entry: {}", e))
+    })
+}
+
+fn db_error(e: rusqlite::Error) -> StorageError {
+    StorageError::DatabaseError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+
+    fn in_memory() -> SqliteStorage {
+        SqliteStorage::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn create_entry(id: &str, timestamp: DateTime<Utc>) -> LogEntry {
+        LogEntry {
+            id: id.to_string(),
+            timestamp,
+            level: "info".to_string(),
+            message: format!("Message {}", id),
+            user_id: None,
+            device_id: "test-device".to_string(),
+            source: "test".to_string(),
+            metadata: HashMap::from([("key".to_string(), "value".to_string())]),
+            tags: vec!["tag".to_string()],
+            file: "main.swift".to_string(),
+            function: "run()".to_string(),
+            line: 42,
+        }
+    }
+
+    #[test]
+    fn test_save_and_read_round_trip() {
+        let storage = in_memory();
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        let logs = vec![create_entry("1", now), create_entry("2", now)];
+        let metadata = storage
+            .save_upload(user_id, "device-1", request_id, &logs)
+            .unwrap();
+        assert_eq!(metadata.log_count, 2);
+
+        let read = storage
+            .read_upload(user_id, "device-1", request_id)
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].id, "1");
+        assert_eq!(read[1].metadata["key"], "value");
+        assert_eq!(read[1].line, 42);
+
+        let uploads = storage.list_uploads(user_id).unwrap();
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0].request_id, request_id.to_string());
+        assert_eq!(uploads[0].log_count, 2);
+
+        // Other users and devices don't see the upload
+        assert!(storage.list_uploads(Uuid::new_v4()).unwrap().is_empty());
+        assert!(matches!(
+            storage.read_upload(user_id, "device-2", request_id),
+            Err(StorageError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_save_replaces_previous_upload() {
+        let storage = in_memory();
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        storage
+            .save_upload(user_id, "device-1", request_id, &[create_entry("old", now)])
+            .unwrap();
+        storage
+            .save_upload(user_id, "device-1", request_id, &[create_entry("new", now)])
+            .unwrap();
+
+        let read = storage
+            .read_upload(user_id, "device-1", request_id)
+            .unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].id, "new");
+        assert_eq!(storage.list_uploads(user_id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_cleanup_old_uploads() {
+        let storage = in_memory();
+        let user_id = Uuid::new_v4();
+        let old = Uuid::new_v4();
+        let recent = Uuid::new_v4();
+
+        for request_id in [old, recent] {
+            let logs = [create_entry("1", Utc::now())];
+            storage
+                .save_upload(user_id, "device-1", request_id, &logs)
+                .unwrap();
+        }
+        let stored_at = sortable_timestamp(Utc::now() - chrono::Duration::days(8));
+        storage
+            .conn
+            .lock()
+            .execute(
+                "UPDATE uploads SET uploaded_at = ?1 WHERE request_id = ?2",
+                params![stored_at, old.to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(storage.cleanup_old_uploads(7).unwrap(), 1);
+
+        let uploads = storage.list_uploads(user_id).unwrap();
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0].request_id, recent.to_string());
+        assert!(matches!(
+            storage.read_upload(user_id, "device-1", old),
+            Err(StorageError::NotFound)
+        ));
+        let remaining = storage.query_logs(user_id, None, None, None).unwrap();
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_upload_then_read_not_found() {
+        let storage = in_memory();
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+
+        let logs = [create_entry("1", Utc::now())];
+        storage
+            .save_upload(user_id, "device-1", request_id, &logs)
+            .unwrap();
+        storage
+            .delete_upload(user_id, "device-1", request_id)
+            .unwrap();
+
+        assert!(matches!(
+            storage.read_upload(user_id, "device-1", request_id),
+            Err(StorageError::NotFound)
+        ));
+        assert!(matches!(
+            storage.delete_upload(user_id, "device-1", request_id),
+            Err(StorageError::NotFound)
+        ));
+        let remaining = storage.query_logs(user_id, None, None, None).unwrap();
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn test_query_logs_by_device_and_time() {
+        let storage = in_memory();
+        let user_id = Uuid::new_v4();
+        let at = |h: u32| Utc.with_ymd_and_hms(2024, 3, 1, h, 0, 0).unwrap();
+
+        storage
+            .save_upload(
+                user_id,
+                "device-1",
+                Uuid::new_v4(),
+                &[create_entry("a", at(9)), create_entry("b", at(12))],
+            )
+            .unwrap();
+        storage
+            .save_upload(
+                user_id,
+                "device-2",
+                Uuid::new_v4(),
+                &[create_entry("c", at(10))],
+            )
+            .unwrap();
+
+        let ids = |device, from, to| {
+            storage
+                .query_logs(user_id, device, from, to)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(None, None, None), ["a", "c", "b"]);
+        assert_eq!(ids(Some("device-1"), None, None), ["a", "b"]);
+        assert_eq!(ids(None, Some(at(10)), None), ["c", "b"]);
+        assert_eq!(ids(None, Some(at(10)), Some(at(11))), ["c"]);
+    }
+
+    #[test]
+    fn test_open_creates_database_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(DATABASE_FILE);
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        storage
+            .save_upload(Uuid::new_v4(), "device-1", Uuid::new_v4(), &[])
+            .unwrap();
+
+        assert!(path.is_file());
+    }
+}
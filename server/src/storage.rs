@@ -2,17 +2,165 @@
 //!
 //! Manages persistent storage of log uploads with automatic cleanup.
 
-use crate::models::{LogEntry, LogUploadMetadata};
-use chrono::Utc;
+use crate::models::{LogEntry, LogUploadMetadata, UploadUsage};
+use crate::upload_cache::UploadCache;
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 use uuid::Uuid;
 
+/// Default layout of upload files relative to the storage root
+pub const DEFAULT_PATH_TEMPLATE: &str = "{user}/{device}/{request}.jsonl";
+
+/// File extension for uploaded log files
+const UPLOAD_EXTENSION: &str = ".jsonl";
+
+/// File extension for uploads stored gzip-compressed
+const COMPRESSED_EXTENSION: &str = ".jsonl.gz";
+
+/// Marker before the index of a split upload's part file (`{request}.part0.jsonl`)
+const PART_MARKER: &str = ".part";
+
+/// Extension of the file recording an upload's user when the template has no `{user}`
+const OWNER_EXTENSION: &str = ".owner";
+
+/// Backend that persists uploaded client logs
+pub trait UploadStore: Send + Sync {
+    /// Persist an upload, replacing any earlier upload for the same request
+    fn save_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+        logs: &[LogEntry],
+    ) -> Result<LogUploadMetadata, StorageError>;
+
+    /// Describe where an upload is stored (recorded on the fulfilled request)
+    fn upload_location(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<String, StorageError>;
+
+    /// Read the entries of a stored upload
+    fn read_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<Vec<LogEntry>, StorageError>;
+
+    /// Open a stored upload as JSONL bytes, for streaming without parsing entries
+    ///
+    /// The default re-serializes [`read_upload`](Self::read_upload); file
+    /// storage streams the file itself.
+    fn open_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<Box<dyn Read + Send>, StorageError> {
+        let mut jsonl = Vec::new();
+        for log in self.read_upload(user_id, device_id, request_id)? {
+            serde_json::to_writer(&mut jsonl, &log)
+                .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+            jsonl.push(b'\n');
+        }
+        Ok(Box::new(io::Cursor::new(jsonl)))
+    }
+
+    /// Delete a stored upload, or fail with `NotFound` if there is none
+    fn delete_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<(), StorageError>;
+
+    /// List all uploads for a specific user
+    fn list_uploads(&self, user_id: Uuid) -> Result<Vec<LogUploadMetadata>, StorageError>;
+
+    /// Disk space taken up by a user's uploads
+    ///
+    /// Counts one file per upload unless the backend knows better.
+    fn upload_usage(&self, user_id: Uuid) -> Result<UploadUsage, StorageError> {
+        let uploads = self.list_uploads(user_id)?;
+        Ok(UploadUsage {
+            total_bytes: uploads.iter().map(|upload| upload.file_size_bytes).sum(),
+            file_count: uploads.len(),
+        })
+    }
+
+    /// Delete uploads older than `days`, returning the number removed
+    /// (files for file storage, uploads for a database)
+    fn cleanup_old_uploads(&self, days: i64) -> Result<usize, StorageError>;
+
+    /// List a user's uploads from one device, oldest first
+    fn list_uploads_for_device(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+    ) -> Result<Vec<LogUploadMetadata>, StorageError> {
+        let device_id = sanitize_filename(device_id);
+        let mut uploads: Vec<LogUploadMetadata> = self
+            .list_uploads(user_id)?
+            .into_iter()
+            .filter(|upload| sanitize_filename(&upload.device_id) == device_id)
+            .collect();
+
+        uploads.sort_by(|a, b| a.uploaded_at.cmp(&b.uploaded_at));
+        Ok(uploads)
+    }
+
+    /// List uploads for a user modified within `[from, to]`
+    ///
+    /// Either bound may be omitted to leave that side of the range open.
+    fn list_uploads_between(
+        &self,
+        user_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogUploadMetadata>, StorageError> {
+        let uploads = self.list_uploads(user_id)?;
+
+        Ok(uploads
+            .into_iter()
+            .filter(|upload| {
+                let Ok(uploaded_at) = DateTime::parse_from_rfc3339(&upload.uploaded_at) else {
+                    return true;
+                };
+                from.map_or(true, |from| uploaded_at >= from)
+                    && to.map_or(true, |to| uploaded_at <= to)
+            })
+            .collect())
+    }
+}
+
+/// Disk space taken up by every stored upload
+#[derive(Debug, Default)]
+pub struct DiskUsage {
+    pub total: UploadUsage,
+    /// Usage by user id
+    pub per_user: HashMap<String, UploadUsage>,
+}
+
 /// Manages file storage for uploaded logs
 #[derive(Clone)]
 pub struct LogStorage {
     base_path: PathBuf,
+    template: PathTemplate,
+    /// Write new uploads as `.jsonl.gz`
+    compress: bool,
+    /// Split uploads with more entries than this into part files
+    split_threshold: Option<usize>,
+    /// Parsed uploads kept for repeated reads
+    cache: Option<Arc<UploadCache>>,
 }
 
 impl LogStorage {
@@ -25,36 +173,231 @@ impl LogStorage {
 
         tracing::info!(path = %base_path.display(), "Log storage initialized");
 
-        Ok(Self { base_path })
+        Ok(Self {
+            base_path,
+            template: PathTemplate::default(),
+            compress: false,
+            split_threshold: None,
+            cache: None,
+        })
     }
 
-    /// Save uploaded logs to disk
+    /// Keep recently read uploads parsed in memory, up to `max_entries` log
+    /// entries in total; a cached upload is re-read once its files change
+    pub fn with_read_cache(mut self, max_entries: usize) -> Self {
+        self.cache = Some(Arc::new(UploadCache::new(max_entries)));
+        self
+    }
+
+    /// Store uploads of more than `threshold` entries as numbered part files
+    /// of at most `threshold` entries each; reads reassemble them in order
+    pub fn with_split_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.split_threshold = threshold.filter(|&threshold| threshold > 0);
+        self
+    }
+
+    /// Gzip new uploads; existing plain and compressed uploads stay readable either way
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Use a custom layout for upload files
+    pub fn with_path_template(mut self, template: PathTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Find the stored file for an upload, relative to the storage root
     ///
-    /// Stores logs in: `{base_path}/{user_id}/{device_id}/{request_id}.jsonl`
-    pub fn save_upload(
+    /// For a split upload this is its first part file.
+    pub fn locate_upload(
         &self,
         user_id: Uuid,
         device_id: &str,
         request_id: Uuid,
-        logs: &[LogEntry],
-    ) -> Result<LogUploadMetadata, StorageError> {
-        // Sanitize device_id to prevent path traversal
-        let safe_device_id = sanitize_filename(device_id);
-
-        // Create directory structure: base/user_id/device_id/
-        let user_dir = self.base_path.join(user_id.to_string());
-        let device_dir = user_dir.join(&safe_device_id);
-        fs::create_dir_all(&device_dir).map_err(|e| {
-            StorageError::IoError(format!("Failed to create device directory: {}", e))
-        })?;
+    ) -> Result<PathBuf, StorageError> {
+        let path = self
+            .upload_files(user_id, device_id, request_id)?
+            .swap_remove(0);
+
+        Ok(path
+            .strip_prefix(&self.base_path)
+            .map(Path::to_path_buf)
+            .unwrap_or(path))
+    }
+
+    /// Every file holding an upload, in order (several for a split upload)
+    fn upload_files(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<Vec<PathBuf>, StorageError> {
+        Ok(self.find_upload(user_id, device_id, request_id)?.files)
+    }
+
+    /// Locate one upload of `user_id`
+    fn find_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<FoundUpload, StorageError> {
+        let bindings = HashMap::from([
+            (Placeholder::User, user_id.to_string()),
+            (Placeholder::Device, sanitize_filename(device_id)),
+            (Placeholder::Request, request_id.to_string()),
+        ]);
+
+        self.find_uploads(&bindings)
+            .into_iter()
+            .next()
+            .ok_or(StorageError::NotFound)
+    }
+
+    /// Delete old log files (cleanup)
+    ///
+    /// Removes files older than the specified number of days.
+    pub fn cleanup_old_logs(&self, days: i64) -> Result<usize, StorageError> {
+        let cutoff = Utc::now() - chrono::Duration::days(days);
+        let cutoff_timestamp = cutoff.timestamp();
+
+        let mut removed = 0;
+
+        for found in self.find_uploads(&HashMap::new()) {
+            // Check file age
+            let metadata = match fs::metadata(found.path()) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+
+            let modified = match metadata.modified() {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+
+            let timestamp = match modified.duration_since(std::time::UNIX_EPOCH) {
+                Ok(d) => d.as_secs() as i64,
+                Err(_) => continue,
+            };
+
+            if timestamp >= cutoff_timestamp {
+                continue;
+            }
+            // Parts of a split upload go together
+            for path in &found.files {
+                if fs::remove_file(path).is_ok() {
+                    removed += 1;
+                    tracing::debug!(path = %path.display(), "Removed old log file");
+                }
+            }
+            found.remove_owner();
+        }
+
+        if removed > 0 {
+            tracing::info!(removed = removed, days = days, "Cleaned up old log files");
+        }
+
+        Ok(removed)
+    }
+
+    /// Total size and number of upload files, overall and per user
+    pub fn disk_usage(&self) -> Result<DiskUsage, StorageError> {
+        Ok(self.usage(&HashMap::new()))
+    }
+
+    /// Sum the files of the uploads matching `bindings`
+    fn usage(&self, bindings: &HashMap<Placeholder, String>) -> DiskUsage {
+        let mut usage = DiskUsage::default();
+
+        for found in self.find_uploads(bindings) {
+            let user = usage
+                .per_user
+                .entry(found.value(Placeholder::User))
+                .or_default();
+
+            for path in &found.files {
+                // A file deleted since the walk no longer takes up space
+                let Ok(metadata) = fs::metadata(path) else {
+                    continue;
+                };
+                user.total_bytes += metadata.len();
+                user.file_count += 1;
+            }
+        }
+
+        for user in usage.per_user.values() {
+            usage.total.total_bytes += user.total_bytes;
+            usage.total.file_count += user.file_count;
+        }
+
+        usage
+    }
+
+    /// Keep only the newest uploads of each device (retention by count)
+    ///
+    /// Within each device directory, uploads beyond the `per_device` most
+    /// recently modified are removed with all their parts. Returns the number
+    /// of files removed.
+    #[allow(dead_code)]
+    pub fn cleanup_by_count(&self, per_device: usize) -> Result<usize, StorageError> {
+        let mut by_device: HashMap<(String, String), Vec<(SystemTime, FoundUpload)>> =
+            HashMap::new();
+
+        for found in self.find_uploads(&HashMap::new()) {
+            let modified = match fs::metadata(found.path()).and_then(|m| m.modified()) {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+
+            let device = (
+                found.value(Placeholder::User),
+                found.value(Placeholder::Device),
+            );
+            by_device.entry(device).or_default().push((modified, found));
+        }
+
+        let mut removed = 0;
+
+        for uploads in by_device.values_mut() {
+            // Newest first, so everything past `per_device` goes
+            uploads.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+            for (_, found) in uploads.iter().skip(per_device) {
+                for path in &found.files {
+                    if fs::remove_file(path).is_ok() {
+                        removed += 1;
+                        tracing::debug!(path = %path.display(), "Removed surplus log file");
+                    }
+                }
+                found.remove_owner();
+            }
+        }
+
+        if removed > 0 {
+            tracing::info!(
+                removed = removed,
+                per_device = per_device,
+                "Cleaned up surplus log files"
+            );
+        }
+
+        Ok(removed)
+    }
 
-        // Create log file: request_id.jsonl
-        let file_path = device_dir.join(format!("{}.jsonl", request_id));
-        let file = File::create(&file_path).map_err(|e| {
+    /// Write entries to one JSONL file, returning its size on disk
+    /// (compressed size for `.jsonl.gz`)
+    fn write_upload_file(&self, file_path: &Path, logs: &[LogEntry]) -> Result<u64, StorageError> {
+        let file = File::create(file_path).map_err(|e| {
             StorageError::IoError(format!("Failed to create log file: {}", e))
         })?;
 
-        let mut writer = BufWriter::new(file);
+        let mut writer: Box<dyn Write> = if self.compress {
+            Box::new(GzEncoder::new(BufWriter::new(file), Compression::default()))
+        } else {
+            Box::new(BufWriter::new(file))
+        };
 
         // Write logs in JSON Lines format (one JSON object per line)
         for log in logs {
@@ -70,18 +413,196 @@ impl LogStorage {
         writer.flush().map_err(|e| {
             StorageError::IoError(format!("Failed to flush writer: {}", e))
         })?;
+        // Dropping the encoder writes the gzip trailer
+        drop(writer);
 
-        // Get file size
-        let metadata = fs::metadata(&file_path).map_err(|e| {
+        let metadata = fs::metadata(file_path).map_err(|e| {
             StorageError::IoError(format!("Failed to read file metadata: {}", e))
         })?;
+        Ok(metadata.len())
+    }
+
+    /// Walk the storage tree and collect upload files matching the template
+    ///
+    /// Placeholders present in `bindings` must match exactly; fully bound path
+    /// segments are joined directly instead of scanning the directory.
+    /// Part files of a split upload are grouped into one [`FoundUpload`].
+    /// Without `{user}` in the template, each upload's user is read from its
+    /// owner file instead.
+    fn find_uploads(&self, bindings: &HashMap<Placeholder, String>) -> Vec<FoundUpload> {
+        let mut files = Vec::new();
+        let mut walk_bindings = bindings.clone();
+        if !self.template.has(Placeholder::User) {
+            walk_bindings.remove(&Placeholder::User);
+        }
+        self.walk_segment(&self.base_path, 0, walk_bindings, &mut files);
+
+        files.sort_by(|a, b| (&a.stem, a.part).cmp(&(&b.stem, b.part)));
+        let mut found: Vec<FoundUpload> = Vec::new();
+        for file in files {
+            match found.last_mut() {
+                Some(upload) if upload.stem == file.stem => upload.files.push(file.path),
+                _ => found.push(FoundUpload {
+                    stem: file.stem,
+                    files: vec![file.path],
+                    captures: file.captures,
+                }),
+            }
+        }
+
+        if !self.template.has(Placeholder::User) {
+            for upload in &mut found {
+                if let Ok(owner) = fs::read_to_string(upload.owner_path()) {
+                    upload
+                        .captures
+                        .insert(Placeholder::User, owner.trim().to_string());
+                }
+            }
+            if let Some(user) = bindings.get(&Placeholder::User) {
+                found.retain(|upload| upload.captures.get(&Placeholder::User) == Some(user));
+            }
+        }
+        found
+    }
+
+    fn walk_segment(
+        &self,
+        dir: &Path,
+        index: usize,
+        captures: HashMap<Placeholder, String>,
+        found: &mut Vec<FoundFile>,
+    ) {
+        let segment = &self.template.segments[index];
+        let is_last = index + 1 == self.template.segments.len();
+
+        // Fast path: every placeholder in this segment is already known
+        if let Some(name) = segment.render_bound(&captures) {
+            if is_last {
+                let stem = dir.join(&name);
+                for path in files_for_stem(&stem) {
+                    let part = path.file_name().and_then(|file_name| {
+                        split_upload_name(&file_name.to_string_lossy()).and_then(|(_, part)| part)
+                    });
+                    found.push(FoundFile {
+                        path,
+                        stem: stem.clone(),
+                        part,
+                        captures: captures.clone(),
+                    });
+                }
+            } else {
+                let path = dir.join(name);
+                if path.is_dir() {
+                    self.walk_segment(&path, index + 1, captures, found);
+                }
+            }
+            return;
+        }
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+
+            let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+            let is_file = entry.file_type().map(|ft| ft.is_file()).unwrap_or(false);
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let (name, part) = if is_last {
+                match split_upload_name(&file_name) {
+                    Some((stem, part)) if is_file => (stem.to_string(), part),
+                    _ => continue,
+                }
+            } else if is_dir {
+                (file_name, None)
+            } else {
+                continue;
+            };
+
+            if let Some(captures) = segment.matches(&name, &captures) {
+                if is_last {
+                    found.push(FoundFile {
+                        path: entry.path(),
+                        stem: dir.join(&name),
+                        part,
+                        captures,
+                    });
+                } else {
+                    self.walk_segment(&entry.path(), index + 1, captures, found);
+                }
+            }
+        }
+    }
+}
+
+impl UploadStore for LogStorage {
+    /// Save uploaded logs to disk
+    ///
+    /// Stores logs at the path produced by the storage template, by default
+    /// `{base_path}/{user_id}/{device_id}/{request_id}.jsonl` (`.jsonl.gz`
+    /// when compressing). Uploads over the split threshold are written as
+    /// `{request_id}.part0.jsonl`, `{request_id}.part1.jsonl`, ... instead.
+    fn save_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+        logs: &[LogEntry],
+    ) -> Result<LogUploadMetadata, StorageError> {
+        let now = Utc::now();
+
+        // Placeholders are sanitized to prevent path traversal
+        let stem = self
+            .base_path
+            .join(self.template.render(user_id, device_id, request_id, now));
+        let extension = if self.compress {
+            COMPRESSED_EXTENSION
+        } else {
+            UPLOAD_EXTENSION
+        };
+
+        // A re-upload in the other format or split differently replaces the earlier files
+        for path in files_for_stem(&stem) {
+            let _ = fs::remove_file(path);
+        }
+
+        // Create the directory structure for the rendered path
+        if let Some(parent) = stem.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                StorageError::IoError(format!("Failed to create device directory: {}", e))
+            })?;
+        }
+
+        let mut file_size = 0;
+        match self.split_threshold {
+            Some(threshold) if logs.len() > threshold => {
+                for (index, part) in logs.chunks(threshold).enumerate() {
+                    let suffix = format!("{}{}{}", PART_MARKER, index, extension);
+                    file_size += self.write_upload_file(&with_extension(&stem, &suffix), part)?;
+                }
+            }
+            _ => file_size = self.write_upload_file(&with_extension(&stem, extension), logs)?,
+        }
+
+        // The path doesn't say whose upload this is, so record it alongside
+        if !self.template.has(Placeholder::User) {
+            fs::write(with_extension(&stem, OWNER_EXTENSION), user_id.to_string()).map_err(
+                |e| StorageError::IoError(format!("Failed to write upload owner: {}", e)),
+            )?;
+        }
 
         let upload_metadata = LogUploadMetadata {
             request_id: request_id.to_string(),
             device_id: device_id.to_string(),
-            uploaded_at: Utc::now().to_rfc3339(),
+            uploaded_at: now.to_rfc3339(),
             log_count: logs.len(),
-            file_size_bytes: metadata.len(),
+            file_size_bytes: file_size,
         };
 
         tracing::info!(
@@ -89,36 +610,152 @@ impl LogStorage {
             device_id = %device_id,
             request_id = %request_id,
             log_count = logs.len(),
-            file_size = metadata.len(),
+            file_size = file_size,
             "Logs saved successfully"
         );
 
         Ok(upload_metadata)
     }
 
-    /// Read uploaded logs from disk
-    pub fn read_upload(
+    fn upload_location(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<String, StorageError> {
+        Ok(self
+            .locate_upload(user_id, device_id, request_id)?
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// Read uploaded logs from disk, joining the parts of a split upload in order
+    fn read_upload(
         &self,
         user_id: Uuid,
         device_id: &str,
         request_id: Uuid,
     ) -> Result<Vec<LogEntry>, StorageError> {
-        let safe_device_id = sanitize_filename(device_id);
-        let file_path = self
-            .base_path
-            .join(user_id.to_string())
-            .join(&safe_device_id)
-            .join(format!("{}.jsonl", request_id));
+        let files = self.upload_files(user_id, device_id, request_id)?;
 
-        if !file_path.exists() {
-            return Err(StorageError::NotFound);
+        match &self.cache {
+            Some(cache) => cache.read_through(&files, || parse_upload_files(&files)),
+            None => parse_upload_files(&files),
         }
+    }
 
-        let content = fs::read_to_string(&file_path).map_err(|e| {
-            StorageError::IoError(format!("Failed to read log file: {}", e))
-        })?;
+    /// Stream the stored files, inflating `.jsonl.gz` uploads on the fly
+    fn open_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<Box<dyn Read + Send>, StorageError> {
+        let mut reader: Box<dyn Read + Send> = Box::new(io::empty());
 
-        let mut logs = Vec::new();
+        for file_path in self.upload_files(user_id, device_id, request_id)? {
+            let file = File::open(&file_path)
+                .map_err(|e| StorageError::IoError(format!("Failed to open log file: {}", e)))?;
+            reader = if file_path.to_string_lossy().ends_with(COMPRESSED_EXTENSION) {
+                Box::new(reader.chain(GzDecoder::new(file)))
+            } else {
+                Box::new(reader.chain(file))
+            };
+        }
+
+        Ok(reader)
+    }
+
+    /// Remove an upload's file, or every part of a split upload
+    fn delete_upload(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+    ) -> Result<(), StorageError> {
+        let found = self.find_upload(user_id, device_id, request_id)?;
+        for path in &found.files {
+            fs::remove_file(path)
+                .map_err(|e| StorageError::IoError(format!("Failed to delete log file: {}", e)))?;
+        }
+        found.remove_owner();
+
+        tracing::info!(
+            user_id = %user_id,
+            device_id = %device_id,
+            request_id = %request_id,
+            "Upload deleted"
+        );
+
+        Ok(())
+    }
+
+    /// List all uploads for a specific user
+    fn list_uploads(&self, user_id: Uuid) -> Result<Vec<LogUploadMetadata>, StorageError> {
+        let bindings = HashMap::from([(Placeholder::User, user_id.to_string())]);
+
+        let mut uploads = Vec::new();
+
+        for found in self.find_uploads(&bindings) {
+            let metadata = fs::metadata(found.path()).map_err(|e| {
+                StorageError::IoError(format!("Failed to read file metadata: {}", e))
+            })?;
+
+            // Count lines and bytes across all parts
+            let mut log_count = 0;
+            let mut file_size_bytes = 0;
+            for path in &found.files {
+                let content = read_upload_file(path)
+                    .map_err(|e| StorageError::IoError(format!("Failed to read file: {}", e)))?;
+                log_count += content.lines().filter(|l| !l.trim().is_empty()).count();
+                file_size_bytes += fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            }
+
+            uploads.push(LogUploadMetadata {
+                request_id: found.value(Placeholder::Request),
+                device_id: found.value(Placeholder::Device),
+                uploaded_at: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| {
+                        chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
+                            .unwrap_or_default()
+                            .to_rfc3339()
+                    })
+                    .unwrap_or_else(|| Utc::now().to_rfc3339()),
+                log_count,
+                file_size_bytes,
+            });
+        }
+
+        Ok(uploads)
+    }
+
+    fn upload_usage(&self, user_id: Uuid) -> Result<UploadUsage, StorageError> {
+        let bindings = HashMap::from([(Placeholder::User, user_id.to_string())]);
+        Ok(self.usage(&bindings).total)
+    }
+
+    fn cleanup_old_uploads(&self, days: i64) -> Result<usize, StorageError> {
+        self.cleanup_old_logs(days)
+    }
+}
+
+/// `stem` with an upload extension appended
+fn with_extension(stem: &Path, extension: &str) -> PathBuf {
+    let mut name = stem.as_os_str().to_owned();
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// Parse the entries of an upload's files, in order
+fn parse_upload_files(files: &[PathBuf]) -> Result<Vec<LogEntry>, StorageError> {
+    let mut logs = Vec::new();
+
+    for file_path in files {
+        let content = read_upload_file(file_path)
+            .map_err(|e| StorageError::IoError(format!("Failed to read log file: {}", e)))?;
 
         // Parse JSON Lines format
         for (line_num, line) in content.lines().enumerate() {
@@ -136,196 +773,369 @@ impl LogStorage {
 
             logs.push(log);
         }
-
-        Ok(logs)
     }
 
-    /// List all uploads for a specific user
-    pub fn list_uploads(&self, user_id: Uuid) -> Result<Vec<LogUploadMetadata>, StorageError> {
-        let user_dir = self.base_path.join(user_id.to_string());
+    Ok(logs)
+}
+
+/// Split an upload file name into the stem matched against the template and
+/// its part index, e.g. `{request}.part2.jsonl` → (`{request}`, `Some(2)`)
+fn split_upload_name(file_name: &str) -> Option<(&str, Option<usize>)> {
+    let stem = file_name
+        .strip_suffix(UPLOAD_EXTENSION)
+        .or_else(|| file_name.strip_suffix(COMPRESSED_EXTENSION))?;
 
-        if !user_dir.exists() {
-            return Ok(Vec::new());
+    let part = stem
+        .rsplit_once(PART_MARKER)
+        .filter(|(_, index)| index.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|(base, index)| Some((base, index.parse().ok()?)));
+    Some(match part {
+        Some((base, index)) => (base, Some(index)),
+        None => (stem, None),
+    })
+}
+
+/// Files stored for the upload at `stem`: the single file in either format,
+/// then any part files in order
+fn files_for_stem(stem: &Path) -> Vec<PathBuf> {
+    let existing = |suffix: &str| {
+        [UPLOAD_EXTENSION, COMPRESSED_EXTENSION]
+            .into_iter()
+            .map(|extension| with_extension(stem, &format!("{}{}", suffix, extension)))
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>()
+    };
+
+    let mut files = existing("");
+    for index in 0.. {
+        let part = existing(&format!("{}{}", PART_MARKER, index));
+        if part.is_empty() {
+            break;
         }
+        files.extend(part);
+    }
+    files
+}
 
-        let mut uploads = Vec::new();
+/// Read an upload file's JSONL content, inflating `.jsonl.gz` files
+fn read_upload_file(path: &Path) -> io::Result<String> {
+    let file = File::open(path)?;
+    let mut content = String::new();
+    if path.to_string_lossy().ends_with(COMPRESSED_EXTENSION) {
+        GzDecoder::new(file).read_to_string(&mut content)?;
+    } else {
+        io::BufReader::new(file).read_to_string(&mut content)?;
+    }
+    Ok(content)
+}
 
-        // Iterate through device directories
-        let device_dirs = fs::read_dir(&user_dir).map_err(|e| {
-            StorageError::IoError(format!("Failed to read user directory: {}", e))
-        })?;
+/// A file matching the template, found while walking the storage tree
+struct FoundFile {
+    path: PathBuf,
+    /// Path without the part marker and extension, shared by an upload's parts
+    stem: PathBuf,
+    part: Option<usize>,
+    captures: HashMap<Placeholder, String>,
+}
 
-        for device_entry in device_dirs {
-            let device_entry = device_entry.map_err(|e| {
-                StorageError::IoError(format!("Failed to read device entry: {}", e))
-            })?;
+/// An upload located by walking the storage tree
+struct FoundUpload {
+    stem: PathBuf,
+    /// The upload file, or a split upload's parts in order
+    files: Vec<PathBuf>,
+    captures: HashMap<Placeholder, String>,
+}
 
-            if !device_entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-                continue;
-            }
+impl FoundUpload {
+    /// The upload file, or the first part of a split upload
+    fn path(&self) -> &Path {
+        &self.files[0]
+    }
 
-            let device_id = device_entry.file_name().to_string_lossy().to_string();
+    /// Captured placeholder value, or an empty string if the template lacks it
+    fn value(&self, placeholder: Placeholder) -> String {
+        self.captures.get(&placeholder).cloned().unwrap_or_default()
+    }
 
-            // Iterate through log files in device directory
-            let log_files = fs::read_dir(device_entry.path()).map_err(|e| {
-                StorageError::IoError(format!("Failed to read device directory: {}", e))
-            })?;
+    /// File naming the upload's user, written when the template has no `{user}`
+    fn owner_path(&self) -> PathBuf {
+        with_extension(&self.stem, OWNER_EXTENSION)
+    }
 
-            for file_entry in log_files {
-                let file_entry = file_entry.map_err(|e| {
-                    StorageError::IoError(format!("Failed to read file entry: {}", e))
-                })?;
+    /// Remove the owner file along with the upload, if there is one
+    fn remove_owner(&self) {
+        let _ = fs::remove_file(self.owner_path());
+    }
+}
 
-                if !file_entry
-                    .file_type()
-                    .map(|ft| ft.is_file())
-                    .unwrap_or(false)
-                {
-                    continue;
-                }
+/// Placeholders supported in storage path templates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Placeholder {
+    User,
+    Device,
+    Request,
+    Year,
+    Month,
+    Day,
+}
 
-                let file_name = file_entry.file_name().to_string_lossy().to_string();
-                if !file_name.ends_with(".jsonl") {
-                    continue;
-                }
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "user" => Some(Self::User),
+            "device" => Some(Self::Device),
+            "request" => Some(Self::Request),
+            "yyyy" => Some(Self::Year),
+            "mm" => Some(Self::Month),
+            "dd" => Some(Self::Day),
+            _ => None,
+        }
+    }
 
-                let request_id = file_name.trim_end_matches(".jsonl").to_string();
+    /// Fixed width of date placeholders (None = variable length)
+    fn width(&self) -> Option<usize> {
+        match self {
+            Self::Year => Some(4),
+            Self::Month | Self::Day => Some(2),
+            _ => None,
+        }
+    }
 
-                let metadata = fs::metadata(file_entry.path()).map_err(|e| {
-                    StorageError::IoError(format!("Failed to read file metadata: {}", e))
-                })?;
+    /// Whether a character can appear in this placeholder's value
+    fn accepts(&self, c: char) -> bool {
+        match self.width() {
+            Some(_) => c.is_ascii_digit(),
+            None => c.is_alphanumeric() || c == '-' || c == '_',
+        }
+    }
+}
 
-                // Count lines in file
-                let content = fs::read_to_string(file_entry.path()).map_err(|e| {
-                    StorageError::IoError(format!("Failed to read file: {}", e))
+#[derive(Debug, Clone)]
+enum Token {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// One `/`-separated component of a path template
+#[derive(Debug, Clone)]
+struct Segment {
+    tokens: Vec<Token>,
+}
+
+impl Segment {
+    fn parse(segment: &str) -> Result<Self, StorageError> {
+        let mut tokens = Vec::new();
+        let mut rest = segment;
+
+        while !rest.is_empty() {
+            if let Some(after_brace) = rest.strip_prefix('{') {
+                let end = after_brace.find('}').ok_or_else(|| {
+                    StorageError::InvalidTemplate(format!("Unclosed placeholder in '{}'", segment))
                 })?;
-                let log_count = content.lines().filter(|l| !l.trim().is_empty()).count();
-
-                uploads.push(LogUploadMetadata {
-                    request_id,
-                    device_id: device_id.clone(),
-                    uploaded_at: metadata
-                        .modified()
-                        .ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| {
-                            chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                                .unwrap_or_default()
-                                .to_rfc3339()
-                        })
-                        .unwrap_or_else(|| Utc::now().to_rfc3339()),
-                    log_count,
-                    file_size_bytes: metadata.len(),
-                });
+                let name = &after_brace[..end];
+                let placeholder = Placeholder::from_name(name).ok_or_else(|| {
+                    StorageError::InvalidTemplate(format!("Unknown placeholder {{{}}}", name))
+                })?;
+                if matches!(tokens.last(), Some(Token::Placeholder(p)) if p.width().is_none())
+                    && placeholder.width().is_none()
+                {
+                    return Err(StorageError::InvalidTemplate(format!(
+                        "Adjacent placeholders in '{}' are ambiguous",
+                        segment
+                    )));
+                }
+                tokens.push(Token::Placeholder(placeholder));
+                rest = &after_brace[end + 1..];
+            } else {
+                let end = rest.find('{').unwrap_or(rest.len());
+                let literal = &rest[..end];
+                if literal.contains('}') || literal == "." || literal == ".." {
+                    return Err(StorageError::InvalidTemplate(format!(
+                        "Invalid path segment '{}'",
+                        segment
+                    )));
+                }
+                tokens.push(Token::Literal(literal.to_string()));
+                rest = &rest[end..];
             }
         }
 
-        Ok(uploads)
+        Ok(Self { tokens })
     }
 
-    /// Delete old log files (cleanup)
-    ///
-    /// Removes files older than the specified number of days.
-    pub fn cleanup_old_logs(&self, days: i64) -> Result<usize, StorageError> {
-        let cutoff = Utc::now() - chrono::Duration::days(days);
-        let cutoff_timestamp = cutoff.timestamp();
+    fn placeholders(&self) -> impl Iterator<Item = Placeholder> + '_ {
+        self.tokens.iter().filter_map(|t| match t {
+            Token::Placeholder(p) => Some(*p),
+            Token::Literal(_) => None,
+        })
+    }
 
-        let mut removed = 0;
+    fn render(&self, values: &HashMap<Placeholder, String>) -> String {
+        self.tokens
+            .iter()
+            .map(|t| match t {
+                Token::Literal(s) => s.as_str(),
+                Token::Placeholder(p) => values.get(p).map(String::as_str).unwrap_or(""),
+            })
+            .collect()
+    }
 
-        // Iterate through all user directories
-        let user_dirs = fs::read_dir(&self.base_path).map_err(|e| {
-            StorageError::IoError(format!("Failed to read base directory: {}", e))
-        })?;
+    /// Render the segment if every placeholder is bound
+    fn render_bound(&self, values: &HashMap<Placeholder, String>) -> Option<String> {
+        if self.placeholders().all(|p| values.contains_key(&p)) {
+            Some(self.render(values))
+        } else {
+            None
+        }
+    }
 
-        for user_entry in user_dirs {
-            let user_entry = match user_entry {
-                Ok(e) => e,
-                Err(_) => continue,
-            };
+    /// Match a path component, returning the extended captures on success
+    fn matches(
+        &self,
+        name: &str,
+        captures: &HashMap<Placeholder, String>,
+    ) -> Option<HashMap<Placeholder, String>> {
+        let mut captures = captures.clone();
+        if match_tokens(&self.tokens, name, &mut captures) {
+            Some(captures)
+        } else {
+            None
+        }
+    }
+}
 
-            if !user_entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-                continue;
+/// Backtracking matcher for a token sequence against a path component
+fn match_tokens(
+    tokens: &[Token],
+    input: &str,
+    captures: &mut HashMap<Placeholder, String>,
+) -> bool {
+    let Some((first, rest)) = tokens.split_first() else {
+        return input.is_empty();
+    };
+
+    match first {
+        Token::Literal(lit) => match input.strip_prefix(lit.as_str()) {
+            Some(remaining) => match_tokens(rest, remaining, captures),
+            None => false,
+        },
+        Token::Placeholder(p) => {
+            if let Some(bound) = captures.get(p).cloned() {
+                return match input.strip_prefix(bound.as_str()) {
+                    Some(remaining) => match_tokens(rest, remaining, captures),
+                    None => false,
+                };
             }
 
-            // Iterate through device directories
-            let device_dirs = match fs::read_dir(user_entry.path()) {
-                Ok(dirs) => dirs,
-                Err(_) => continue,
+            let max_len = input.chars().take_while(|c| p.accepts(*c)).count();
+            let lengths: Vec<usize> = match p.width() {
+                Some(width) if width <= max_len => vec![width],
+                Some(_) => Vec::new(),
+                None => (1..=max_len).rev().collect(),
             };
 
-            for device_entry in device_dirs {
-                let device_entry = match device_entry {
-                    Ok(e) => e,
-                    Err(_) => continue,
-                };
-
-                if !device_entry
-                    .file_type()
-                    .map(|ft| ft.is_dir())
-                    .unwrap_or(false)
-                {
-                    continue;
+            for len in lengths {
+                let split = input
+                    .char_indices()
+                    .nth(len)
+                    .map(|(i, _)| i)
+                    .unwrap_or(input.len());
+                captures.insert(*p, input[..split].to_string());
+                if match_tokens(rest, &input[split..], captures) {
+                    return true;
                 }
+                captures.remove(p);
+            }
+            false
+        }
+    }
+}
 
-                // Iterate through log files
-                let log_files = match fs::read_dir(device_entry.path()) {
-                    Ok(files) => files,
-                    Err(_) => continue,
-                };
+/// Layout of upload files relative to the storage root
+///
+/// Supports `{user}`, `{device}`, `{request}`, `{yyyy}`, `{mm}` and `{dd}`
+/// placeholders, e.g. `{yyyy}/{mm}/{dd}/{user}/{device}/{request}.jsonl`.
+/// The trailing `.jsonl` extension is optional.
+#[derive(Debug, Clone)]
+pub struct PathTemplate {
+    segments: Vec<Segment>,
+}
 
-                for file_entry in log_files {
-                    let file_entry = match file_entry {
-                        Ok(e) => e,
-                        Err(_) => continue,
-                    };
-
-                    if !file_entry
-                        .file_type()
-                        .map(|ft| ft.is_file())
-                        .unwrap_or(false)
-                    {
-                        continue;
-                    }
+impl PathTemplate {
+    /// Parse a template string
+    ///
+    /// Templates must end in a segment containing `{request}`. Without
+    /// `{user}`, each upload's user is kept in a `.owner` file next to it.
+    pub fn parse(template: &str) -> Result<Self, StorageError> {
+        let template = template.trim().trim_matches('/');
+        let template = template.strip_suffix(UPLOAD_EXTENSION).unwrap_or(template);
 
-                    // Check file age
-                    let metadata = match fs::metadata(file_entry.path()) {
-                        Ok(m) => m,
-                        Err(_) => continue,
-                    };
-
-                    let modified = match metadata.modified() {
-                        Ok(t) => t,
-                        Err(_) => continue,
-                    };
-
-                    let timestamp = match modified.duration_since(std::time::UNIX_EPOCH) {
-                        Ok(d) => d.as_secs() as i64,
-                        Err(_) => continue,
-                    };
-
-                    if timestamp < cutoff_timestamp {
-                        if fs::remove_file(file_entry.path()).is_ok() {
-                            removed += 1;
-                            tracing::debug!(
-                                path = %file_entry.path().display(),
-                                "Removed old log file"
-                            );
-                        }
-                    }
-                }
-            }
+        if template.is_empty() {
+            return Err(StorageError::InvalidTemplate(
+                "Template is empty".to_string(),
+            ));
         }
 
-        if removed > 0 {
-            tracing::info!(removed = removed, days = days, "Cleaned up old log files");
+        let segments = template
+            .split('/')
+            .map(Segment::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if segments.iter().any(|s| s.tokens.is_empty()) {
+            return Err(StorageError::InvalidTemplate(
+                "Template contains an empty path segment".to_string(),
+            ));
         }
 
-        Ok(removed)
+        let last = segments.last().expect("template has at least one segment");
+        if !last.placeholders().any(|p| p == Placeholder::Request) {
+            return Err(StorageError::InvalidTemplate(
+                "Template must end in a segment containing {request}".to_string(),
+            ));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Whether any segment uses `placeholder`
+    fn has(&self, placeholder: Placeholder) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| segment.placeholders().any(|p| p == placeholder))
+    }
+
+    /// Render the relative file path for an upload, without its extension
+    fn render(
+        &self,
+        user_id: Uuid,
+        device_id: &str,
+        request_id: Uuid,
+        date: DateTime<Utc>,
+    ) -> PathBuf {
+        let values = HashMap::from([
+            (Placeholder::User, user_id.to_string()),
+            (Placeholder::Device, sanitize_filename(device_id)),
+            (Placeholder::Request, request_id.to_string()),
+            (Placeholder::Year, date.format("%Y").to_string()),
+            (Placeholder::Month, date.format("%m").to_string()),
+            (Placeholder::Day, date.format("%d").to_string()),
+        ]);
+
+        self.segments
+            .iter()
+            .map(|segment| segment.render(&values))
+            .collect()
+    }
+}
+
+impl Default for PathTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PATH_TEMPLATE).expect("default template is valid")
     }
 }
 
 /// Sanitize a filename to prevent path traversal attacks
-fn sanitize_filename(name: &str) -> String {
+pub(crate) fn sanitize_filename(name: &str) -> String {
     name.chars()
         .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
         .collect()
@@ -342,11 +1152,36 @@ pub enum StorageError {
 
     #[error("File not found")]
     NotFound,
+
+    #[error("Invalid storage path template: {0}")]
+    InvalidTemplate(String),
+
+    #[cfg(feature = "sqlite")]
+    #[error("Database error: {0}")]
+    DatabaseError(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
+
+    fn create_entry(id: &str) -> LogEntry {
+        LogEntry {
+            id: id.to_string(),
+            timestamp: Utc::now(),
+            level: "info".to_string(),
+            message: format!("Message {}", id),
+            user_id: None,
+            device_id: "test-device".to_string(),
+            source: "test".to_string(),
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            file: String::new(),
+            function: String::new(),
+            line: 0,
+        }
+    }
 
     #[test]
     fn test_sanitize_filename() {
@@ -354,4 +1189,404 @@ mod tests {
         assert_eq!(sanitize_filename("../../../etc/passwd"), "etcpasswd");
         assert_eq!(sanitize_filename("device@#$%123"), "device123");
     }
+
+    #[test]
+    fn test_default_template_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf()).unwrap();
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+
+        storage
+            .save_upload(user_id, "device-1", request_id, &[create_entry("1")])
+            .unwrap();
+
+        let expected = PathBuf::from(user_id.to_string())
+            .join("device-1")
+            .join(format!("{}.jsonl", request_id));
+        assert_eq!(
+            storage
+                .locate_upload(user_id, "device-1", request_id)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_compressed_upload_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf())
+            .unwrap()
+            .with_compression(true);
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        let logs: Vec<LogEntry> = (0..50).map(|i| create_entry(&i.to_string())).collect();
+
+        let saved = storage
+            .save_upload(user_id, "device-1", request_id, &logs)
+            .unwrap();
+
+        let location = storage
+            .locate_upload(user_id, "device-1", request_id)
+            .unwrap();
+        assert_eq!(
+            location,
+            PathBuf::from(user_id.to_string())
+                .join("device-1")
+                .join(format!("{}.jsonl.gz", request_id))
+        );
+        let on_disk = fs::metadata(dir.path().join(&location)).unwrap().len();
+        assert_eq!(saved.file_size_bytes, on_disk);
+
+        let read = storage
+            .read_upload(user_id, "device-1", request_id)
+            .unwrap();
+        let ids: Vec<&str> = read.iter().map(|e| e.id.as_str()).collect();
+        let expected: Vec<&str> = logs.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, expected);
+
+        let listed = storage.list_uploads(user_id).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].log_count, 50);
+        assert_eq!(listed[0].file_size_bytes, on_disk);
+
+        // Plain uploads stay readable with compression on
+        let plain_request = Uuid::new_v4();
+        LogStorage::new(dir.path().to_path_buf())
+            .unwrap()
+            .save_upload(user_id, "device-1", plain_request, &logs[..2])
+            .unwrap();
+        assert_eq!(
+            storage
+                .read_upload(user_id, "device-1", plain_request)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_split_upload_reassembled_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf())
+            .unwrap()
+            .with_split_threshold(Some(10));
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        let logs: Vec<LogEntry> = (0..25).map(|i| create_entry(&i.to_string())).collect();
+
+        let saved = storage
+            .save_upload(user_id, "device-1", request_id, &logs)
+            .unwrap();
+        assert_eq!(saved.log_count, 25);
+
+        let device_dir = dir.path().join(user_id.to_string()).join("device-1");
+        let part = |index: usize| device_dir.join(format!("{}.part{}.jsonl", request_id, index));
+        for index in 0..3 {
+            assert!(part(index).is_file());
+        }
+        assert!(!part(3).exists());
+        assert!(!device_dir.join(format!("{}.jsonl", request_id)).exists());
+        let on_disk: u64 = (0..3).map(|i| fs::metadata(part(i)).unwrap().len()).sum();
+        assert_eq!(saved.file_size_bytes, on_disk);
+
+        let read = storage
+            .read_upload(user_id, "device-1", request_id)
+            .unwrap();
+        let ids: Vec<&str> = read.iter().map(|e| e.id.as_str()).collect();
+        let expected: Vec<&str> = logs.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, expected);
+
+        let mut streamed = Vec::new();
+        storage
+            .open_upload(user_id, "device-1", request_id)
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed.iter().filter(|&&b| b == b'\n').count(), 25);
+
+        let listed = storage.list_uploads(user_id).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].log_count, 25);
+        assert_eq!(listed[0].file_size_bytes, on_disk);
+
+        // A smaller re-upload replaces every part
+        storage
+            .save_upload(user_id, "device-1", request_id, &logs[..5])
+            .unwrap();
+        assert!(!part(0).exists());
+        assert_eq!(
+            storage
+                .read_upload(user_id, "device-1", request_id)
+                .unwrap()
+                .len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_date_template_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let template =
+            PathTemplate::parse("{yyyy}/{mm}/{dd}/{user}/{device}/{request}.jsonl").unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf())
+            .unwrap()
+            .with_path_template(template);
+        let user_id = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+
+        let logs = vec![create_entry("1"), create_entry("2")];
+        storage
+            .save_upload(user_id, "device-1", request_id, &logs)
+            .unwrap();
+
+        let today = Utc::now();
+        let expected = dir
+            .path()
+            .join(today.format("%Y/%m/%d").to_string())
+            .join(user_id.to_string())
+            .join("device-1")
+            .join(format!("{}.jsonl", request_id));
+        assert!(expected.is_file());
+
+        let read = storage
+            .read_upload(user_id, "device-1", request_id)
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].id, "1");
+
+        let uploads = storage.list_uploads(user_id).unwrap();
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0].device_id, "device-1");
+        assert_eq!(uploads[0].request_id, request_id.to_string());
+        assert_eq!(uploads[0].log_count, 2);
+
+        // Other users don't see the upload
+        assert!(storage.list_uploads(Uuid::new_v4()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_template_without_user_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = PathTemplate::parse("{yyyy}/{mm}/{dd}/{device}/{request}.jsonl").unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf())
+            .unwrap()
+            .with_path_template(template);
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+
+        let logs = vec![create_entry("1"), create_entry("2")];
+        storage
+            .save_upload(alice, "device-1", request_id, &logs)
+            .unwrap();
+        storage
+            .save_upload(bob, "device-1", Uuid::new_v4(), &logs[..1])
+            .unwrap();
+
+        let expected = dir
+            .path()
+            .join(Utc::now().format("%Y/%m/%d").to_string())
+            .join("device-1")
+            .join(format!("{}.jsonl", request_id));
+        assert!(expected.is_file());
+
+        let read = storage.read_upload(alice, "device-1", request_id).unwrap();
+        assert_eq!(read.len(), 2);
+        assert!(matches!(
+            storage.read_upload(bob, "device-1", request_id),
+            Err(StorageError::NotFound)
+        ));
+
+        let uploads = storage.list_uploads(alice).unwrap();
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0].request_id, request_id.to_string());
+        assert_eq!(uploads[0].log_count, 2);
+        assert_eq!(storage.list_uploads(bob).unwrap().len(), 1);
+        assert_eq!(storage.upload_usage(alice).unwrap().file_count, 1);
+
+        storage
+            .delete_upload(alice, "device-1", request_id)
+            .unwrap();
+        assert!(storage.list_uploads(alice).unwrap().is_empty());
+        assert!(!expected.with_extension("owner").exists());
+    }
+
+    #[test]
+    fn test_list_uploads_between() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf()).unwrap();
+        let user_id = Uuid::new_v4();
+        let day = |d: u32| Utc.with_ymd_and_hms(2024, 3, d, 12, 0, 0).unwrap();
+
+        for d in [1, 10, 20] {
+            let request_id = Uuid::new_v4();
+            storage
+                .save_upload(user_id, "device-1", request_id, &[create_entry("1")])
+                .unwrap();
+
+            let path = dir.path().join(
+                storage
+                    .locate_upload(user_id, "device-1", request_id)
+                    .unwrap(),
+            );
+            File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(day(d).into())
+                .unwrap();
+        }
+
+        let count = |from, to| {
+            storage
+                .list_uploads_between(user_id, from, to)
+                .unwrap()
+                .len()
+        };
+        assert_eq!(count(None, None), 3);
+        assert_eq!(count(Some(day(5)), None), 2);
+        assert_eq!(count(None, Some(day(10))), 2);
+        assert_eq!(count(Some(day(5)), Some(day(15))), 1);
+        assert_eq!(count(Some(day(21)), None), 0);
+    }
+
+    #[test]
+    fn test_cleanup_by_count_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf()).unwrap();
+        let user_id = Uuid::new_v4();
+        let save = |device_id: &str, d: u32| {
+            let request_id = Uuid::new_v4();
+            storage
+                .save_upload(user_id, device_id, request_id, &[create_entry("1")])
+                .unwrap();
+
+            let path = dir.path().join(
+                storage
+                    .locate_upload(user_id, device_id, request_id)
+                    .unwrap(),
+            );
+            let modified = Utc.with_ymd_and_hms(2024, 3, d, 12, 0, 0).unwrap();
+            File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified.into())
+                .unwrap();
+            request_id.to_string()
+        };
+
+        // Saved out of order so the result can't follow creation order
+        let ids: Vec<String> = [3, 5, 1, 4, 2].map(|d| save("device-1", d)).into();
+        let other_device = save("device-2", 1);
+
+        assert_eq!(storage.cleanup_by_count(2).unwrap(), 3);
+
+        let mut kept: Vec<String> = storage
+            .list_uploads(user_id)
+            .unwrap()
+            .into_iter()
+            .map(|upload| upload.request_id)
+            .collect();
+        kept.sort();
+        let mut expected = vec![ids[1].clone(), ids[3].clone(), other_device];
+        expected.sort();
+        assert_eq!(kept, expected);
+    }
+
+    #[test]
+    fn test_delete_upload_then_read_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf())
+            .unwrap()
+            .with_split_threshold(Some(1));
+        let user_id = Uuid::new_v4();
+        let (kept, deleted) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let logs = [create_entry("1"), create_entry("2")];
+        for request_id in [kept, deleted] {
+            storage
+                .save_upload(user_id, "device-1", request_id, &logs)
+                .unwrap();
+        }
+
+        storage.delete_upload(user_id, "device-1", deleted).unwrap();
+
+        assert!(matches!(
+            storage.read_upload(user_id, "device-1", deleted),
+            Err(StorageError::NotFound)
+        ));
+        assert!(matches!(
+            storage.delete_upload(user_id, "device-1", deleted),
+            Err(StorageError::NotFound)
+        ));
+        assert_eq!(
+            storage
+                .read_upload(user_id, "device-1", kept)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(storage.disk_usage().unwrap().total.file_count, 2);
+    }
+
+    #[test]
+    fn test_disk_usage_totals() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LogStorage::new(dir.path().to_path_buf()).unwrap();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+        for (user_id, entries) in [(alice, 1), (alice, 3), (bob, 2)] {
+            let logs: Vec<LogEntry> = (0..entries).map(|i| create_entry(&i.to_string())).collect();
+            storage
+                .save_upload(user_id, "device-1", Uuid::new_v4(), &logs)
+                .unwrap();
+        }
+        let size_of = |user_id: Uuid| -> u64 {
+            storage
+                .list_uploads(user_id)
+                .unwrap()
+                .iter()
+                .map(|upload| upload.file_size_bytes)
+                .sum()
+        };
+
+        let usage = storage.disk_usage().unwrap();
+        assert_eq!(usage.total.file_count, 3);
+        assert_eq!(usage.total.total_bytes, size_of(alice) + size_of(bob));
+        assert_eq!(usage.per_user.len(), 2);
+        assert_eq!(
+            usage.per_user[&alice.to_string()],
+            UploadUsage {
+                total_bytes: size_of(alice),
+                file_count: 2,
+            }
+        );
+        assert_eq!(storage.upload_usage(bob).unwrap().file_count, 1);
+    }
+
+    #[test]
+    fn test_template_with_literals() {
+        let template = PathTemplate::parse("{user}/{yyyy}-{mm}/dev_{device}/{request}").unwrap();
+        let segment = &template.segments[2];
+
+        let captures = segment.matches("dev_abc-1", &HashMap::new()).unwrap();
+        assert_eq!(captures[&Placeholder::Device], "abc-1");
+        assert!(segment.matches("abc-1", &HashMap::new()).is_none());
+
+        let month = &template.segments[1];
+        let captures = month.matches("2024-01", &HashMap::new()).unwrap();
+        assert_eq!(captures[&Placeholder::Year], "2024");
+        assert_eq!(captures[&Placeholder::Month], "01");
+    }
+
+    #[test]
+    fn test_invalid_templates_rejected() {
+        assert!(PathTemplate::parse("{user}/{device}").is_err());
+        assert!(PathTemplate::parse("{device}").is_err());
+        assert!(PathTemplate::parse("{user}/{unknown}/{request}").is_err());
+        assert!(PathTemplate::parse("{user}/../{request}").is_err());
+        assert!(PathTemplate::parse("{user}/{device}{request}").is_err());
+    }
 }
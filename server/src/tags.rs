@@ -71,6 +71,7 @@ pub fn get_tag_color(tag: &str) -> &'static TagColor {
 }
 
 /// Get Tailwind CSS classes for a tag (combined bg + text).
+#[allow(dead_code)]
 pub fn get_tag_classes(tag: &str) -> String {
     let color = get_tag_color(tag);
     format!("{} {}", color.bg_class, color.text_class)
@@ -0,0 +1,246 @@
+//! In-memory LRU cache of parsed uploads.
+//!
+//! Repeated downloads of the same upload skip reading and parsing the file.
+//! Entries are checked against each file's modification time and size, so a
+//! rewritten upload is read again.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+
+use crate::models::LogEntry;
+
+/// Default number of log entries kept across all cached uploads
+pub const DEFAULT_MAX_CACHED_ENTRIES: usize = 100_000;
+
+/// Parsed uploads, bounded by their total number of log entries
+pub struct UploadCache {
+    inner: Mutex<CacheInner>,
+    max_entries: usize,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    /// Keyed by the files holding the upload (several for a split upload)
+    uploads: HashMap<Vec<PathBuf>, CachedUpload>,
+    /// Log entries held across all cached uploads
+    total_entries: usize,
+    /// Advances on every hit or insert to order uploads by last use
+    clock: u64,
+}
+
+struct CachedUpload {
+    stamps: Vec<FileStamp>,
+    logs: Arc<Vec<LogEntry>>,
+    last_used: u64,
+}
+
+/// What must stay the same for a cached upload to still be valid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
+impl UploadCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            inner: Mutex::new(CacheInner::default()),
+            max_entries,
+        }
+    }
+
+    /// Return the cached entries for `files`, or parse them with `read` and
+    /// cache the result
+    ///
+    /// Files are stamped before reading, so a write racing the read leaves a
+    /// stale stamp and the next call reads again. Uploads larger than the
+    /// whole cache are never cached.
+    pub fn read_through<E>(
+        &self,
+        files: &[PathBuf],
+        read: impl FnOnce() -> Result<Vec<LogEntry>, E>,
+    ) -> Result<Vec<LogEntry>, E> {
+        let stamps: Option<Vec<FileStamp>> = files.iter().map(|path| FileStamp::of(path)).collect();
+        let Some(stamps) = stamps else {
+            return read();
+        };
+
+        if let Some(logs) = self.get(files, &stamps) {
+            return Ok(logs.as_ref().clone());
+        }
+
+        let logs = read()?;
+        if logs.len() <= self.max_entries {
+            self.insert(files.to_vec(), stamps, Arc::new(logs.clone()));
+        }
+        Ok(logs)
+    }
+
+    fn get(&self, files: &[PathBuf], stamps: &[FileStamp]) -> Option<Arc<Vec<LogEntry>>> {
+        let mut inner = self.inner.lock();
+        inner.clock += 1;
+        let clock = inner.clock;
+
+        let cached = inner.uploads.get_mut(files)?;
+        if cached.stamps != stamps {
+            return None;
+        }
+        cached.last_used = clock;
+        Some(cached.logs.clone())
+    }
+
+    fn insert(&self, files: Vec<PathBuf>, stamps: Vec<FileStamp>, logs: Arc<Vec<LogEntry>>) {
+        let mut inner = self.inner.lock();
+        inner.clock += 1;
+
+        let added = logs.len();
+        let cached = CachedUpload {
+            stamps,
+            logs,
+            last_used: inner.clock,
+        };
+        if let Some(replaced) = inner.uploads.insert(files, cached) {
+            inner.total_entries -= replaced.logs.len();
+        }
+        inner.total_entries += added;
+
+        // Evict least recently used uploads until back within bounds
+        while inner.total_entries > self.max_entries {
+            let Some(oldest) = inner
+                .uploads
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(files, _)| files.clone())
+            else {
+                break;
+            };
+            if let Some(evicted) = inner.uploads.remove(&oldest) {
+                inner.total_entries -= evicted.logs.len();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::convert::Infallible;
+    use std::fs::File;
+    use std::time::Duration;
+
+    fn create_entry(id: &str) -> LogEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "timestamp": "2024-03-01T12:00:00Z",
+            "level": "info",
+            "message": "hello",
+            "deviceId": "device-1",
+        }))
+        .unwrap()
+    }
+
+    /// Read that records how often it ran
+    fn counting_read<'a>(
+        reads: &'a Cell<usize>,
+        ids: &'a [&str],
+    ) -> impl FnOnce() -> Result<Vec<LogEntry>, Infallible> + 'a {
+        move || {
+            reads.set(reads.get() + 1);
+            Ok(ids.iter().map(|id| create_entry(id)).collect())
+        }
+    }
+
+    #[test]
+    fn test_second_read_hits_cache_until_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upload.jsonl");
+        fs::write(&path, "{}\n").unwrap();
+        let files = vec![path.clone()];
+        let cache = UploadCache::new(100);
+        let reads = Cell::new(0);
+
+        let first = cache
+            .read_through(&files, counting_read(&reads, &["a", "b"]))
+            .unwrap();
+        let second = cache
+            .read_through(&files, counting_read(&reads, &["changed"]))
+            .unwrap();
+        assert_eq!(reads.get(), 1);
+        assert_eq!(first.len(), 2);
+        assert_eq!(second[0].id, "a");
+
+        // A newer modification time invalidates the cached entries
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified + Duration::from_secs(5))
+            .unwrap();
+        let third = cache
+            .read_through(&files, counting_read(&reads, &["changed"]))
+            .unwrap();
+        assert_eq!(reads.get(), 2);
+        assert_eq!(third[0].id, "changed");
+    }
+
+    #[test]
+    fn test_least_recently_used_upload_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let upload = |name: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, "{}\n").unwrap();
+            vec![path]
+        };
+        let (a, b, c) = (upload("a.jsonl"), upload("b.jsonl"), upload("c.jsonl"));
+        let cache = UploadCache::new(4);
+        let reads = Cell::new(0);
+
+        cache
+            .read_through(&a, counting_read(&reads, &["1", "2"]))
+            .unwrap();
+        cache
+            .read_through(&b, counting_read(&reads, &["1", "2"]))
+            .unwrap();
+        // Touch `a` so `b` is the least recently used
+        cache.read_through(&a, counting_read(&reads, &[])).unwrap();
+        cache
+            .read_through(&c, counting_read(&reads, &["1", "2"]))
+            .unwrap();
+        assert_eq!(reads.get(), 3);
+
+        cache.read_through(&a, counting_read(&reads, &[])).unwrap();
+        assert_eq!(reads.get(), 3);
+        cache
+            .read_through(&b, counting_read(&reads, &["1"]))
+            .unwrap();
+        assert_eq!(reads.get(), 4);
+
+        // Uploads bigger than the whole cache are read every time
+        let big = upload("big.jsonl");
+        let ids = ["1", "2", "3", "4", "5"];
+        cache
+            .read_through(&big, counting_read(&reads, &ids))
+            .unwrap();
+        cache
+            .read_through(&big, counting_read(&reads, &ids))
+            .unwrap();
+        assert_eq!(reads.get(), 6);
+    }
+}
@@ -345,6 +345,15 @@ function showCopyFeedback() {
     }
 }
 
+function showUploadToast(notice) {
+    const toast = document.getElementById('upload-toast');
+    if (!toast) return;
+    toast.textContent = `${notice.count} logs uploaded from ${notice.deviceId}`;
+    toast.classList.add('show');
+    clearTimeout(toast.hideTimer);
+    toast.hideTimer = setTimeout(() => toast.classList.remove('show'), 5000);
+}
+
 // Dropdown management
 function toggleDropdown(dropdownId) {
     const dropdown = document.getElementById(dropdownId);
@@ -376,6 +385,13 @@ function escapeHtml(text) {
     return div.innerHTML;
 }
 
+// Uppercase level name padded to a fixed width so badges line up
+const LEVEL_LABEL_WIDTH = 8;
+
+function formatLevelLabel(level) {
+    return level.toUpperCase().padEnd(LEVEL_LABEL_WIDTH);
+}
+
 function getLevelColor(level) {
     const colors = {
         trace: { dot: 'bg-gray-400', text: 'text-gray-600' },
@@ -444,6 +460,9 @@ function prependLogEntry(entry) {
     const tbody = document.getElementById('logs-tbody');
     if (!tbody) return;
 
+    // In dedup mode the server re-sends a collapsed entry with its new count
+    const replaced = removeLogRow(entry.id);
+
     const levelLower = entry.level.toLowerCase();
     const colors = getLevelColor(entry.level);
     const timeShort = formatTimeShort(entry.timestamp);
@@ -486,7 +505,7 @@ function prependLogEntry(entry) {
         <td class="px-3 py-2 tags-cell-level" data-column="level">
             <span class="inline-flex items-center gap-1 text-xs font-medium ${colors.text}">
                 <span class="w-2 h-2 rounded-full flex-shrink-0 ${colors.dot}"></span>
-                ${entry.level.toUpperCase()}
+                <span class="whitespace-pre font-mono">${escapeHtml(formatLevelLabel(entry.level))}</span>
             </span>
         </td>
         <td class="px-3 py-2 text-xs text-gray-500 font-mono whitespace-nowrap" data-column="time">${timeShort}</td>
@@ -570,7 +589,7 @@ function prependLogEntry(entry) {
         tbody.appendChild(detailTr);
     }
 
-    updateStatistics(entry);
+    if (!replaced) updateStatistics(entry);
     applyAllFilters();
 
     if (document.getElementById('autoscroll')?.checked) {
@@ -578,6 +597,15 @@ function prependLogEntry(entry) {
     }
 }
 
+// Remove an entry's row and detail row, returning whether it was shown
+function removeLogRow(id) {
+    const row = document.querySelector(`.log-row[data-id="${id}"]`);
+    if (!row) return false;
+    row.remove();
+    document.getElementById(`detail-${id}`)?.remove();
+    return true;
+}
+
 // Update source dropdown with new source if needed
 function updateSourceDropdown(source) {
     const select = document.getElementById('source-select');
@@ -705,12 +733,39 @@ function initializeSSE() {
         }
     });
 
+    eventSource.addEventListener('clear', function() {
+        const tbody = document.getElementById('logs-tbody');
+        if (tbody) tbody.innerHTML = '';
+    });
+
+    eventSource.addEventListener('upload', function(event) {
+        try {
+            showUploadToast(JSON.parse(event.data));
+        } catch (e) {
+            console.error('Failed to parse upload notice:', e);
+        }
+    });
+
+    // The server's clock is only roughly in sync, so this is an estimate
+    eventSource.addEventListener('heartbeat', function(event) {
+        try {
+            const sentAt = Date.parse(JSON.parse(event.data).timestamp);
+            const latency = Math.max(0, Date.now() - sentAt);
+            if (statusEl) statusEl.title = `Connected (latency ~${latency} ms)`;
+        } catch (e) {
+            console.error('Failed to parse heartbeat:', e);
+        }
+    });
+
     eventSource.onopen = () => {
         if (statusEl) statusEl.className = 'w-2 h-2 rounded-full bg-green-500';
     };
 
+    // EventSource reconnects on its own using the server's `retry:` hint;
+    // only rebuild the connection if the browser gave up entirely.
     eventSource.onerror = () => {
         if (statusEl) statusEl.className = 'w-2 h-2 rounded-full bg-red-500';
+        if (eventSource.readyState !== EventSource.CLOSED) return;
         setTimeout(() => {
             if (document.getElementById('live-stream')?.checked) initializeSSE();
         }, 5000);
@@ -33,7 +33,12 @@ export function Dashboard() {
   }, [])
 
   // SSE stream
-  const connectionStatus = useLogStream(prefs.liveStream, store.appendEntry)
+  const connectionStatus = useLogStream(
+    prefs.liveStream,
+    store.appendEntry,
+    store.clearEntries,
+    (notice) => toast.info(`${notice.count} logs uploaded from ${notice.deviceId}`),
+  )
 
   // Keep tag prefs in sync: auto-select new tags as they appear
   useEffect(() => {
@@ -19,12 +19,20 @@ const LEVEL_STYLES: Record<string, { dot: string; text: string }> = {
   },
 }
 
+// Wide enough for the longest level name (CRITICAL)
+const LEVEL_LABEL_WIDTH = 8
+
+/** Uppercase level name padded to a fixed width so badges line up */
+export function formatLevelLabel(level: string) {
+  return level.toUpperCase().padEnd(LEVEL_LABEL_WIDTH)
+}
+
 export function LevelBadge({ level }: { level: string }) {
   const s = LEVEL_STYLES[level.toLowerCase()] ?? LEVEL_STYLES.info
   return (
     <span className={cn("inline-flex items-center gap-1.5 text-xs font-medium", s.text)}>
       <span className={cn("h-2 w-2 shrink-0 rounded-full", s.dot)} />
-      {level.toUpperCase()}
+      <span className="whitespace-pre font-mono">{formatLevelLabel(level)}</span>
     </span>
   )
 }
@@ -20,6 +20,17 @@ const ThemeContext = createContext<ThemeContextValue>({
   resolved: "light",
 })
 
+// ?theme=light|dark overrides the stored choice for this page load
+function initialTheme(): Theme {
+  const param = new URLSearchParams(window.location.search).get("theme")
+  if (param === "light" || param === "dark") return param
+  try {
+    return (localStorage.getItem("theme") as Theme) ?? "system"
+  } catch {
+    return "system"
+  }
+}
+
 function resolveTheme(theme: Theme): "light" | "dark" {
   if (theme !== "system") return theme
   return window.matchMedia("(prefers-color-scheme: dark)").matches
@@ -28,13 +39,7 @@ function resolveTheme(theme: Theme): "light" | "dark" {
 }
 
 export function ThemeProvider({ children }: { children: React.ReactNode }) {
-  const [theme, setThemeState] = useState<Theme>(() => {
-    try {
-      return (localStorage.getItem("theme") as Theme) ?? "system"
-    } catch {
-      return "system"
-    }
-  })
+  const [theme, setThemeState] = useState<Theme>(initialTheme)
   const [resolved, setResolved] = useState<"light" | "dark">(() =>
     resolveTheme(theme),
   )
@@ -38,6 +38,16 @@ function reducer(state: LogState, action: Action): LogState {
       return { entries, ...deriveStats(entries) }
     }
     case "APPEND": {
+      // In dedup mode the server re-sends a collapsed entry with its new count
+      const existing = state.entries.findIndex((e) => e.id === action.entry.id)
+      if (existing !== -1) {
+        const entries = [
+          ...state.entries.slice(0, existing),
+          ...state.entries.slice(existing + 1),
+          action.entry,
+        ]
+        return { ...state, entries }
+      }
       const entries =
         state.entries.length >= MAX_ENTRIES
           ? [...state.entries.slice(1), action.entry]
@@ -3,13 +3,26 @@ import type { LogEntry } from "@/types/log-entry"
 
 export type ConnectionStatus = "disconnected" | "connecting" | "connected"
 
+/** Sent when a device's upload for a log request has been stored */
+export interface UploadNotice {
+  requestId: string
+  deviceId: string
+  count: number
+}
+
 export function useLogStream(
   enabled: boolean,
   onEntry: (entry: LogEntry) => void,
+  onClear: () => void,
+  onUpload: (notice: UploadNotice) => void,
 ) {
   const [status, setStatus] = useState<ConnectionStatus>("disconnected")
   const onEntryRef = useRef(onEntry)
   onEntryRef.current = onEntry
+  const onClearRef = useRef(onClear)
+  onClearRef.current = onClear
+  const onUploadRef = useRef(onUpload)
+  onUploadRef.current = onUpload
 
   useEffect(() => {
     if (!enabled) {
@@ -28,6 +41,17 @@ export function useLogStream(
       }
     })
 
+    // Buffer was cleared on the server (e.g. DELETE /logs from another client)
+    es.addEventListener("clear", () => onClearRef.current())
+
+    es.addEventListener("upload", (event) => {
+      try {
+        onUploadRef.current(JSON.parse(event.data))
+      } catch {
+        // ignore parse errors
+      }
+    })
+
     es.onopen = () => setStatus("connected")
 
     es.onerror = () => {